@@ -0,0 +1,368 @@
+//! Merkle tree building and proofs for the airdrop whitelists.  Both the
+//! contract and the merkle_tree tool use this crate, so the proofs the tool
+//! gives are always the ones the contract checks.
+#![no_std]
+
+extern crate alloc;
+
+use alloc::{format, string::String, vec::Vec};
+use sha2::{Digest, Sha256};
+use sha3::Sha3_256;
+use tiny_keccak::{Hasher, Keccak};
+
+// Basic merkle tree implementation
+// This will produce merkle trees like the following (note the real values would be hashed)
+// Example 1 - input 1,2,3
+//  1    2    3   3
+//    12        33
+//        1233
+//
+// Example 2 - input 1,2,3,4,5,6
+// 1   2   3   4   5    6
+//  12       34      56    56
+//      1234           5656
+//           12345656
+//
+// The tree is stored as one vector of nodes, level by level from the leaves
+// up, with each odd level made even by repeating its last node.
+
+/// Prefix of hashed leaves.  Leaves and internal nodes are hashed with
+/// different prefixes so one can never be passed off as the other.
+pub const LEAF_PREFIX: u8 = 0x00;
+
+/// Prefix of hashed internal nodes.
+pub const NODE_PREFIX: u8 = 0x01;
+
+/// Hash function the tree is built with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MerkleHash {
+    Sha256,
+    Keccak256,
+    Sha3_256,
+}
+
+impl MerkleHash {
+    /// Hashes some bytes, giving the lowercase hex string the tree is built from.
+    pub fn digest(self, input: &[u8]) -> String {
+        match self {
+            MerkleHash::Sha256 => encode_hex(&Sha256::digest(input)),
+            MerkleHash::Sha3_256 => encode_hex(&Sha3_256::digest(input)),
+            MerkleHash::Keccak256 => {
+                let mut hasher = Keccak::v256();
+                let mut output = [0u8; 32];
+                hasher.update(input);
+                hasher.finalize(&mut output);
+                encode_hex(&output)
+            }
+        }
+    }
+
+    /// Hashes an encoded whitelist entry into a leaf of the tree.
+    pub fn hash_leaf(self, node: impl AsRef<[u8]>) -> String {
+        self.digest(&leaf_input(node.as_ref()))
+    }
+}
+
+/// How accounts are written in the leaves.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LeafEncoding {
+    /// The whitelist entry as given
+    NodeString,
+    /// The 32 bytes of the account address
+    Raw,
+    /// The account address as lowercase hex
+    Hex,
+    /// The base58check address wallets show, such as `3XSLuJcX...`
+    Base58Check,
+}
+
+impl LeafEncoding {
+    /// Encodes a whitelist entry.  Raw entries are given as hex and decoded.
+    /// Entries with an allocation keep their `:max_claims` suffix as text.
+    pub fn entry_leaf(self, entry: &str) -> Vec<u8> {
+        match self {
+            LeafEncoding::NodeString | LeafEncoding::Base58Check => entry.as_bytes().to_vec(),
+            LeafEncoding::Hex => entry.to_lowercase().into_bytes(),
+            LeafEncoding::Raw => {
+                let (address, allocation) = match entry.split_once(':') {
+                    Some((address, max_claims)) => (address, format!(":{}", max_claims)),
+                    None => (entry, String::new()),
+                };
+                match decode_hex(address).filter(|bytes| bytes.len() == 32) {
+                    Some(mut bytes) => {
+                        bytes.extend_from_slice(allocation.as_bytes());
+                        bytes
+                    }
+                    None => entry.as_bytes().to_vec(),
+                }
+            }
+        }
+    }
+}
+
+/// Writes bytes as lowercase hex.
+pub fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Decodes a hex string, or returns None if it is not valid hex.
+pub fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(hex.get(index..index + 2)?, 16).ok())
+        .collect()
+}
+
+/// The bytes hashed into the leaf of an encoded whitelist entry.
+pub fn leaf_input(node: &[u8]) -> Vec<u8> {
+    [&[LEAF_PREFIX], node].concat()
+}
+
+/// The bytes hashed into the parent of two sibling nodes.  With sorted pairs
+/// the smallest hash goes first, as OpenZeppelin style trees do.
+pub fn node_input(left: &str, right: &str, sorted: bool) -> Vec<u8> {
+    let (first, second) = if sorted && right < left {
+        (right, left)
+    } else {
+        (left, right)
+    };
+    [&[NODE_PREFIX], first.as_bytes(), second.as_bytes()].concat()
+}
+
+/// Hashes two sibling nodes into their parent.
+pub fn hash_pair(left: &str, right: &str, sorted: bool, hash: MerkleHash) -> String {
+    hash.digest(&node_input(left, right, sorted))
+}
+
+/// Hashes each pair of nodes of an even level into the level above.
+pub fn hash_level(level: &[String], sorted_pairs: bool, hash: MerkleHash) -> Vec<String> {
+    level
+        .chunks(2)
+        .map(|pair| hash_pair(&pair[0], &pair[1], sorted_pairs, hash))
+        .collect()
+}
+
+/// Number of nodes stored for a level above the leaves, counting the node
+/// repeated to make it even.  The root is stored on its own.
+pub fn level_step(size: usize) -> usize {
+    if size == 1 {
+        1
+    } else {
+        size + size % 2
+    }
+}
+
+/// A tree with its levels laid out one after the other in `hash_tree`.
+/// `length` is the number of leaves stored, and `steps` the number of nodes
+/// stored for each level above them.  The root is the last node.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FlatTree {
+    pub hash_tree: Vec<String>,
+    pub length: usize,
+    pub steps: Vec<usize>,
+}
+
+impl FlatTree {
+    /// Builds the tree over hashed leaves, hashing each level into the one
+    /// above with `hash_level`.  Returns None if there are no leaves.
+    pub fn build(
+        leaves: Vec<String>,
+        hash_level: impl Fn(&[String]) -> Vec<String>,
+    ) -> Option<Self> {
+        let mut hash_tree = leaves;
+        let last = hash_tree.last()?.clone();
+        if hash_tree.len() % 2 == 1 {
+            hash_tree.push(last);
+        }
+        let length = hash_tree.len();
+        let mut steps: Vec<usize> = Vec::new();
+        let mut start = 0;
+        loop {
+            let level = hash_level(&hash_tree[start..]);
+            start = hash_tree.len();
+            steps.push(level_step(level.len()));
+            hash_tree.extend(level);
+            if hash_tree.len() - start == 1 {
+                return Some(FlatTree {
+                    hash_tree,
+                    length,
+                    steps,
+                });
+            }
+            // make sure the level is even
+            if (hash_tree.len() - start) % 2 == 1 {
+                hash_tree.push(hash_tree[hash_tree.len() - 1].clone());
+            }
+        }
+    }
+
+    /// Gives the chain of nodes from a hashed leaf up to the root, which is
+    /// the proof claimers pass.  Returns None if the leaf is not in the tree.
+    pub fn path(&self, leaf: String, sorted_pairs: bool, hash: MerkleHash) -> Option<Vec<String>> {
+        path(
+            &self.hash_tree,
+            self.length,
+            &self.steps,
+            leaf,
+            sorted_pairs,
+            hash,
+        )
+    }
+}
+
+/// Gives the chain of nodes from a hashed leaf up to the root of a tree laid
+/// out as in `FlatTree`, whatever integer type its steps are stored as.
+/// Returns None if the leaf is not in the tree.
+pub fn path<S: Copy + TryInto<usize>>(
+    hash_tree: &[String],
+    length: usize,
+    steps: &[S],
+    leaf: String,
+    sorted_pairs: bool,
+    hash: MerkleHash,
+) -> Option<Vec<String>> {
+    path_with(hash_tree, length, steps, leaf, sorted_pairs, |input| {
+        hash.digest(input)
+    })
+}
+
+/// Like `path`, with the parents hashed by `digest` into lowercase hex.  The
+/// contract passes its host's crypto primitives here.
+pub fn path_with<S: Copy + TryInto<usize>>(
+    hash_tree: &[String],
+    length: usize,
+    steps: &[S],
+    leaf: String,
+    sorted_pairs: bool,
+    digest: impl Fn(&[u8]) -> String,
+) -> Option<Vec<String>> {
+    let root = hash_tree.last()?;
+    let mut steps = steps.iter();
+    let mut start = 0;
+    let mut end = length;
+    let mut hunted = leaf;
+    let mut path: Vec<String> = Vec::new();
+    loop {
+        if hunted == *root {
+            path.push(hunted);
+            return Some(path);
+        }
+        let index = hash_tree
+            .get(start..end)?
+            .iter()
+            .position(|node| *node == hunted)?;
+        let node = start + index;
+        let parent = if index % 2 == 1 {
+            // it is on the right hand side
+            digest(&node_input(&hash_tree[node - 1], &hunted, sorted_pairs))
+        } else {
+            // it is on the left hand side
+            digest(&node_input(&hunted, hash_tree.get(node + 1)?, sorted_pairs))
+        };
+        path.push(hunted);
+        hunted = parent;
+        start = end;
+        end += (*steps.next()?).try_into().ok()?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    fn tree(count: usize, sorted_pairs: bool) -> (Vec<String>, FlatTree) {
+        let leaves: Vec<String> = (0..count)
+            .map(|i| MerkleHash::Sha256.hash_leaf(format!("address{}", i)))
+            .collect();
+        let tree = FlatTree::build(leaves.clone(), |level| {
+            hash_level(level, sorted_pairs, MerkleHash::Sha256)
+        })
+        .unwrap();
+        (leaves, tree)
+    }
+
+    #[test]
+    fn test_digest() {
+        assert_eq!(
+            MerkleHash::Sha256.digest(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            MerkleHash::Keccak256.digest(b""),
+            "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+        );
+        assert_eq!(
+            MerkleHash::Sha3_256.digest(b""),
+            "a7ffc6f8bf1ed76651c14756a061d662f580ff4de43b49fa82d80a4b80f8434a"
+        );
+        assert_eq!(decode_hex("00ff"), Some(vec![0, 255]));
+        assert_eq!(decode_hex("0"), None);
+    }
+
+    #[test]
+    fn test_build() {
+        let (leaves, tree) = tree(3, false);
+        let left = hash_pair(&leaves[0], &leaves[1], false, MerkleHash::Sha256);
+        let right = hash_pair(&leaves[2], &leaves[2], false, MerkleHash::Sha256);
+        let root = hash_pair(&left, &right, false, MerkleHash::Sha256);
+        assert_eq!(tree.length, 4);
+        assert_eq!(tree.hash_tree.last(), Some(&root));
+        assert_eq!(
+            tree.path(leaves[2].clone(), false, MerkleHash::Sha256),
+            Some(vec![leaves[2].clone(), right, root])
+        );
+        assert!(FlatTree::build(vec![], |level| level.to_vec()).is_none());
+    }
+
+    #[test]
+    fn test_every_leaf_has_a_path() {
+        // Odd levels of five or more nodes used to throw the walk off by one
+        for count in 1..=40 {
+            for sorted_pairs in [false, true] {
+                let (leaves, tree) = tree(count, sorted_pairs);
+                for leaf in &leaves {
+                    let proof = tree
+                        .path(leaf.clone(), sorted_pairs, MerkleHash::Sha256)
+                        .unwrap();
+                    assert_eq!(proof.len(), tree.steps.len() + 1);
+                    assert_eq!(proof.last(), tree.hash_tree.last());
+                }
+                let missing = MerkleHash::Sha256.hash_leaf("missing");
+                assert_eq!(tree.path(missing, sorted_pairs, MerkleHash::Sha256), None);
+            }
+        }
+    }
+
+    #[test]
+    fn test_narrow_steps() {
+        let (leaves, tree) = tree(9, false);
+        let steps: Vec<u8> = tree.steps.iter().map(|step| *step as u8).collect();
+        assert_eq!(
+            path(
+                &tree.hash_tree,
+                tree.length,
+                &steps,
+                leaves[8].clone(),
+                false,
+                MerkleHash::Sha256
+            ),
+            tree.path(leaves[8].clone(), false, MerkleHash::Sha256)
+        );
+        let steps: Vec<u32> = tree.steps.iter().map(|step| *step as u32).collect();
+        assert_eq!(
+            path(
+                &tree.hash_tree,
+                tree.length,
+                &steps,
+                leaves[8].clone(),
+                false,
+                MerkleHash::Sha256
+            ),
+            tree.path(leaves[8].clone(), false, MerkleHash::Sha256)
+        );
+    }
+}
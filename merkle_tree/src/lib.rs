@@ -1,176 +1,980 @@
-use concordium_cis2::*;
-use concordium_std::*;
+use js_sys::{JsString, Uint8Array};
+use merkle_core::{decode_hex, FlatTree};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use wasm_bindgen::prelude::*;
-use sha256::digest;
-use js_sys::*;
-
-// Basic merkle tree implementation
-// This will produce merkle trees like the following (note the real values would be hashed)
-// Example 1 - input 1,2,3
-//  1    2    3   3
-//    12        33
-//        1233
-//
-// Example 2 - input 1,2,3,4,5,6
-// 1   2   3   4   5    6
-//  12       34      56    56
-//      1234           5656
-//           12345656
-
-type ContractTokenId = TokenIdU32;
-
-#[derive(Serial, Deserial, SchemaType, Clone)]
+
+// Merkle trees for dApp frontends to generate the proofs passed to claim_nft.
+// The trees are built with merkle_core, as the airdrop contract's are, so the
+// proofs always match the ones the contract checks.
+
 #[wasm_bindgen]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct MerkleTree {
-    length: u8,
+    length: usize,
+    leaves: usize,
     hash_tree: Vec<String>,
     hashroot: String,
-    steps: Vec<u8>,
+    steps: Vec<usize>,
+    sorted_pairs: bool,
+    hash: MerkleHash,
+    encoding: LeafEncoding,
 }
 
-#[derive(Debug, Serialize, SchemaType)]
+/// Hash function the tree is built with.  This must match the contract's merkle_hash.
 #[wasm_bindgen]
-pub struct ClaimNFTParams {
-    proof: Vec<String>,
-    node: String,
-    selected_token: ContractTokenId,
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MerkleHash {
+    Sha256,
+    Keccak256,
+    Sha3_256,
 }
 
+/// How accounts are written in the leaves.  This must match the contract's leaf_encoding.
 #[wasm_bindgen]
-pub fn create_hash_tree(nodes: Vec<JsString>) ->Option<MerkleTree> {
-    let mut working_vec: Vec<String> = vec![];
-    for node in nodes {
-        working_vec.push(digest(node.as_string().unwrap()));
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LeafEncoding {
+    NodeString,
+    Raw,
+    Hex,
+    Base58Check,
+}
+
+impl LeafEncoding {
+    /// Encodes a whitelist entry the way the contract does.  Raw entries are
+    /// given as hex and decoded.  Entries with an allocation keep their
+    /// `:max_claims` suffix as text.
+    pub fn entry_leaf(self, entry: &str) -> Vec<u8> {
+        merkle_core::LeafEncoding::from(self).entry_leaf(entry)
+    }
+}
+
+impl From<LeafEncoding> for merkle_core::LeafEncoding {
+    fn from(encoding: LeafEncoding) -> Self {
+        match encoding {
+            LeafEncoding::NodeString => merkle_core::LeafEncoding::NodeString,
+            LeafEncoding::Raw => merkle_core::LeafEncoding::Raw,
+            LeafEncoding::Hex => merkle_core::LeafEncoding::Hex,
+            LeafEncoding::Base58Check => merkle_core::LeafEncoding::Base58Check,
+        }
+    }
+}
+
+/// Alphabet of the base58 encoding used for Concordium account addresses.
+const BASE58_ALPHABET: &[u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// Decodes a base58check Concordium account address, such as `3XSLuJcX...`,
+/// into its 32 bytes.  Returns None if it is not a valid account address.
+pub fn decode_base58_address(address: &str) -> Option<[u8; 32]> {
+    // Bytes of the payload, least significant first
+    let mut bytes: Vec<u8> = Vec::new();
+    for character in address.bytes() {
+        let mut carry = BASE58_ALPHABET
+            .iter()
+            .position(|digit| *digit == character)? as u32;
+        for byte in bytes.iter_mut() {
+            carry += u32::from(*byte) * 58;
+            *byte = (carry & 0xff) as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.push((carry & 0xff) as u8);
+            carry >>= 8;
+        }
+    }
+    let zeros = address
+        .bytes()
+        .take_while(|character| *character == b'1')
+        .count();
+    bytes.extend(std::iter::repeat_n(0, zeros));
+    bytes.reverse();
+
+    // A version byte of 1, the address and the first four bytes of the
+    // double SHA256 hash of the two
+    if bytes.len() != 37 || bytes[0] != 1 {
+        return None;
+    }
+    let hash = decode_hex(&MerkleHash::Sha256.digest(&bytes[..33]))?;
+    let checksum = decode_hex(&MerkleHash::Sha256.digest(&hash))?;
+    if checksum[..4] != bytes[33..] {
+        return None;
     }
-    let mut working_node_total: usize = working_vec.len();
-    let mut steps: Vec<u8> = Vec::new();
+    bytes[1..33].try_into().ok()
+}
+
+/// Converts a base58 account address to the lowercase hex the contract writes
+/// accounts as.  Anything else, such as a hex address or a contract, is returned as is.
+pub fn normalize_address(address: &str) -> String {
+    match decode_base58_address(address) {
+        Some(bytes) => bytes.iter().map(|byte| format!("{:02x}", byte)).collect(),
+        None => address.to_string(),
+    }
+}
+
+/// Reads the addresses of a whitelist file with one address per line.  For CSV
+/// files the address is taken from the first column and a header line
+/// starting with "address" is skipped.
+pub fn parse_whitelist(contents: &str) -> Vec<String> {
+    contents
+        .lines()
+        .filter_map(|line| line.split(',').next())
+        .map(|address| address.trim().trim_matches('"'))
+        .filter(|address| !address.is_empty() && !address.eq_ignore_ascii_case("address"))
+        .map(String::from)
+        .collect()
+}
+
+/// Converts the address of a whitelist entry for the leaf encoding, keeping
+/// any allocation.
+pub fn normalize_entry(entry: &str, encoding: LeafEncoding) -> String {
+    if encoding == LeafEncoding::Base58Check {
+        return entry.to_string();
+    }
+    match entry.split_once(':') {
+        Some((address, max_claims)) => format!("{}:{}", normalize_address(address), max_claims),
+        None => normalize_address(entry),
+    }
+}
+
+impl From<MerkleHash> for merkle_core::MerkleHash {
+    fn from(hash: MerkleHash) -> Self {
+        match hash {
+            MerkleHash::Sha256 => merkle_core::MerkleHash::Sha256,
+            MerkleHash::Keccak256 => merkle_core::MerkleHash::Keccak256,
+            MerkleHash::Sha3_256 => merkle_core::MerkleHash::Sha3_256,
+        }
+    }
+}
+
+impl MerkleHash {
+    /// Hashes some bytes, giving the lowercase hex string the tree is built from.
+    pub fn digest(self, input: &[u8]) -> String {
+        merkle_core::MerkleHash::from(self).digest(input)
+    }
+
+    /// Hashes an encoded whitelist entry into a leaf of the tree.
+    pub fn hash_leaf(self, node: impl AsRef<[u8]>) -> String {
+        merkle_core::MerkleHash::from(self).hash_leaf(node)
+    }
+}
+
+/// Hashes two sibling nodes into their parent.  With sorted pairs the smallest
+/// hash goes first, as OpenZeppelin style trees do.
+fn hash_pair(left: &str, right: &str, sorted: bool, hash: MerkleHash) -> String {
+    merkle_core::hash_pair(left, right, sorted, hash.into())
+}
+
+/// Hashes the whitelist entries into the leaves of the tree.
+#[cfg(not(all(feature = "parallel", not(target_arch = "wasm32"))))]
+fn hash_leaves(nodes: &[String], hash: MerkleHash, encoding: LeafEncoding) -> Vec<String> {
+    nodes
+        .iter()
+        .map(|node| hash.hash_leaf(encoding.entry_leaf(node)))
+        .collect()
+}
+
+/// Hashes the whitelist entries into the leaves of the tree, spread over all
+/// cores with the `parallel` feature.
+#[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+fn hash_leaves(nodes: &[String], hash: MerkleHash, encoding: LeafEncoding) -> Vec<String> {
+    use rayon::prelude::*;
+    nodes
+        .par_iter()
+        .map(|node| hash.hash_leaf(encoding.entry_leaf(node)))
+        .collect()
+}
+
+/// Hashes each pair of nodes of an even level into the level above.
+#[cfg(not(all(feature = "parallel", not(target_arch = "wasm32"))))]
+fn hash_level(level: &[String], sorted_pairs: bool, hash: MerkleHash) -> Vec<String> {
+    merkle_core::hash_level(level, sorted_pairs, hash.into())
+}
+
+/// Hashes each pair of nodes of an even level into the level above, spread
+/// over all cores with the `parallel` feature.
+#[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+fn hash_level(level: &[String], sorted_pairs: bool, hash: MerkleHash) -> Vec<String> {
+    use rayon::prelude::*;
+    level
+        .par_chunks(2)
+        .map(|pair| hash_pair(&pair[0], &pair[1], sorted_pairs, hash))
+        .collect()
+}
 
-    if working_vec.len() % 2 == 1 {
-        working_vec.push(working_vec[working_node_total - 1].clone());
-        working_node_total += 1;
+impl MerkleTree {
+    /// Builds the tree from the whitelisted addresses, as the contract does at init.
+    /// Returns None if there are no addresses.  sorted_pairs and hash must match the
+    /// contract's init settings.
+    pub fn new(nodes: &[String], sorted_pairs: bool, hash: MerkleHash) -> Option<Self> {
+        MerkleTree::with_encoding(nodes, sorted_pairs, hash, LeafEncoding::NodeString)
     }
 
-    let initial_length = working_node_total;
-    let mut startpoint = 0;
-    let mut vec_to_add: Vec<String> = Vec::new();
+    /// Builds the tree for a contract initialised with the given leaf_encoding.
+    pub fn with_encoding(
+        nodes: &[String],
+        sorted_pairs: bool,
+        hash: MerkleHash,
+        encoding: LeafEncoding,
+    ) -> Option<Self> {
+        let tree = FlatTree::build(hash_leaves(nodes, hash, encoding), |level| {
+            hash_level(level, sorted_pairs, hash)
+        })?;
+        Some(MerkleTree {
+            length: tree.length,
+            leaves: nodes.len(),
+            hashroot: tree.hash_tree.last()?.clone(),
+            steps: tree.steps,
+            hash_tree: tree.hash_tree,
+            sorted_pairs,
+            hash,
+            encoding,
+        })
+    }
 
-    loop {
-        // make sure tree is even
-        if working_node_total % 2 == 1 {
-            working_vec.push(working_vec.last().unwrap().clone());
-        }   
+    /// Splits the tree into its levels, from the leaves up to the root, without
+    /// the nodes copied to make a level even.
+    fn levels(&self) -> Vec<Vec<String>> {
+        let mut levels: Vec<Vec<String>> = Vec::new();
+        let mut start = 0;
+        let mut size = self.leaves;
+        loop {
+            levels.push(self.hash_tree[start..start + size].to_vec());
+            if size == 1 && levels.len() > 1 {
+                return levels;
+            }
+            start += size + size % 2;
+            size = size.div_ceil(2);
+        }
+    }
 
-        for index in (startpoint..working_vec.len()).step_by(2) {
-            vec_to_add.push(digest(working_vec[index].clone() + &working_vec[index + 1]));
+    /// Appends entries to the end of the whitelist.  The nodes left of the first
+    /// new leaf are kept on every level and only the right edge of the tree is
+    /// hashed again, so a growing whitelist doesn't need a full rebuild.  Gives
+    /// the same tree as building it from the whole whitelist, and returns the
+    /// new root to pass to set_whitelist.
+    pub fn append(&mut self, nodes: &[String]) -> String {
+        if nodes.is_empty() {
+            return self.hashroot.clone();
         }
+        let old_levels = self.levels();
+        let mut level = old_levels[0].clone();
+        level.extend(hash_leaves(nodes, self.hash, self.encoding));
+        let mut changed = self.leaves;
+        let mut levels: Vec<Vec<String>> = Vec::new();
+        let mut steps: Vec<usize> = Vec::new();
+        loop {
+            if level.len() % 2 == 1 {
+                level.push(level[level.len() - 1].clone());
+            }
+            // Parents of the unchanged pairs are kept from the old tree
+            changed /= 2;
+            let mut next: Vec<String> = old_levels
+                .get(levels.len() + 1)
+                .map(|old| old[..changed].to_vec())
+                .unwrap_or_default();
+            let start = 2 * next.len();
+            next.extend(hash_level(&level[start..], self.sorted_pairs, self.hash));
+            levels.push(level);
+            steps.push(merkle_core::level_step(next.len()));
+            if next.len() == 1 {
+                levels.push(next);
+                break;
+            }
+            level = next;
+        }
+
+        self.leaves += nodes.len();
+        self.length = levels[0].len();
+        self.steps = steps;
+        self.hash_tree = levels.concat();
+        self.hashroot = self.hash_tree.last().unwrap().clone();
+        self.hashroot.clone()
+    }
+
+    /// Returns the proof the contract expects in ClaimNFTParams for an address.
+    /// Returns None if the address is not in the tree.
+    pub fn proof(&self, leaf: &str) -> Option<Vec<String>> {
+        merkle_core::path(
+            &self.hash_tree,
+            self.length,
+            &self.steps,
+            self.hash.hash_leaf(self.encoding.entry_leaf(leaf)),
+            self.sorted_pairs,
+            self.hash.into(),
+        )
+    }
 
-        startpoint = working_vec.len();
-        working_vec.append(&mut vec_to_add.clone());
-        working_node_total = working_vec.len();
+    /// Returns one proof covering a batch of entries, or None if any of them is
+    /// not in the tree.  Nodes shared by the paths of several entries are only
+    /// given once, and nodes which can be hashed from the entries are left out.
+    pub fn multiproof(&self, leaves: &[String]) -> Option<MultiProof> {
+        let levels = self.levels();
+        let indices = leaves
+            .iter()
+            .map(|leaf| {
+                let leaf = self.hash.hash_leaf(self.encoding.entry_leaf(leaf));
+                levels[0].iter().position(|node| *node == leaf)
+            })
+            .collect::<Option<Vec<usize>>>()?;
 
-        if (vec_to_add.len()) / 2 == 1 {
-            steps.push((vec_to_add.len() + 1).try_into().unwrap());
-        } else {
-            steps.push((vec_to_add.len()).try_into().unwrap());
+        let mut known: Vec<usize> = indices.clone();
+        known.sort_unstable();
+        known.dedup();
+        let mut hashes: Vec<String> = Vec::new();
+        for level in &levels[..levels.len() - 1] {
+            for &index in &known {
+                let sibling = index ^ 1;
+                // a missing right sibling is the node itself
+                if sibling < level.len() && known.binary_search(&sibling).is_err() {
+                    hashes.push(level[sibling].clone());
+                }
+            }
+            known = known.iter().map(|index| index / 2).collect();
+            known.dedup();
         }
+        Some(MultiProof {
+            leaf_count: self.leaves,
+            indices,
+            hashes,
+        })
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl MerkleTree {
+    /// Writes the tree to a JSON file, so it can be loaded again without
+    /// rebuilding it from the whitelist.
+    pub fn save(&self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        let file = std::fs::File::create(path)?;
+        serde_json::to_writer(std::io::BufWriter::new(file), self)?;
+        Ok(())
+    }
+
+    /// Reads a tree written by `save`.
+    pub fn load(path: impl AsRef<std::path::Path>) -> std::io::Result<Self> {
+        let file = std::fs::File::open(path)?;
+        Ok(serde_json::from_reader(std::io::BufReader::new(file))?)
+    }
+}
 
-        if vec_to_add.len() == 1 {
-            return Some(MerkleTree {
-                length: initial_length as u8,
-                hashroot: working_vec.last().unwrap().clone(),
-                steps,
-                hash_tree: working_vec.clone(),
-            });
+/// File input and output for backend services, which use the tree without
+/// the browser bindings.  Enabled with the `native` feature.
+#[cfg(all(feature = "native", not(target_arch = "wasm32")))]
+impl MerkleTree {
+    /// Builds the tree from a whitelist file, read as the command line tool
+    /// reads it.  Base58 account addresses are converted to hex unless the
+    /// encoding is Base58Check.
+    pub fn from_file(
+        path: impl AsRef<std::path::Path>,
+        sorted_pairs: bool,
+        hash: MerkleHash,
+        encoding: LeafEncoding,
+    ) -> std::io::Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        let whitelist: Vec<String> = parse_whitelist(&contents)
+            .iter()
+            .map(|entry| normalize_entry(entry, encoding))
+            .collect();
+        MerkleTree::with_encoding(&whitelist, sorted_pairs, hash, encoding).ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::InvalidData, "the whitelist is empty")
+        })
+    }
+
+    /// Writes the proof of each whitelist entry to `<entry>.json` in the
+    /// directory, as an object with the root and the proof, so the proofs can
+    /// be served as static files.  Entries which are not in the tree are
+    /// skipped.  Returns the number of proofs written.
+    pub fn export_proofs(
+        &self,
+        whitelist: &[String],
+        dir: impl AsRef<std::path::Path>,
+    ) -> std::io::Result<usize> {
+        std::fs::create_dir_all(&dir)?;
+        let mut written = 0;
+        for entry in whitelist {
+            let Some(proof) = self.proof(entry) else {
+                continue;
+            };
+            let json = serde_json::json!({ "root": self.hashroot, "proof": proof });
+            std::fs::write(
+                dir.as_ref().join(format!("{}.json", entry)),
+                serde_json::to_string(&json)?,
+            )?;
+            written += 1;
         }
-        vec_to_add.clear();
+        Ok(written)
     }
 }
 
 #[wasm_bindgen]
-// Use this to get the node chain for a given value.
-// Returns None if the value is not found.
-pub fn get_hash_proof(test: JsString, merkle_tree: MerkleTree) -> Option<Vec<JsString>> {
-    let local_tree = merkle_tree;
-
-    let steps = &local_tree.steps;
-    let mut end_point: usize = local_tree.length as usize;
-    let nodes: &Vec<String> = &local_tree.hash_tree;
-    let mut hunted: String = test.as_string().unwrap();
-    let mut startpoint: usize = 0;
-    let mut step_number = 0;
-    let mut proof: Vec<JsString> = Vec::new();
-    let mut index = 0;
-    while startpoint + index < end_point {
-        if hunted == local_tree.hashroot {
-            proof.push(hunted.into());
-            return Some(proof);
-        }
-
-        if nodes[startpoint + index] == hunted {
-            proof.push(hunted.into());
-            if index % 2 == 1 {
-                // it is on the right hand side
-                hunted =
-                    digest(nodes[startpoint + index - 1].clone() + &nodes[startpoint + index]);
-            } else {
-                // it is on the left hand side
-                hunted =
-                    digest(nodes[startpoint + index].clone() + &nodes[startpoint + index + 1]);
-            }
-            startpoint = end_point;
-            end_point += steps[step_number] as usize;
-            step_number += 1;
-            index = 0;
-            continue;
+impl MerkleTree {
+    /// Builds the tree from the whitelisted addresses.
+    pub fn create(
+        nodes: Vec<JsString>,
+        sorted_pairs: bool,
+        hash: MerkleHash,
+    ) -> Result<MerkleTree, JsError> {
+        let nodes: Vec<String> = nodes.into_iter().map(String::from).collect();
+        MerkleTree::new(&nodes, sorted_pairs, hash)
+            .ok_or_else(|| JsError::new("the whitelist is empty"))
+    }
+
+    /// The root hash of the tree.
+    #[wasm_bindgen(getter)]
+    pub fn root(&self) -> String {
+        self.hashroot.clone()
+    }
+
+    /// Number of whitelist entries in the tree.
+    #[wasm_bindgen(getter = leafCount)]
+    pub fn leaf_count(&self) -> usize {
+        self.leaves
+    }
+
+    /// Number of levels above the leaves.  Proofs hold a sibling hash for each
+    /// level followed by the root.
+    #[wasm_bindgen(getter)]
+    pub fn depth(&self) -> usize {
+        self.steps.len()
+    }
+
+    /// Returns the proof for an address, or undefined if it is not whitelisted.
+    pub fn proof_for(&self, leaf: JsString) -> Option<Vec<JsString>> {
+        let proof = self.proof(&String::from(leaf))?;
+        Some(proof.into_iter().map(JsString::from).collect())
+    }
+
+    /// Returns the proof for an address with each hash as its 32 bytes, or
+    /// undefined if it is not whitelisted.
+    #[wasm_bindgen(js_name = proofBytes)]
+    pub fn proof_bytes(&self, leaf: JsString) -> Option<Vec<Uint8Array>> {
+        let proof = self.proof(&String::from(leaf))?;
+        proof
+            .iter()
+            .map(|hash| Some(Uint8Array::from(decode_hex(hash)?.as_slice())))
+            .collect()
+    }
+
+    /// Checks whether an address is whitelisted.
+    pub fn contains(&self, leaf: JsString) -> bool {
+        self.proof(&String::from(leaf)).is_some()
+    }
+
+    /// Returns one proof covering a batch of addresses, or undefined if any of
+    /// them is not whitelisted.
+    #[wasm_bindgen(js_name = multiproof)]
+    pub fn multiproof_for(&self, leaves: Vec<JsString>) -> Result<Option<JsMultiProof>, JsError> {
+        let leaves: Vec<String> = leaves.into_iter().map(String::from).collect();
+        match self.multiproof(&leaves) {
+            Some(proof) => Ok(Some(serde_wasm_bindgen::to_value(&proof)?.unchecked_into())),
+            None => Ok(None),
         }
+    }
+
+    /// Returns the tree as a plain object, so `JSON.stringify(tree)` gives the
+    /// same JSON as `MerkleTree::save` and the tree can be cached.
+    #[wasm_bindgen(js_name = toJSON)]
+    pub fn to_json(&self) -> Result<JsValue, JsError> {
+        Ok(serde_wasm_bindgen::to_value(self)?)
+    }
 
-        index += 1;
+    /// Restores a tree from the parsed JSON of `toJSON` or `MerkleTree::save`.
+    #[wasm_bindgen(js_name = fromJSON)]
+    pub fn from_json(value: JsValue) -> Result<MerkleTree, JsError> {
+        Ok(serde_wasm_bindgen::from_value(value)?)
     }
-    None
 }
 
+#[wasm_bindgen(typescript_custom_section)]
+const MULTI_PROOF_TS: &str = r#"
+export interface MultiProof {
+    leaf_count: number;
+    indices: number[];
+    hashes: string[];
+}
+"#;
+
 #[wasm_bindgen]
-// Use this to compare the user's proof with our's
-pub fn check_proof(test: &ClaimNFTParams, merkle_tree: MerkleTree) -> bool {
-    let master_proof: Vec<JsString> = get_hash_proof(test.node.clone().into(), merkle_tree).unwrap();
-    master_proof == test.proof
+extern "C" {
+    /// A MultiProof as a plain object, typed for TypeScript.
+    #[wasm_bindgen(typescript_type = "MultiProof")]
+    pub type JsMultiProof;
+}
+
+/// Builds the tree of a very large whitelist one level at a time.  Only the
+/// leaf hashes are kept as entries are pushed, and each level is hashed in
+/// place over the one below it, so memory stays linear in the number of
+/// entries.  Gives the same root and proofs as `MerkleTree`.
+pub struct RootBuilder {
+    leaves: Vec<String>,
+    sorted_pairs: bool,
+    hash: MerkleHash,
+    encoding: LeafEncoding,
 }
 
-// Checks to see whether a given value is in the tree
-// Generally used in testing
+impl RootBuilder {
+    pub fn new(sorted_pairs: bool, hash: MerkleHash, encoding: LeafEncoding) -> Self {
+        RootBuilder {
+            leaves: Vec::new(),
+            sorted_pairs,
+            hash,
+            encoding,
+        }
+    }
+
+    /// Adds a whitelist entry, in whitelist order.
+    pub fn push(&mut self, entry: &str) {
+        self.leaves
+            .push(self.hash.hash_leaf(self.encoding.entry_leaf(entry)));
+    }
+
+    /// Number of entries pushed so far.
+    pub fn len(&self) -> usize {
+        self.leaves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.leaves.is_empty()
+    }
+
+    /// Hashes each pair of the level into the front of it and drops the rest.
+    /// Odd levels pair their last node with itself.
+    fn reduce(&self, level: &mut Vec<String>) {
+        if level.len() % 2 == 1 {
+            level.push(level[level.len() - 1].clone());
+        }
+        for index in 0..level.len() / 2 {
+            level[index] = hash_pair(
+                &level[2 * index],
+                &level[2 * index + 1],
+                self.sorted_pairs,
+                self.hash,
+            );
+        }
+        level.truncate(level.len() / 2);
+    }
+
+    /// Consumes the builder and returns the root, or None if no entries were pushed.
+    pub fn root(mut self) -> Option<String> {
+        let mut level = std::mem::take(&mut self.leaves);
+        if level.is_empty() {
+            return None;
+        }
+        loop {
+            self.reduce(&mut level);
+            if level.len() == 1 {
+                return level.pop();
+            }
+        }
+    }
+
+    /// Computes the proof of an entry on demand, from the leaf up to the root.
+    /// Returns None if the entry was not pushed.
+    pub fn proof(&self, entry: &str) -> Option<Vec<String>> {
+        let leaf = self.hash.hash_leaf(self.encoding.entry_leaf(entry));
+        let mut index = self.leaves.iter().position(|node| *node == leaf)?;
+        let mut level = self.leaves.clone();
+        let mut proof = vec![leaf];
+        loop {
+            self.reduce(&mut level);
+            index /= 2;
+            proof.push(level[index].clone());
+            if level.len() == 1 {
+                return Some(proof);
+            }
+        }
+    }
+}
+
+/// Checks that a proof belongs to a whitelist entry and ends at the given root.
+/// The proof is the chain from the leaf up to the root, so the leaf hash and the
+/// root are what can be checked without the tree.  This catches proofs for a
+/// differently encoded entry, or from a tree which is not the one on chain.
+pub fn verify_proof_chain(
+    leaf: &str,
+    proof: &[String],
+    root: &str,
+    hash: MerkleHash,
+    encoding: LeafEncoding,
+) -> bool {
+    proof.first() == Some(&hash.hash_leaf(encoding.entry_leaf(leaf)))
+        && proof.last().map(String::as_str) == Some(root)
+}
+
+/// Checks a proof against the root returned by the contract's merkle_root view,
+/// before the user signs the claim.  hash and encoding must match the
+/// contract's merkle_hash and leaf_encoding.
 #[wasm_bindgen]
-pub fn check_hash_value(tree: MerkleTree, test_address: JsString) -> bool {
-    let steps = &tree.steps;
-    let mut end_point = tree.length as usize;
-    let nodes = &tree.hash_tree;
-    let mut hunted = test_address.as_string().unwrap();
-    let mut startpoint = 0;
-    let mut step_number = 0;
-
-    let mut index: usize = 0;
-    while startpoint + index < end_point {
-        if hunted.eq(&tree.hashroot) {
-            return true;
-        }
-
-        if nodes[startpoint + index] == hunted {
-            if index % 2 == 1 {
-                // it is on the right hand side
-                hunted =
-                    digest(nodes[startpoint + index - 1].clone() + &nodes[startpoint + index]).into();
+pub fn verify_proof(
+    leaf: String,
+    proof: Vec<JsString>,
+    root: String,
+    hash: MerkleHash,
+    encoding: LeafEncoding,
+) -> bool {
+    let proof: Vec<String> = proof.into_iter().map(String::from).collect();
+    verify_proof_chain(&leaf, &proof, &root, hash, encoding)
+}
+
+/// Proof that a batch of entries is in the tree, from `MerkleTree::multiproof`.
+/// indices holds the leaf position of each entry, in the order they were given,
+/// and hashes the sibling nodes which can't be hashed from the entries, level
+/// by level from the leaves up.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MultiProof {
+    pub leaf_count: usize,
+    pub indices: Vec<usize>,
+    pub hashes: Vec<String>,
+}
+
+/// Checks a multiproof for the entries, given in the order the proof was made
+/// for, against a root.  sorted_pairs, hash and encoding must match the
+/// contract's init settings.
+pub fn verify_multiproof(
+    leaves: &[String],
+    proof: &MultiProof,
+    root: &str,
+    sorted_pairs: bool,
+    hash: MerkleHash,
+    encoding: LeafEncoding,
+) -> bool {
+    if leaves.is_empty() || leaves.len() != proof.indices.len() {
+        return false;
+    }
+    let mut known: BTreeMap<usize, String> = BTreeMap::new();
+    for (leaf, index) in leaves.iter().zip(&proof.indices) {
+        let leaf = hash.hash_leaf(encoding.entry_leaf(leaf));
+        if *index >= proof.leaf_count
+            || known
+                .insert(*index, leaf.clone())
+                .is_some_and(|other| other != leaf)
+        {
+            return false;
+        }
+    }
+
+    let mut hashes = proof.hashes.iter();
+    let mut size = proof.leaf_count;
+    let mut first_level = true;
+    while size > 1 || first_level {
+        let mut parents: BTreeMap<usize, String> = BTreeMap::new();
+        for (&index, node) in &known {
+            if parents.contains_key(&(index / 2)) {
+                continue;
+            }
+            let sibling = match known.get(&(index ^ 1)) {
+                Some(sibling) => sibling,
+                None if index ^ 1 >= size => node,
+                None => match hashes.next() {
+                    Some(sibling) => sibling,
+                    None => return false,
+                },
+            };
+            let parent = if index % 2 == 0 {
+                hash_pair(node, sibling, sorted_pairs, hash)
             } else {
-                // it is on the left hand side
-                hunted =
-                    digest(nodes[startpoint + index].clone() + &nodes[startpoint + index + 1]).into();
+                hash_pair(sibling, node, sorted_pairs, hash)
+            };
+            parents.insert(index / 2, parent);
+        }
+        known = parents;
+        size = size.div_ceil(2);
+        first_level = false;
+    }
+    hashes.next().is_none() && known.get(&0).map(String::as_str) == Some(root)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addresses(count: usize) -> Vec<String> {
+        (0..count).map(|i| format!("address{}", i)).collect()
+    }
+
+    #[test]
+    fn test_proof() {
+        let nodes = addresses(3);
+        let tree = MerkleTree::new(&nodes, false, MerkleHash::Sha256).unwrap();
+
+        let leaves: Vec<String> = nodes
+            .iter()
+            .map(|node| MerkleHash::Sha256.hash_leaf(node))
+            .collect();
+        let left = hash_pair(&leaves[0], &leaves[1], false, MerkleHash::Sha256);
+        let right = hash_pair(&leaves[2], &leaves[2], false, MerkleHash::Sha256);
+        let root = hash_pair(&left, &right, false, MerkleHash::Sha256);
+        assert_eq!(tree.root(), root);
+
+        assert_eq!(
+            tree.proof("address1"),
+            Some(vec![leaves[1].clone(), left, root.clone()])
+        );
+        assert_eq!(
+            tree.proof("address2"),
+            Some(vec![leaves[2].clone(), right, root])
+        );
+        assert_eq!(tree.proof("address3"), None);
+    }
+
+    #[test]
+    fn test_every_leaf_has_a_proof() {
+        for count in [1, 2, 3, 6, 9, 11, 20] {
+            let nodes = addresses(count);
+            let tree = MerkleTree::new(&nodes, false, MerkleHash::Sha256).unwrap();
+            for node in &nodes {
+                let proof = tree.proof(node).unwrap();
+                assert_eq!(proof.last(), Some(&tree.root()));
+                assert_eq!(proof.len(), tree.depth() + 1);
             }
-            startpoint = end_point;
-            end_point += steps[step_number] as usize;
-            step_number += 1;
-            index = 0;
-            continue;
         }
+        assert!(MerkleTree::new(&[], false, MerkleHash::Sha256).is_none());
+    }
+
+    #[test]
+    fn test_sorted_pairs() {
+        let nodes = addresses(3);
+        let tree = MerkleTree::new(&nodes, true, MerkleHash::Sha256).unwrap();
+
+        let leaves: Vec<String> = nodes
+            .iter()
+            .map(|node| MerkleHash::Sha256.hash_leaf(node))
+            .collect();
+        let left = hash_pair(&leaves[1], &leaves[0], true, MerkleHash::Sha256);
+        assert_eq!(
+            left,
+            hash_pair(&leaves[0], &leaves[1], true, MerkleHash::Sha256)
+        );
+        let right = hash_pair(&leaves[2], &leaves[2], true, MerkleHash::Sha256);
+        let root = hash_pair(&right, &left, true, MerkleHash::Sha256);
+
+        assert_eq!(tree.root(), root);
+        assert_eq!(
+            tree.proof("address0"),
+            Some(vec![leaves[0].clone(), left, root])
+        );
+    }
+
+    #[test]
+    fn test_keccak() {
+        let hash = MerkleHash::Keccak256;
+        assert_eq!(
+            hash.digest(b""),
+            "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+        );
+
+        let nodes = addresses(2);
+        let tree = MerkleTree::new(&nodes, false, hash).unwrap();
+        let leaves: Vec<String> = nodes.iter().map(|node| hash.hash_leaf(node)).collect();
+        let root = hash_pair(&leaves[0], &leaves[1], false, hash);
+        assert_eq!(tree.proof("address0"), Some(vec![leaves[0].clone(), root]));
+    }
+
+    #[test]
+    fn test_base58_addresses() {
+        assert_eq!(
+            decode_base58_address("2wkBET2rRgE8pahuaczxKbmv7ciehqsne57F9gtzf1PVdr2VP3"),
+            Some([0u8; 32])
+        );
+        assert_eq!(
+            decode_base58_address("2xBpaHottqhwFZURMZW4uZduQvpxNDSy46iXMYs9kceNGaPpZX"),
+            Some([1u8; 32])
+        );
+        // a mistyped character breaks the checksum
+        assert_eq!(
+            decode_base58_address("2xBpaHottqhwFZURMZW4uZduQvpxNDSy46iXMYs9kceNGaPpZY"),
+            None
+        );
+        assert_eq!(normalize_address("<5,0>"), "<5,0>");
+        assert_eq!(
+            normalize_address("2xBpaHottqhwFZURMZW4uZduQvpxNDSy46iXMYs9kceNGaPpZX"),
+            "01".repeat(32)
+        );
+    }
+
+    #[test]
+    fn test_verify_proof_chain() {
+        let nodes = vec!["ABCD".to_string(), "address1".to_string()];
+        let tree = MerkleTree::with_encoding(&nodes, false, MerkleHash::Sha256, LeafEncoding::Hex)
+            .unwrap();
+        let proof = tree.proof("ABCD").unwrap();
+        let verify = |leaf: &str, root: &str, encoding: LeafEncoding| {
+            verify_proof_chain(leaf, &proof, root, MerkleHash::Sha256, encoding)
+        };
+
+        assert!(verify("ABCD", &tree.root(), LeafEncoding::Hex));
+        assert!(verify("abcd", &tree.root(), LeafEncoding::Hex));
+        // the contract would hash a different leaf with another encoding
+        assert!(!verify("ABCD", &tree.root(), LeafEncoding::NodeString));
+        assert!(!verify("address1", &tree.root(), LeafEncoding::Hex));
+        assert!(!verify("ABCD", &"00".repeat(32), LeafEncoding::Hex));
+        assert!(!verify_proof_chain(
+            "ABCD",
+            &[],
+            &tree.root(),
+            MerkleHash::Sha256,
+            LeafEncoding::Hex
+        ));
+    }
+
+    #[test]
+    fn test_multiproof() {
+        let nodes = addresses(11);
+        for sorted_pairs in [false, true] {
+            let tree = MerkleTree::new(&nodes, sorted_pairs, MerkleHash::Sha256).unwrap();
+            let verify = |leaves: &[String], proof: &MultiProof| {
+                verify_multiproof(
+                    leaves,
+                    proof,
+                    &tree.root(),
+                    sorted_pairs,
+                    MerkleHash::Sha256,
+                    LeafEncoding::NodeString,
+                )
+            };
+            for batch in [
+                vec![0],
+                vec![10],
+                vec![3, 2],
+                vec![0, 5, 9, 10],
+                (0..11).collect(),
+            ] {
+                let leaves: Vec<String> = batch.iter().map(|i| nodes[*i].clone()).collect();
+                let proof = tree.multiproof(&leaves).unwrap();
+                assert_eq!(proof.indices, batch);
+                assert!(verify(&leaves, &proof));
+            }
+
+            let leaves = vec![nodes[1].clone(), nodes[4].clone()];
+            let proof = tree.multiproof(&leaves).unwrap();
+            // shared nodes are only given once
+            assert!(proof.hashes.len() < 2 * tree.proof(&nodes[1]).unwrap().len());
+            assert!(!verify(&[nodes[1].clone(), nodes[5].clone()], &proof));
+            assert!(!verify(&leaves[..1], &proof));
+            let mut short = proof.clone();
+            short.hashes.pop();
+            assert!(!verify(&leaves, &short));
+        }
+
+        let tree = MerkleTree::new(&addresses(1), false, MerkleHash::Sha256).unwrap();
+        let proof = tree.multiproof(&addresses(1)).unwrap();
+        assert!(proof.hashes.is_empty());
+        assert!(verify_multiproof(
+            &addresses(1),
+            &proof,
+            &tree.root(),
+            false,
+            MerkleHash::Sha256,
+            LeafEncoding::NodeString
+        ));
+        assert!(tree.multiproof(&addresses(2)).is_none());
+    }
+
+    #[test]
+    fn test_root_builder() {
+        for count in [1, 2, 3, 6, 8, 9, 17, 100] {
+            let nodes = addresses(count);
+            let tree = MerkleTree::new(&nodes, true, MerkleHash::Sha256).unwrap();
+            let mut builder = RootBuilder::new(true, MerkleHash::Sha256, LeafEncoding::NodeString);
+            for node in &nodes {
+                builder.push(node);
+            }
+            assert_eq!(builder.len(), count);
+            if count <= 8 {
+                assert_eq!(
+                    builder.proof(&nodes[count - 1]),
+                    tree.proof(&nodes[count - 1])
+                );
+            }
+            let proof = builder.proof(&nodes[0]).unwrap();
+            assert_eq!(builder.proof("missing"), None);
+            assert_eq!(builder.root(), Some(tree.root()));
+            assert_eq!(proof.last(), Some(&tree.root()));
+        }
+        let builder = RootBuilder::new(false, MerkleHash::Sha256, LeafEncoding::NodeString);
+        assert!(builder.is_empty());
+        assert_eq!(builder.root(), None);
+    }
+
+    #[test]
+    fn test_append() {
+        for (count, added) in [(1, 1), (2, 1), (3, 3), (4, 5), (5, 2), (8, 1), (7, 10)] {
+            let nodes = addresses(count + added);
+            let mut tree = MerkleTree::new(&nodes[..count], true, MerkleHash::Sha256).unwrap();
+            let root = tree.append(&nodes[count..]);
+
+            let rebuilt = MerkleTree::new(&nodes, true, MerkleHash::Sha256).unwrap();
+            assert_eq!(root, rebuilt.root());
+            assert_eq!(tree.leaf_count(), count + added);
+            assert_eq!(tree.hash_tree, rebuilt.hash_tree);
+            assert_eq!(tree.steps, rebuilt.steps);
+            assert_eq!(tree.length, rebuilt.length);
+        }
+
+        let mut tree = MerkleTree::new(&addresses(3), false, MerkleHash::Sha256).unwrap();
+        assert_eq!(tree.append(&[]), tree.root());
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let nodes = addresses(6);
+        let tree =
+            MerkleTree::with_encoding(&nodes, true, MerkleHash::Keccak256, LeafEncoding::Hex)
+                .unwrap();
+        let path = std::env::temp_dir().join(format!("merkle_tree_{}.json", std::process::id()));
+        tree.save(&path).unwrap();
+        let loaded = MerkleTree::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.root(), tree.root());
+        for node in &nodes {
+            assert_eq!(loaded.proof(node), tree.proof(node));
+        }
+        assert!(MerkleTree::load(&path).is_err());
+    }
+
+    #[cfg(feature = "native")]
+    #[test]
+    fn test_from_file_and_export_proofs() {
+        let dir = std::env::temp_dir().join(format!("merkle_tree_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("whitelist.csv");
+        std::fs::write(&path, "address\naddress0\naddress1,2\naddress2\n").unwrap();
+
+        let tree =
+            MerkleTree::from_file(&path, false, MerkleHash::Sha256, LeafEncoding::NodeString)
+                .unwrap();
+        assert_eq!(
+            tree.root(),
+            MerkleTree::new(&addresses(3), false, MerkleHash::Sha256)
+                .unwrap()
+                .root()
+        );
+
+        let proofs = dir.join("proofs");
+        let mut whitelist = addresses(3);
+        whitelist.push("missing".to_string());
+        assert_eq!(tree.export_proofs(&whitelist, &proofs).unwrap(), 3);
+        let json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(proofs.join("address1.json")).unwrap())
+                .unwrap();
+        assert_eq!(json["root"], tree.root());
+        assert_eq!(
+            json["proof"],
+            serde_json::json!(tree.proof("address1").unwrap())
+        );
+        assert!(!proofs.join("missing.json").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 
-        index += 1;
+    #[test]
+    fn test_raw_leaves() {
+        let nodes = vec!["00".repeat(32), format!("{}:2", "01".repeat(32))];
+        let tree = MerkleTree::with_encoding(&nodes, false, MerkleHash::Sha256, LeafEncoding::Raw)
+            .unwrap();
+        let leaf = MerkleHash::Sha256.hash_leaf([0u8; 32]);
+        assert_eq!(tree.proof(&nodes[0]).unwrap()[0], leaf);
+        let leaf = MerkleHash::Sha256.hash_leaf([&[1u8; 32][..], b":2"].concat());
+        assert_eq!(tree.proof(&nodes[1]).unwrap()[0], leaf);
     }
-    false
 }
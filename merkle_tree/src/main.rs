@@ -0,0 +1,291 @@
+// Command line tool which builds the whitelist merkle tree and writes the
+// proof of every address, ready to upload to the claim website.
+//
+// Usage: merkle_tree [--sorted] [--keccak | --sha3] [--allocations] [--raw | --hex | --base58check]
+//                    <whitelist.csv> <proofs.json>
+//        merkle_tree --root-only [options] <whitelist.csv>
+//        merkle_tree --tree <tree.json> [options] <whitelist.csv> <proofs.json>
+//
+// Pass --sorted when the contract was initialised with sorted_pairs, and
+// --keccak or --sha3 when it was initialised with the Keccak256 or Sha3_256
+// merkle_hash.  Pass --raw, --hex or --base58check for the contract's
+// leaf_encoding, or none of them for NodeString.
+//
+// Account addresses can be given in the base58 format wallets show, such as
+// 3XSLuJcX...  Unless the encoding is --base58check they are converted to the
+// hex the contract writes accounts as, which is what the whitelist in the
+// output holds.  Proofs are listed under the addresses as given.
+//
+// With --root-only the whitelist is read a line at a time and only the root
+// is printed, for whitelists too large to hold every level of the tree.
+//
+// With --tree the built tree is saved to tree.json, and later runs load it
+// from there instead of rebuilding it.  The proof server can load the same
+// file with MerkleTree::load.  When addresses have been added to the end of
+// the whitelist since, only they are appended to the saved tree, and the new
+// root to pass to set_whitelist is printed.
+//
+// The input has one address per line.  For CSV files the address is taken
+// from the first column and a header line starting with "address" is skipped.
+// With --allocations the second column holds the maximum number of claims of
+// the address, and the leaves become "address:max_claims".
+use merkle_tree::{
+    normalize_entry, parse_whitelist, LeafEncoding, MerkleHash, MerkleTree, RootBuilder,
+};
+use serde_json::{json, Map, Value};
+use std::{
+    env, fs,
+    io::{BufRead, BufReader},
+    process,
+};
+
+fn parse_allocations(contents: &str) -> Result<Vec<String>, String> {
+    contents
+        .lines()
+        .map(|line| {
+            line.split(',')
+                .map(|column| column.trim().trim_matches('"'))
+        })
+        .filter_map(|mut columns| Some((columns.next()?, columns.next())))
+        .filter(|(address, _)| !address.is_empty() && !address.eq_ignore_ascii_case("address"))
+        .map(|(address, max_claims)| {
+            let max_claims: u32 = max_claims
+                .and_then(|max_claims| max_claims.parse().ok())
+                .ok_or(format!("{} has no valid allocation", address))?;
+            Ok(format!("{}:{}", address, max_claims))
+        })
+        .collect()
+}
+
+fn proofs_json(entries: &[String], whitelist: &[String], tree: &MerkleTree) -> Value {
+    let mut proofs = Map::new();
+    for (entry, address) in entries.iter().zip(whitelist) {
+        proofs.insert(entry.clone(), json!(tree.proof(address)));
+    }
+    json!({
+        "root": tree.root(),
+        "whitelist": whitelist,
+        "proofs": proofs,
+    })
+}
+
+fn run(
+    input: &str,
+    output: &str,
+    sorted_pairs: bool,
+    hash: MerkleHash,
+    allocations: bool,
+    encoding: LeafEncoding,
+    tree_path: Option<&str>,
+) -> Result<usize, String> {
+    let contents =
+        fs::read_to_string(input).map_err(|err| format!("cannot read {}: {}", input, err))?;
+    let entries = if allocations {
+        parse_allocations(&contents)?
+    } else {
+        parse_whitelist(&contents)
+    };
+    let whitelist: Vec<String> = entries
+        .iter()
+        .map(|entry| normalize_entry(entry, encoding))
+        .collect();
+    let tree = match tree_path {
+        Some(path) if fs::metadata(path).is_ok() => {
+            let mut tree =
+                MerkleTree::load(path).map_err(|err| format!("cannot load {}: {}", path, err))?;
+            let saved = tree.leaf_count();
+            if whitelist.len() < saved {
+                return Err(format!(
+                    "{} has more addresses than {}, delete it to rebuild the tree",
+                    path, input
+                ));
+            }
+            if whitelist.len() > saved {
+                let root = tree.append(&whitelist[saved..]);
+                tree.save(path)
+                    .map_err(|err| format!("cannot write {}: {}", path, err))?;
+                println!(
+                    "appended {} addresses, new root for set_whitelist: {}",
+                    whitelist.len() - saved,
+                    root
+                );
+            }
+            tree
+        }
+        _ => {
+            let tree = MerkleTree::with_encoding(&whitelist, sorted_pairs, hash, encoding)
+                .ok_or(format!("{} has no addresses", input))?;
+            if let Some(path) = tree_path {
+                tree.save(path)
+                    .map_err(|err| format!("cannot write {}: {}", path, err))?;
+            }
+            tree
+        }
+    };
+
+    let json = serde_json::to_string_pretty(&proofs_json(&entries, &whitelist, &tree))
+        .map_err(|err| err.to_string())?;
+    fs::write(output, json).map_err(|err| format!("cannot write {}: {}", output, err))?;
+    Ok(whitelist.len())
+}
+
+fn run_root_only(
+    input: &str,
+    sorted_pairs: bool,
+    hash: MerkleHash,
+    allocations: bool,
+    encoding: LeafEncoding,
+) -> Result<(usize, String), String> {
+    let file = fs::File::open(input).map_err(|err| format!("cannot read {}: {}", input, err))?;
+    let mut builder = RootBuilder::new(sorted_pairs, hash, encoding);
+    for line in BufReader::new(file).lines() {
+        let line = line.map_err(|err| format!("cannot read {}: {}", input, err))?;
+        let entries = if allocations {
+            parse_allocations(&line)?
+        } else {
+            parse_whitelist(&line)
+        };
+        for entry in entries {
+            builder.push(&normalize_entry(&entry, encoding));
+        }
+    }
+    let count = builder.len();
+    let root = builder
+        .root()
+        .ok_or(format!("{} has no addresses", input))?;
+    Ok((count, root))
+}
+
+fn main() {
+    let mut args: Vec<String> = env::args().collect();
+    let sorted_pairs = args.iter().any(|arg| arg == "--sorted");
+    let hash = if args.iter().any(|arg| arg == "--keccak") {
+        MerkleHash::Keccak256
+    } else if args.iter().any(|arg| arg == "--sha3") {
+        MerkleHash::Sha3_256
+    } else {
+        MerkleHash::Sha256
+    };
+    let allocations = args.iter().any(|arg| arg == "--allocations");
+    let encoding = if args.iter().any(|arg| arg == "--raw") {
+        LeafEncoding::Raw
+    } else if args.iter().any(|arg| arg == "--hex") {
+        LeafEncoding::Hex
+    } else if args.iter().any(|arg| arg == "--base58check") {
+        LeafEncoding::Base58Check
+    } else {
+        LeafEncoding::NodeString
+    };
+    let root_only = args.iter().any(|arg| arg == "--root-only");
+    let tree_path = match args.iter().position(|arg| arg == "--tree") {
+        Some(index) if index + 1 < args.len() => {
+            args.remove(index);
+            Some(args.remove(index))
+        }
+        _ => None,
+    };
+    args.retain(|arg| {
+        !matches!(
+            arg.as_str(),
+            "--sorted"
+                | "--keccak"
+                | "--sha3"
+                | "--allocations"
+                | "--raw"
+                | "--hex"
+                | "--base58check"
+                | "--root-only"
+        )
+    });
+    if root_only && args.len() == 2 {
+        match run_root_only(&args[1], sorted_pairs, hash, allocations, encoding) {
+            Ok((count, root)) => println!("root of {} addresses: {}", count, root),
+            Err(err) => {
+                eprintln!("error: {}", err);
+                process::exit(1);
+            }
+        }
+        return;
+    }
+    if args.len() != 3 {
+        eprintln!(
+            "usage: {} [--sorted] [--keccak | --sha3] [--allocations] [--raw | --hex | --base58check] \
+             <whitelist.csv> <proofs.json>",
+            args[0]
+        );
+        process::exit(2);
+    }
+
+    match run(
+        &args[1],
+        &args[2],
+        sorted_pairs,
+        hash,
+        allocations,
+        encoding,
+        tree_path.as_deref(),
+    ) {
+        Ok(count) => println!("wrote proofs for {} addresses to {}", count, args[2]),
+        Err(err) => {
+            eprintln!("error: {}", err);
+            process::exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_whitelist() {
+        let contents = "address,allocation\n\"addr1\",2\n\naddr2\r\n  addr3  ,1\n";
+        assert_eq!(parse_whitelist(contents), vec!["addr1", "addr2", "addr3"]);
+    }
+
+    #[test]
+    fn test_parse_allocations() {
+        let contents = "address,allocation\naddr1,2\n\"addr2\", 1 \n";
+        assert_eq!(
+            parse_allocations(contents),
+            Ok(vec!["addr1:2".to_string(), "addr2:1".to_string()])
+        );
+        assert!(parse_allocations("addr1\n").is_err());
+    }
+
+    #[test]
+    fn test_proofs_json() {
+        let whitelist = parse_whitelist("addr1\naddr2\naddr3");
+        let tree = MerkleTree::new(&whitelist, false, MerkleHash::Sha256).unwrap();
+        let json = proofs_json(&whitelist, &whitelist, &tree);
+
+        assert_eq!(json["root"], tree.root());
+        assert_eq!(json["whitelist"], json!(["addr1", "addr2", "addr3"]));
+        assert_eq!(json["proofs"]["addr2"], json!(tree.proof("addr2").unwrap()));
+    }
+
+    #[test]
+    fn test_base58_input() {
+        let account = "2xBpaHottqhwFZURMZW4uZduQvpxNDSy46iXMYs9kceNGaPpZX";
+        let entries = vec![account.to_string(), format!("{}:2", account)];
+        let whitelist: Vec<String> = entries
+            .iter()
+            .map(|entry| normalize_entry(entry, LeafEncoding::Raw))
+            .collect();
+        assert_eq!(
+            whitelist,
+            vec!["01".repeat(32), format!("{}:2", "01".repeat(32))]
+        );
+        assert_eq!(normalize_entry(account, LeafEncoding::Base58Check), account);
+
+        let tree =
+            MerkleTree::with_encoding(&whitelist, false, MerkleHash::Sha256, LeafEncoding::Raw)
+                .unwrap();
+        let json = proofs_json(&entries, &whitelist, &tree);
+        assert_eq!(json["whitelist"][0], "01".repeat(32));
+        assert_eq!(
+            json["proofs"][account][0],
+            MerkleHash::Sha256.hash_leaf([1u8; 32])
+        );
+    }
+}
@@ -1,28 +1,176 @@
 //! # A Concordium V1 smart contract
 use concordium_cis2::*;
 use concordium_std::*;
+use merkle_core::decode_hex;
 use sha256::digest;
 
 use core::fmt::Debug;
 
 /// Contract token ID type.
-/// To save bytes we use a token ID type limited to a `u32`.
+/// To save bytes we use a token ID type limited to a `u32`.  Build with the
+/// `u64_token_ids` feature for collections which need IDs above that.
+#[cfg(not(feature = "u64_token_ids"))]
 type ContractTokenId = TokenIdU32;
+#[cfg(feature = "u64_token_ids")]
+type ContractTokenId = TokenIdU64;
 
 type ContractTokenAmount = TokenAmountU32;
 
-/*
+/// Version of the state layout written by this module.  Bump this whenever
+/// `State` changes so a migration can tell which layout it is reading.
+const STATE_VERSION: u32 = 2;
+
+/// Identifier of an airdrop round.  Round 0 is the collection set up at init.
+type RoundId = u32;
+
+/// Token IDs of later rounds carry the round ID in the top 8 bits, above this
+/// shift, so each round can hold up to 2^24 tokens (more with `u64_token_ids`).
+#[cfg(not(feature = "u64_token_ids"))]
+const ROUND_TOKEN_ID_SHIFT: u32 = 24;
+#[cfg(feature = "u64_token_ids")]
+const ROUND_TOKEN_ID_SHIFT: u32 = 56;
+
+/// The highest round ID which fits in the top bits of a token ID.
+const MAX_ROUND_ID: RoundId = 255;
+
+/// Length of the days claim statistics are kept for, in milliseconds of slot time.
+const MILLIS_PER_DAY: u64 = 24 * 60 * 60 * 1000;
+
+/// The most days `claim_stats` returns in one call.
+const MAX_STATS_DAYS: u32 = 366;
+
+/// Returns the token ID at the given index of the collection set up at init.
+#[cfg(not(feature = "u64_token_ids"))]
+fn index_token_id(index: u32) -> ContractTokenId {
+    TokenIdU32(index)
+}
+#[cfg(feature = "u64_token_ids")]
+fn index_token_id(index: u32) -> ContractTokenId {
+    TokenIdU64(u64::from(index))
+}
+
+/// Returns the token ID at the given index of a round.
+#[cfg(not(feature = "u64_token_ids"))]
+fn round_token_id(round_id: RoundId, index: u32) -> ContractTokenId {
+    TokenIdU32((round_id << ROUND_TOKEN_ID_SHIFT) + index)
+}
+#[cfg(feature = "u64_token_ids")]
+fn round_token_id(round_id: RoundId, index: u32) -> ContractTokenId {
+    TokenIdU64((u64::from(round_id) << ROUND_TOKEN_ID_SHIFT) + u64::from(index))
+}
+
+/// Returns the ID of the token `offset` places after the given one.
+#[cfg(not(feature = "u64_token_ids"))]
+fn offset_token_id(token_id: ContractTokenId, offset: u32) -> ContractTokenId {
+    TokenIdU32(token_id.0 + offset)
+}
+#[cfg(feature = "u64_token_ids")]
+fn offset_token_id(token_id: ContractTokenId, offset: u32) -> ContractTokenId {
+    TokenIdU64(token_id.0 + u64::from(offset))
+}
+
+/// Returns the round a token ID belongs to, 0 being the collection set up at init.
+#[cfg(not(feature = "u64_token_ids"))]
+fn round_of(token_id: &ContractTokenId) -> RoundId {
+    token_id.0 >> ROUND_TOKEN_ID_SHIFT
+}
+#[cfg(feature = "u64_token_ids")]
+fn round_of(token_id: &ContractTokenId) -> RoundId {
+    // Only the top 8 bits are left after the shift, so this never truncates
+    (token_id.0 >> ROUND_TOKEN_ID_SHIFT) as RoundId
+}
+
+/// Returns the index of a token ID within its round.
+fn round_index(token_id: &ContractTokenId) -> u32 {
+    // Tokens are minted at u32 indexes of their round, so this never truncates
+    (token_index(token_id) - token_index(&round_token_id(round_of(token_id), 0))) as u32
+}
+
+/// Returns the numeric value of a token ID, whichever width it has.
+#[cfg(not(feature = "u64_token_ids"))]
+fn token_index(token_id: &ContractTokenId) -> u64 {
+    u64::from(token_id.0)
+}
+#[cfg(feature = "u64_token_ids")]
+fn token_index(token_id: &ContractTokenId) -> u64 {
+    token_id.0
+}
+
+/// The parameter type for the contract function `transfer`.
+type TransferParameter = TransferParams<ContractTokenId, ContractTokenAmount>;
+
+/// The tokens minted by a claim, with the number of copies of each.
+type ClaimedTokens = Vec<(ContractTokenId, ContractTokenAmount)>;
+
+/// The parameter type for the contract function `tokenMetadata`.
+type ContractTokenMetadataQueryParams = TokenMetadataQueryParams<ContractTokenId>;
+
+/// The parameter type for the contract function `balanceOf`.
+type ContractBalanceOfQueryParams = BalanceOfQueryParams<ContractTokenId>;
+
+/// The return type of the contract function `balanceOf`.
+type ContractBalanceOfQueryResponse = BalanceOfQueryResponse<ContractTokenAmount>;
+
+/// Hex encodes an account address.  Used as the owner string for tokens which
+/// were minted without the owner supplying their own address string.
 fn account_address_to_string(address: AccountAddress) -> String {
     let hex_chars: Vec<String> = address
         .0
         .iter()
         .map(|byte| format!("{:02x}", byte))
-        .skip(7)
         .collect();
     hex_chars.join("")
 }
-*/
 
+/// Alphabet of the base58 encoding used for Concordium account addresses.
+const BASE58_ALPHABET: &[u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// Encodes an account address in the base58check format wallets show, such as
+/// `3XSLuJcX...`: a version byte of 1, the address and a checksum of the first
+/// four bytes of its double SHA256 hash.
+fn account_address_to_base58(address: AccountAddress) -> String {
+    let mut payload = vec![1u8];
+    payload.extend_from_slice(&address.0);
+    let checksum = sha256_bytes(&sha256_bytes(&payload));
+    payload.extend_from_slice(&checksum[..4]);
+
+    // Base58 digits of the payload, least significant first
+    let mut digits: Vec<u8> = Vec::new();
+    for byte in payload.iter() {
+        let mut carry = u32::from(*byte);
+        for digit in digits.iter_mut() {
+            carry += u32::from(*digit) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+    let zeros = payload.iter().take_while(|byte| **byte == 0).count();
+    core::iter::repeat_n('1', zeros)
+        .chain(
+            digits
+                .iter()
+                .rev()
+                .map(|digit| BASE58_ALPHABET[*digit as usize] as char),
+        )
+        .collect()
+}
+
+/// Hashes some bytes with SHA256, giving the raw hash rather than hex.
+fn sha256_bytes(input: &[u8]) -> Vec<u8> {
+    decode_hex(&digest(input)).unwrap_or_default()
+}
+
+/// Formats an address as an owner string.  Contracts are written as `<index,subindex>`.
+fn address_to_string(address: Address) -> String {
+    match address {
+        Address::Account(account) => account_address_to_string(account),
+        Address::Contract(contract) => format!("<{},{}>", contract.index, contract.subindex),
+    }
+}
 /// The parameter for the contract function `mint` which mints a number of
 /// tokens to a given address.
 #[derive(Serial, Deserial, SchemaType)]
@@ -36,16 +184,337 @@ struct InitParams {
     metadata: String,
     whitelist_file: String,
     selected_index: bool,
+    mint_price: Amount,
+    start_time: u64,
+    phases: Vec<Phase>,
+    tiers: Vec<TierParams>,
+    soulbound: bool,
+    placeholder_url: String,
+    metadata_hashes: Vec<[u8; 32]>,
+    allow_sponsored_claims: bool,
+    random_index: bool,
+    random_salt_commitment: String,
+    voucher_key: Option<PublicKeyEd25519>,
+    edition_sizes: Vec<u32>,
+    sorted_pairs: bool,
+    merkle_hash: MerkleHash,
+    reserve_release_time: u64,
+    beneficiaries: Vec<Beneficiary>,
+    marketplace: Option<ContractAddress>,
+    max_per_tx: u32,
+    one_claim_per_account: bool,
+    provenance_hash: String,
+    reserved_ranges: Vec<IndexRange>,
+    hex_token_ids: bool,
+    payment_token: Option<PaymentToken>,
+    raffle: Option<RaffleParams>,
+    rate_limit: Option<RateLimit>,
+    leaf_encoding: LeafEncoding,
+    reserve_allocations: Vec<(Address, u32)>,
+    shuffle_seed_commitment: String,
+    token_gate: Option<TokenGate>,
+    identity_policy: Option<IdentityPolicy>,
+    admins: Vec<AccountAddress>,
+    admin_threshold: u32,
+    price_curve: Option<PriceCurve>,
+    bridge_key: Option<PublicKeyEd25519>,
+    airdrop_token: Option<AirdropToken>,
+    /// CCD sent for each unit of a claimer's allocation, in CCD airdrop mode.
+    /// Leave 0 to mint NFTs.
+    ccd_unit: Amount,
+    /// Time until which claimed tokens can't be transferred.  Leave 0 for no lock.
+    unlock_time: u64,
+}
+
+/// A range of token indexes, from start to end inclusive.
+#[derive(Debug, Serialize, SchemaType, Clone, PartialEq)]
+pub struct IndexRange {
+    start: u32,
+    end: u32,
+}
+
+/// A CIS-2 token, such as a stablecoin, which claims are paid in instead of CCD.
+#[derive(Debug, Serialize, SchemaType, Clone, PartialEq)]
+pub struct PaymentToken {
+    contract: ContractAddress,
+    token_id: TokenIdVec,
+    /// Price of a single NFT in units of the payment token
+    price: TokenAmountU64,
+}
+
+/// An existing CIS-2 token, such as a fungible project token, which claims
+/// transfer out of this contract's balance instead of minting NFTs.
+#[derive(Debug, Serialize, SchemaType, Clone, PartialEq)]
+pub struct AirdropToken {
+    contract: ContractAddress,
+    token_id: TokenIdVec,
+    /// Amount of the token sent for each unit of a claimer's allocation
+    unit: TokenAmountU64,
+}
+
+/// A CIS-2 token claimers have to hold, such as a membership pass from another collection.
+#[derive(Debug, Serialize, SchemaType, Clone, PartialEq)]
+pub struct TokenGate {
+    contract: ContractAddress,
+    token_id: TokenIdVec,
+    /// Least number of the tokens the claimer has to hold
+    min_balance: TokenAmountU64,
+}
+
+/// An identity attribute claimers have to reveal in their account credentials,
+/// and the values of it which are not allowed to claim.
+#[derive(Debug, Serialize, SchemaType, Clone, PartialEq)]
+pub struct IdentityPolicy {
+    /// Tag of the attribute, such as 4 for the country of residence or 5 for the nationality
+    attribute: u8,
+    /// Values of the attribute which can not claim, such as ISO 3166-1 alpha-2 country codes
+    blocked: Vec<String>,
+}
+
+/// A raffle deciding who can claim.  Accounts register until the deadline,
+/// after which the admin draws the winners.
+#[derive(Debug, Serialize, SchemaType, Clone, PartialEq)]
+pub struct RaffleParams {
+    /// Time at which registration closes
+    registration_end: Timestamp,
+    /// Number of accounts drawn as winners
+    winners: u32,
+    /// CCD each account pays to register, refunded if it is not drawn
+    deposit: Amount,
+    /// SHA256 hash of the salt which seeds the draw
+    salt_commitment: String,
+}
+
+/// The raffle as kept in the state.
+#[derive(Debug, Serialize, SchemaType, Clone, PartialEq)]
+pub struct Raffle {
+    registration_end: Timestamp,
+    winners: u32,
+    deposit: Amount,
+    salt_commitment: String,
+    /// Number of accounts registered
+    entries: u32,
+    /// Whether the winners have been drawn
+    drawn: bool,
+}
+
+/// A cap on the number of successful claims in each window of slot time.
+#[derive(Debug, Serialize, SchemaType, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    /// Max number of claims in a window
+    max_claims: u32,
+    /// Length of a window
+    window: Duration,
+}
+
+/// An account receiving a share of the sale proceeds.
+#[derive(Debug, Serialize, SchemaType, Clone, PartialEq)]
+pub struct Beneficiary {
+    account: AccountAddress,
+    /// Percentage of the proceeds paid to this account
+    share: u32,
+}
+
+/// A stage of the sale, such as a whitelist only phase followed by a public phase.
+#[derive(Debug, Serialize, SchemaType, Clone, PartialEq)]
+pub struct Phase {
+    /// Time at which the phase opens
+    start: Timestamp,
+    /// Time at which the phase closes
+    end: Timestamp,
+    /// Whether a whitelist proof is needed to claim during this phase
+    whitelist_required: bool,
+    /// Price in CCD of a single token during this phase
+    price: Amount,
+    /// Max number of nfts that can be claimed per address during this phase.  0 for no limit.
+    max_per_wallet: u32,
+}
+
+/// A whitelist tier given at init, such as an OG list alongside the public allow-list.
+#[derive(Debug, Serialize, SchemaType)]
+pub struct TierParams {
+    /// The addresses in this tier
+    whitelist: Vec<String>,
+    /// Max number of nfts that can be claimed per address in this tier.  0 for no limit.
+    max_per_wallet: u32,
+    /// Number of nfts held back for this tier
+    reserve: u32,
+    /// Time until which tokens claimed through this tier can't be transferred,
+    /// instead of the global unlock_time.  0 to use the global one.
+    unlock_time: u64,
 }
 
 /// The parameter type for the contract function `contract_claim_nft`.
 #[derive(Debug, Serialize, SchemaType)]
 pub struct ClaimNFTParams {
     proof: Vec<String>,
-    node: AccountAddress,
+    /// The claimer, which can be an account or a contract such as a DAO or smart-contract wallet
+    node: Address,
+    node_string: String,
+    selected_token: ContractTokenId,
+    amount_of_tokens: u32,
+    tier: Option<u32>,
+    /// The round to claim from.  Leave None to claim from the collection set up at init.
+    round: Option<RoundId>,
+    /// A voucher signed by the backend, used instead of a whitelist proof
+    voucher: Option<Voucher>,
+    /// The allocation encoded in the claimer's whitelist leaf.  Leave None for
+    /// whitelists of plain addresses.
+    max_claims: Option<u32>,
+}
+
+impl ClaimNFTParams {
+    /// The whitelist entry of the claimer.  Leaves with an allocation are the
+    /// address and the maximum number of claims joined by a colon.
+    fn leaf_node(&self) -> String {
+        match self.max_claims {
+            Some(max_claims) => format!("{}:{}", self.node_string, max_claims),
+            None => self.node_string.clone(),
+        }
+    }
+}
+
+/// The parameter type for the contract function `claim_next`.  The tokens are
+/// the next ones in sequential order, or drawn at random in random_index mode.
+#[derive(Debug, Serialize, SchemaType)]
+pub struct ClaimNextParams {
+    proof: Vec<String>,
+    node: Address,
+    node_string: String,
+    amount_of_tokens: u32,
+    tier: Option<u32>,
+    round: Option<RoundId>,
+    voucher: Option<Voucher>,
+    max_claims: Option<u32>,
+}
+
+impl From<ClaimNextParams> for ClaimNFTParams {
+    fn from(params: ClaimNextParams) -> Self {
+        ClaimNFTParams {
+            proof: params.proof,
+            node: params.node,
+            node_string: params.node_string,
+            selected_token: index_token_id(0),
+            amount_of_tokens: params.amount_of_tokens,
+            tier: params.tier,
+            round: params.round,
+            voucher: params.voucher,
+            max_claims: params.max_claims,
+        }
+    }
+}
+
+/// The parameter type for the contract function `claim_from_waitlist`.  The
+/// claimer is the sender.
+#[derive(Debug, Serialize, SchemaType)]
+pub struct WaitlistClaimParams {
+    /// The merkle proof of the claimer's whitelist entry.  Can be blank if the
+    /// claim needs no whitelist.
+    proof: Vec<String>,
+    node_string: String,
+    /// The token to claim, only used if selected_index is set
+    selected_token: ContractTokenId,
+    /// The allocation encoded in the claimer's whitelist leaf.  Leave None for
+    /// whitelists of plain addresses.
+    max_claims: Option<u32>,
+}
+
+/// The parameter type for the contract function `claim_selected`.
+#[derive(Debug, Serialize, SchemaType)]
+pub struct ClaimSelectedParams {
+    proof: Vec<String>,
+    node: Address,
     node_string: String,
+    /// The token to claim
     selected_token: ContractTokenId,
     amount_of_tokens: u32,
+    tier: Option<u32>,
+    voucher: Option<Voucher>,
+    max_claims: Option<u32>,
+}
+
+impl From<ClaimSelectedParams> for ClaimNFTParams {
+    fn from(params: ClaimSelectedParams) -> Self {
+        ClaimNFTParams {
+            proof: params.proof,
+            node: params.node,
+            node_string: params.node_string,
+            selected_token: params.selected_token,
+            amount_of_tokens: params.amount_of_tokens,
+            tier: params.tier,
+            round: None,
+            voucher: params.voucher,
+            max_claims: params.max_claims,
+        }
+    }
+}
+
+/// The message signed by the backend to vouch that an address can claim.
+#[derive(Debug, Serialize, SchemaType)]
+pub struct VoucherMessage {
+    /// The address of this contract, so the voucher cannot be used on another instance
+    contract_address: ContractAddress,
+    claimer: Address,
+    /// Total number of tokens the claimer can claim with vouchers
+    max_amount: u32,
+    /// Time at which the voucher expires
+    expiry: Timestamp,
+}
+
+/// A voucher signed by the backend with the key registered at init.
+#[derive(Debug, Serialize, SchemaType)]
+pub struct Voucher {
+    max_amount: u32,
+    expiry: Timestamp,
+    /// The ed25519 signature of the serialized `VoucherMessage`
+    signature: SignatureEd25519,
+}
+
+/// The message signed by the bridge to attest that a claim was made on another chain.
+#[derive(Debug, Serialize, SchemaType)]
+pub struct BridgeMessage {
+    /// The address of this contract, so the attestation cannot be used on another instance
+    contract_address: ContractAddress,
+    source_chain: String,
+    source_id: String,
+    claimer: Address,
+    amount: u32,
+}
+
+/// The parameter type for the contract function `claim_bridged`.
+#[derive(Debug, Serialize, SchemaType)]
+pub struct BridgeClaimParams {
+    /// Chain the claim was made on, such as "ethereum"
+    source_chain: String,
+    /// ID of the claim on the source chain, such as its transaction hash.  Each
+    /// can only be bridged once.
+    source_id: String,
+    /// Address receiving the tokens
+    claimer: Address,
+    /// Number of tokens claimed
+    amount: u32,
+    /// The ed25519 signature of the serialized `BridgeMessage` by the bridge key
+    signature: SignatureEd25519,
+}
+
+/// The parameter type for the contract function `view`.
+#[derive(Debug, Serialize, SchemaType)]
+pub struct ViewParams {
+    /// Token ID to start the page at, the first token ID when None
+    from: Option<ContractTokenId>,
+    /// Maximum number of token IDs to look up
+    limit: u32,
+}
+
+/// The parameter type for the contract function `snapshot`.
+#[derive(Debug, Serialize, SchemaType)]
+pub struct SnapshotParams {
+    /// Token ID to start the page of token owners at, the first token ID when None
+    from_token: Option<ContractTokenId>,
+    /// Number of edition balances and claims to skip
+    offset: u32,
+    /// Maximum number of token IDs to look up and of edition balances and claims to return
+    limit: u32,
 }
 
 #[derive(Serialize, SchemaType, PartialEq, Debug)]
@@ -53,6 +522,177 @@ struct ViewResult {
     metadata: String,
     whitelist: String,
     number_of_nfts: u32,
+    /// The claimed tokens and their owners among the token IDs looked up, ordered by token ID
+    claimed: Vec<(ContractTokenId, String)>,
+    /// Token ID to start the next page at, None after the last page
+    next: Option<ContractTokenId>,
+}
+
+/// The return type of the contract function `config`.
+#[derive(Serialize, SchemaType, PartialEq, Debug)]
+struct ConfigReply {
+    nft_limit: u32,
+    nft_limit_per_address: Option<u32>,
+    nft_reserve: Option<u32>,
+    reserve_release_time: Option<Timestamp>,
+    nft_time_limit: Option<Timestamp>,
+    nft_start_time: Option<Timestamp>,
+    /// Time until which claimed tokens are locked, unless their tier sets its own
+    unlock_time: Option<Timestamp>,
+    base_url: String,
+    metadata: String,
+    whitelist: String,
+    /// Whether claimers pick the index of the token they claim
+    selected_index: bool,
+    /// Whether token IDs are drawn at random
+    random_index: bool,
+    paused: bool,
+    /// Whether the airdrop has finished, either closed by the admin or past nft_time_limit
+    closed: bool,
+    mint_price: Amount,
+    phases: Vec<Phase>,
+    /// Number of whitelist tiers
+    tiers: u32,
+    soulbound: bool,
+    placeholder_url: String,
+    revealed: bool,
+    sponsored_claims: bool,
+    voucher_key: Option<PublicKeyEd25519>,
+    sorted_pairs: bool,
+    merkle_hash: MerkleHash,
+    beneficiaries: Vec<Beneficiary>,
+    marketplace: Option<ContractAddress>,
+    max_per_tx: Option<u32>,
+    one_claim_per_account: bool,
+    metadata_frozen: bool,
+    hex_token_ids: bool,
+    payment_token: Option<PaymentToken>,
+    raffle: Option<Raffle>,
+    rate_limit: Option<RateLimit>,
+    leaf_encoding: LeafEncoding,
+    supply_locked: bool,
+    token_gate: Option<TokenGate>,
+    identity_policy: Option<IdentityPolicy>,
+    admins: Vec<AccountAddress>,
+    admin_threshold: u32,
+    price_curve: Option<PriceCurve>,
+    bridge_key: Option<PublicKeyEd25519>,
+    airdrop_token: Option<AirdropToken>,
+    ccd_unit: Amount,
+    /// CCD left for claims in CCD airdrop mode
+    ccd_pool: Amount,
+}
+
+/// The return type of the contract function `contract_version`.
+#[derive(Serialize, SchemaType, PartialEq, Debug)]
+struct VersionReply {
+    /// Semantic version of the contract crate
+    version: String,
+    /// Version of the state layout
+    state_version: u32,
+    /// Whether the module was built with 64 bit token IDs
+    u64_token_ids: bool,
+    /// Whether claims cost CCD, at the mint price or in any phase
+    paid: bool,
+    soulbound: bool,
+    selected_index: bool,
+    random_index: bool,
+    editions: bool,
+}
+
+/// The return type of the contract function `verify_proof`.
+#[derive(Serial, SchemaType, PartialEq, Debug)]
+struct VerifyProofReply {
+    valid: bool,
+    /// Why the proof was rejected
+    reason: Option<Error>,
+}
+
+/// The return type of the contract function `remaining_supply`.
+#[derive(Serialize, SchemaType, PartialEq, Debug)]
+struct RemainingSupplyReply {
+    /// Number of tokens left to claim, including the reserved ones
+    remaining: u32,
+    /// Number of the remaining tokens only the whitelist or the tiers can claim
+    reserved: u32,
+}
+
+/// The parameter type for the contract function `claim_stats`.
+#[derive(Debug, Serialize, SchemaType)]
+pub struct ClaimStatsParams {
+    /// Slot time within the first day to return
+    from: Timestamp,
+    /// Number of days to return, at most 366
+    days: u32,
+}
+
+/// Claims made during a day of slot time.
+#[derive(Serialize, SchemaType, Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct DailyClaims {
+    /// Number of successful claims
+    claims: u32,
+    /// Number of tokens claimed
+    tokens: u32,
+}
+
+/// The return type of the contract function `dashboard`.
+#[derive(Serialize, SchemaType, PartialEq, Debug)]
+struct DashboardReply {
+    /// Number of tokens left to claim, including the reserved ones
+    remaining: u32,
+    /// Time left until nft_time_limit, None without a time limit
+    time_remaining: Option<Duration>,
+    /// Price of the next token claimed, None while no phase is open
+    current_price: Option<Amount>,
+    /// Whether the address is on the whitelist, None without a whitelist
+    whitelisted: Option<bool>,
+    /// Number of tokens the address has claimed
+    claimed: u32,
+    paused: bool,
+}
+
+/// The return type of the contract function `snapshot`.
+#[derive(Serialize, SchemaType, PartialEq, Debug)]
+struct SnapshotReply {
+    /// Version of the state layout the snapshot was taken from
+    version: u32,
+    config: ConfigReply,
+    next_token_id: u32,
+    /// Number of tokens claimed from the collection set up at init
+    claimed_count: u32,
+    /// Number of successful claims
+    claim_count: u64,
+    collected_funds: Amount,
+    /// The token owners among the token IDs looked up, ordered by token ID
+    token_owners: Vec<(ContractTokenId, Address)>,
+    /// Token ID to start the next page of token owners at, None after the last page
+    next_token: Option<ContractTokenId>,
+    /// A page of the (token ID, holder, copies) held of each edition, in the order the holders were added
+    edition_balances: Vec<(ContractTokenId, Address, u32)>,
+    /// A page of how many tokens each address has claimed, in the order the addresses first claimed
+    claims: Vec<(Address, u32)>,
+    /// Whether the edition balances or claims continue after this page
+    has_more: bool,
+}
+
+/// The return type of the contract function `claim_info`.
+#[derive(Serialize, SchemaType, PartialEq, Debug)]
+struct ClaimInfoReply {
+    /// The current owner of the token
+    owner: Address,
+    /// Slot time at which the token was claimed or minted
+    claimed_at: Timestamp,
+}
+
+/// The return type of the contract function `merkle_root`.
+#[derive(Serialize, SchemaType, PartialEq, Debug)]
+struct MerkleRootReply {
+    /// The hex encoded root hash
+    root: String,
+    /// Number of leaves in the tree, including the padding leaf for odd whitelists
+    length: u32,
+    /// Number of levels above the leaves, which is the number of hashes in a proof
+    depth: u32,
 }
 
 #[derive(Serialize, SchemaType, PartialEq, Debug)]
@@ -70,1172 +710,13137 @@ pub struct TokenParam {
 #[derive(Debug, Serialize, SchemaType)]
 pub struct BalanceParam {
     _dummy: i32, // without this the AccountAddress get corrupted
-    node: AccountAddress,
+    node: Address,
 }
 
-#[derive(Serial, Deserial, SchemaType, Clone)]
-pub struct MerkleTree {
-    length: u8,
-    hash_tree: Vec<String>,
-    hashroot: String,
-    steps: Vec<u8>,
+/// The parameter type for the contract function `set_whitelist`.
+#[derive(Debug, Serialize, SchemaType, Clone)]
+pub struct SetWhitelistParams {
+    whitelist: Vec<String>,
+    whitelist_file: String,
 }
 
-/// Your smart contract state.
-#[derive(Serial, DeserialWithState, StateClone)]
-#[concordium(state_parameter = "S")]
-pub struct State<S> {
-    /// Next token ID.  Used if the user is just claiming tokens in sequential order.
-    next_token_id: u32,
-    /// Map of taken indexes.  Used if the user is claiming specific indexes.
-    taken_indexes: Option<HashMap<ContractTokenId, String>>,
-    /// Map containing how many claims each address has made.
-    claimed_nfts: StateMap<AccountAddress, u32, S>,
-    /// Max number of nfts that can be minted before hitting reserve
+/// The parameter type for the contract function `set_nft_limit`.
+#[derive(Debug, Serialize, SchemaType)]
+pub struct SetNftLimitParams {
     nft_limit: u32,
-    /// Max number of nfts that can be claimed per address
-    nft_limit_per_address: Option<u32>,
-    /// Number of nfts which are held in reserve
-    nft_reserve: Option<u32>,
-    /// Airdrop time limit
-    nft_time_limit: Option<Timestamp>,
-    /// Whitelist proof
-    merkle_tree: Option<MerkleTree>,
-    /// Base url for these NFTs
-    base_url: String, // something like "https://some.example/token/";
-    /// Metadata URL in IPFS
-    metadata: String,
-    /// Whitelist URL in IPFS
-    whitelist: String,
 }
 
-impl<S: HasStateApi> State<S> {
-    /// Creates a new state with no tokens.
-    fn empty(state_builder: &mut StateBuilder<S>) -> Self {
-        State {
-            next_token_id: 0,
-            nft_limit: 1,
-            merkle_tree: None,
-            nft_time_limit: None,
-            nft_reserve: None,
-            base_url: String::new(),
-            metadata: String::new(),
-            whitelist: String::new(),
-            taken_indexes: None,
-            nft_limit_per_address: None,
-            claimed_nfts: state_builder.new_map(),
-        }
-    }
+/// The parameter type for the contract functions `add_to_denylist` and `remove_from_denylist`.
+#[derive(Debug, Serialize, SchemaType)]
+pub struct DenylistParams {
+    addresses: Vec<Address>,
+}
 
-    // Basic merkle tree implementation
-    // This will produce merkle trees like the following (note the real values would be hashed)
-    // Example 1 - input 1,2,3
-    //  1    2    3   3
-    //    12        33
-    //        1233
-    //
-    // Example 2 - input 1,2,3,4,5,6
-    // 1   2   3   4   5    6
-    //  12       34      56    56
-    //      1234           5656
-    //           12345656
-    pub fn create_hash_tree(&mut self, nodes: Vec<String>) {
-        let mut working_vec: Vec<String> = vec![];
-        for node in nodes {
-            working_vec.push(digest(node));
-        }
-        let mut working_node_total: usize = working_vec.len();
-        let mut steps: Vec<u8> = Vec::new();
+/// The parameter type for the contract function `add_whitelist_chunk`.
+#[derive(Debug, Serialize, SchemaType)]
+pub struct WhitelistChunkParams {
+    whitelist: Vec<String>,
+}
 
-        if working_vec.len() % 2 == 1 {
-            working_vec.push(working_vec[working_node_total - 1].clone());
-            working_node_total += 1;
-        }
+/// The parameter type for the contract function `finalize_whitelist`.
+#[derive(Debug, Serialize, SchemaType, Clone)]
+pub struct FinalizeWhitelistParams {
+    whitelist_file: String,
+}
 
-        let initial_length = working_node_total;
-        let mut startpoint = 0;
-        let mut vec_to_add: Vec<String> = Vec::new();
+/// The parameter type for the contract function `airdrop_to`.
+#[derive(Debug, Serialize, SchemaType)]
+pub struct AirdropParams {
+    recipients: Vec<(AccountAddress, ContractTokenId)>,
+}
 
-        loop {
-            // make sure tree is even
-            if working_node_total % 2 == 1 {
-                working_vec.push(working_vec.last().unwrap().clone());
-            }
+/// The parameter type for the contract function `claim_to`.
+#[derive(Debug, Serialize, SchemaType)]
+pub struct ClaimToParams {
+    /// The claim, checked against the claimer in `node`
+    claim: ClaimNFTParams,
+    /// The account, or contract and hook entrypoint, receiving the tokens
+    to: Receiver,
+    /// Additional data passed on to the receive hook
+    data: AdditionalData,
+}
 
-            for index in (startpoint..working_vec.len()).step_by(2) {
-                vec_to_add.push(digest(working_vec[index].clone() + &working_vec[index + 1]));
-            }
+/// The parameter type for the contract function `mint_reserve`.
+#[derive(Debug, Serialize, SchemaType)]
+pub struct MintReserveParams {
+    to: Receiver,
+    count: u32,
+    data: AdditionalData,
+}
 
-            startpoint = working_vec.len();
-            working_vec.append(&mut vec_to_add.clone());
-            working_node_total = working_vec.len();
+/// The parameter type for the contract function `set_partner_quota`.
+#[derive(Debug, Serialize, SchemaType)]
+pub struct PartnerQuotaParams {
+    /// Partner contract minting from the reserve
+    partner: ContractAddress,
+    /// Number of reserved tokens the partner can mint.  0 removes the partner.
+    quota: u32,
+}
 
-            if (vec_to_add.len()) / 2 == 1 {
-                steps.push((vec_to_add.len() + 1).try_into().unwrap());
-            } else {
-                steps.push((vec_to_add.len()).try_into().unwrap());
-            }
+/// The message signed off-chain by the account using `permit`.
+#[derive(Debug, Serialize, SchemaType)]
+pub struct PermitMessage {
+    /// The address of this contract, so the message cannot be replayed on another instance
+    contract_address: ContractAddress,
+    /// The current nonce of the signer
+    nonce: u64,
+    /// Time at which the message expires
+    timestamp: Timestamp,
+    /// The entrypoint to invoke on behalf of the signer
+    entry_point: OwnedEntrypointName,
+    /// The serialized parameter for the entrypoint
+    payload: Vec<u8>,
+}
 
-            if vec_to_add.len() == 1 {
-                self.merkle_tree = Some(MerkleTree {
-                    length: initial_length as u8,
-                    hashroot: working_vec.last().unwrap().clone(),
-                    steps,
-                    hash_tree: working_vec.clone(),
-                });
+/// The parameter type for the contract function `permit`.
+#[derive(Debug, Serialize, SchemaType)]
+pub struct PermitParam {
+    signature: SignatureEd25519,
+    signer: AccountAddress,
+    message: PermitMessage,
+}
 
-                return;
-            }
-            vec_to_add.clear();
-        }
-    }
+/// The parameter type for the contract function `supportsPermit`.
+#[derive(Debug, Serialize, SchemaType)]
+pub struct SupportsPermitQueryParams {
+    queries: Vec<OwnedEntrypointName>,
+}
 
-    // Use this to get the node chain for a given value.
-    // Returns None if the value is not found.
-    pub fn get_hash_proof(&self, test: String) -> Option<Vec<String>> {
-        self.merkle_tree.as_ref()?;
-        let local_tree = self.merkle_tree.as_ref().unwrap();
-
-        let steps = &local_tree.steps;
-        let mut end_point: usize = local_tree.length as usize;
-        let nodes: &Vec<String> = &local_tree.hash_tree;
-        let mut hunted: String = test;
-        let mut startpoint: usize = 0;
-        let mut step_number = 0;
-        let mut proof: Vec<String> = Vec::new();
-        let mut index = 0;
-        while startpoint + index < end_point {
-            if hunted == local_tree.hashroot {
-                proof.push(hunted);
-                return Some(proof);
-            }
+/// The parameter type for the contract function `register_public_keys`.
+#[derive(Debug, Serialize, SchemaType)]
+pub struct RegisterPublicKeysParams {
+    /// The key the sending account signs permit messages with
+    key: PublicKeyEd25519,
+}
 
-            if nodes[startpoint + index] == hunted {
-                proof.push(hunted);
-                if index % 2 == 1 {
-                    // it is on the right hand side
-                    hunted =
-                        digest(nodes[startpoint + index - 1].clone() + &nodes[startpoint + index]);
-                } else {
-                    // it is on the left hand side
-                    hunted =
-                        digest(nodes[startpoint + index].clone() + &nodes[startpoint + index + 1]);
-                }
-                startpoint = end_point;
-                end_point += steps[step_number] as usize;
-                step_number += 1;
-                index = 0;
-                continue;
-            }
+/// The parameter type for the contract function `withdraw`.
+#[derive(Debug, Serialize, SchemaType, Clone)]
+pub struct WithdrawParams {
+    amount: Amount,
+    to: AccountAddress,
+}
 
-            index += 1;
-        }
-        None
-    }
+/// The parameter type for the contract function `reveal`.
+#[derive(Debug, Serialize, SchemaType)]
+pub struct RevealParams {
+    base_url: String,
+}
 
-    // Use this to compare the user's proof with our's
-    pub fn check_proof(&self, test: &ClaimNFTParams) -> bool {
-        let claimer = digest(test.node_string.clone());
+/// The parameter type for the contract function `upgrade`.
+#[derive(Debug, Serialize, SchemaType, Clone)]
+pub struct UpgradeParams {
+    /// The new module to use for this contract instance
+    module: ModuleReference,
+    /// Optional entrypoint of the new module to call afterwards, with its parameter.
+    /// Used to migrate the state to the layout of the new module.
+    migrate: Option<(OwnedEntrypointName, OwnedParameter)>,
+}
 
-        let master_proof = self.get_hash_proof(claimer);
-        if master_proof.is_none() {
-            return false;
-        }
-        master_proof.unwrap() == test.proof
-    }
+/// The parameter type for the contract function `create_round`.
+#[derive(Debug, Serialize, SchemaType)]
+pub struct CreateRoundParams {
+    nft_limit: u32,
+    nft_limit_per_address: u32,
+    start_time: u64,
+    end_time: u64,
+    whitelist: Vec<String>,
+    base_url: String,
+    mint_price: Amount,
+}
 
-    // Checks to see whether a given value is in the tree
-    // Generally used in testing
-    pub fn check_hash_value(&self, test_address: String) -> bool {
-        if self.merkle_tree.is_none() {
-            return false;
-        };
-        let tree = self.merkle_tree.as_ref().unwrap();
-
-        let steps = &tree.steps;
-        let mut end_point = tree.length as usize;
-        let nodes = &tree.hash_tree;
-        let mut hunted = test_address;
-        let mut startpoint = 0;
-        let mut step_number = 0;
-
-        let mut index: usize = 0;
-        while startpoint + index < end_point {
-            if hunted.eq(&tree.hashroot) {
-                return true;
+/// A sensitive admin operation.  When admins are set at init these can only be
+/// done by proposing them with `propose_action`, and run once enough admins
+/// have approved them.
+#[derive(Debug, Serialize, SchemaType, Clone)]
+pub enum AdminAction {
+    Withdraw(WithdrawParams),
+    SetWhitelist(SetWhitelistParams),
+    FinalizeWhitelist(FinalizeWhitelistParams),
+    /// Approves the upgrade, which any admin can then run with `upgrade`
+    Upgrade(UpgradeParams),
+    /// Sends the CCD left unclaimed in CCD airdrop mode to the account
+    SweepCcdAirdrop(AccountAddress),
+}
+
+/// An admin action waiting for approvals.
+#[derive(Debug, Serialize, SchemaType)]
+pub struct PendingAction {
+    action: AdminAction,
+    /// The admins which have approved the action, starting with the proposer
+    approvals: Vec<AccountAddress>,
+}
+
+/// The parameter type for the contract functions `reveal_random_salt` and `draw`.
+#[derive(Debug, Serialize, SchemaType)]
+pub struct RandomSaltParams {
+    salt: String,
+}
+
+/// Event logged when the admin withdraws CCD from the contract.
+#[derive(Debug, Serialize, SchemaType, PartialEq)]
+pub struct WithdrawEvent {
+    amount: Amount,
+    to: AccountAddress,
+}
+
+/// Tag of the CIS-3 Nonce event, below the tags of the CIS-2 events.
+const NONCE_EVENT_TAG: u8 = 250;
+
+/// The CIS-3 standard for sponsored transactions, which `permit` follows.
+const CIS3_STANDARD_IDENTIFIER: StandardIdentifier<'static> =
+    StandardIdentifier::new_unchecked("CIS-3");
+
+/// Event logged when a permit message is executed, following CIS-3.  The
+/// standard fixes its tag, so it is logged on its own rather than as an
+/// AirdropEvent.
+#[derive(Debug, PartialEq)]
+pub struct NonceEvent {
+    /// Nonce of the executed message
+    nonce: u64,
+    /// Account which signed the message
+    sponsoree: AccountAddress,
+}
+
+impl Serial for NonceEvent {
+    fn serial<W: Write>(&self, out: &mut W) -> Result<(), W::Err> {
+        NONCE_EVENT_TAG.serial(out)?;
+        self.nonce.serial(out)?;
+        self.sponsoree.serial(out)
+    }
+}
+
+/// Event logged when the whitelist is replaced after init.
+#[derive(Debug, Serialize, SchemaType, PartialEq)]
+pub struct WhitelistUpdatedEvent {
+    /// The new merkle root, or None if the whitelist was removed
+    root: Option<String>,
+    /// The IPFS link for the new whitelist file
+    whitelist_file: String,
+}
+
+/// Event logged when the admin creates a new airdrop round.
+#[derive(Debug, Serialize, SchemaType, PartialEq)]
+pub struct RoundCreatedEvent {
+    round_id: RoundId,
+    /// The first token ID of the round
+    first_token_id: ContractTokenId,
+}
+
+/// Event logged on every successful claim, alongside the CIS-2 Mint events.
+#[derive(Debug, Serialize, SchemaType, PartialEq)]
+pub struct ClaimEvent {
+    /// The first token ID minted by the claim
+    token_id: ContractTokenId,
+    claimer: Address,
+    /// Number of tokens minted by the claim
+    amount: u32,
+    slot_time: Timestamp,
+    /// Position of the claim among all claims made, starting at 1
+    claim_number: u64,
+}
+
+/// Event logged when a claim takes the last token of the collection set up at init.
+#[derive(Debug, Serialize, SchemaType, PartialEq)]
+pub struct SoldOutEvent {
+    /// Number of successful claims, including the final one
+    claim_count: u64,
+    slot_time: Timestamp,
+}
+
+/// Event logged when an address is added to or removed from the denylist.
+#[derive(Debug, Serialize, SchemaType, PartialEq)]
+pub struct DenylistUpdatedEvent {
+    address: Address,
+    /// Whether the address is now denied
+    denied: bool,
+}
+
+/// Event logged when a holder burns a token for a new random one.
+#[derive(Debug, Serialize, SchemaType, PartialEq)]
+pub struct RerollEvent {
+    burned: ContractTokenId,
+    minted: ContractTokenId,
+    owner: Address,
+}
+
+/// Event logged when an admin proposes or approves an admin action.
+#[derive(Debug, Serialize, SchemaType, PartialEq)]
+pub struct ActionApprovedEvent {
+    action_id: u64,
+    admin: AccountAddress,
+    /// Number of admins which have approved the action so far
+    approvals: u32,
+    /// Whether the action was run by this approval
+    executed: bool,
+}
+
+/// Event logged when the admin changes the supply cap of the collection.
+#[derive(Debug, Serialize, SchemaType, PartialEq)]
+pub struct NftLimitUpdatedEvent {
+    /// The cap before the change
+    previous: u32,
+    nft_limit: u32,
+}
+
+/// Event logged on every successful claim of the airdropped token, alongside
+/// the CIS-2 Transfer event logged by the token contract.
+#[derive(Debug, Serialize, SchemaType, PartialEq)]
+pub struct TokensClaimedEvent {
+    claimer: Address,
+    /// Number of allocation units claimed
+    units: u32,
+    /// Amount of the airdropped token transferred
+    amount: TokenAmountU64,
+    slot_time: Timestamp,
+    /// Position of the claim among all claims made, starting at 1
+    claim_number: u64,
+}
+
+/// Event logged on every successful claim in CCD airdrop mode.
+#[derive(Debug, Serialize, SchemaType, PartialEq)]
+pub struct CcdClaimedEvent {
+    claimer: AccountAddress,
+    /// Number of allocation units claimed
+    units: u32,
+    amount: Amount,
+    slot_time: Timestamp,
+    /// Position of the claim among all claims made, starting at 1
+    claim_number: u64,
+}
+
+/// Contract specific events.  These are logged alongside the CIS-2 events,
+/// whose tags start at 255 and count down, so the tags here must stay small.
+#[derive(Debug, Serial, SchemaType, PartialEq)]
+pub enum AirdropEvent {
+    WhitelistUpdated(WhitelistUpdatedEvent),
+    Withdraw(WithdrawEvent),
+    RoundCreated(RoundCreatedEvent),
+    Claim(ClaimEvent),
+    DenylistUpdated(DenylistUpdatedEvent),
+    SoldOut(SoldOutEvent),
+    NftLimitUpdated(NftLimitUpdatedEvent),
+    ActionApproved(ActionApprovedEvent),
+    Reroll(RerollEvent),
+    TokensClaimed(TokensClaimedEvent),
+    CcdClaimed(CcdClaimedEvent),
+}
+
+#[derive(Serial, Deserial, SchemaType, Clone)]
+pub struct MerkleTree {
+    length: u32,
+    hash_tree: Vec<String>,
+    hashroot: String,
+    steps: Vec<u32>,
+    /// Whether sibling pairs are hashed in sorted order rather than left to right
+    sorted_pairs: bool,
+    /// Hash function the tree is built with
+    hash: MerkleHash,
+}
+
+/// A shuffle of the metadata of the collection set up at init, derived from the
+/// seed revealed after mint-out.  Token index i is given the metadata at index
+/// (multiplier * i + offset) % size, which is a permutation as the multiplier
+/// is coprime with size.
+#[derive(Serial, Deserial, Clone)]
+pub struct MetadataShuffle {
+    /// Number of tokens shuffled, the nft_limit when the seed was revealed
+    size: u32,
+    multiplier: u32,
+    offset: u32,
+}
+
+impl MetadataShuffle {
+    /// Derives the shuffle from the seed.  The seed's own hash is the public
+    /// commitment, so the shuffle is taken from the hash of the seed and size.
+    fn new(seed: &str, size: u32) -> Self {
+        let hash = digest(format!("{}:{}", seed, size));
+        let size = size.max(1);
+        let mut multiplier = (u32::from_str_radix(&hash[..8], 16).unwrap_or(0) % size).max(1);
+        while gcd(multiplier, size) != 1 {
+            multiplier = multiplier % (size - 1) + 1;
+        }
+        MetadataShuffle {
+            size,
+            multiplier,
+            offset: u32::from_str_radix(&hash[8..16], 16).unwrap_or(0) % size,
+        }
+    }
+
+    /// Returns the metadata index of a token index.  Tokens past the shuffled
+    /// ones, added by raising nft_limit later, keep their own metadata.
+    fn metadata_index(&self, index: u64) -> u64 {
+        let size = u64::from(self.size);
+        if index >= size {
+            return index;
+        }
+        (u64::from(self.multiplier) * index + u64::from(self.offset)) % size
+    }
+}
+
+/// Greatest common divisor of two numbers.
+fn gcd(a: u32, b: u32) -> u32 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// A whitelist tier with its own merkle tree and entitlements.
+#[derive(Serial, Deserial, Clone)]
+pub struct Tier {
+    merkle_tree: MerkleTree,
+    /// Max number of nfts that can be claimed per address in this tier.  0 for no limit.
+    max_per_wallet: u32,
+    /// Number of nfts held back for this tier
+    reserve: u32,
+    /// Number of nfts claimed through this tier
+    claimed: u32,
+    /// Time until which tokens claimed through this tier are locked, if it
+    /// overrides the global one
+    unlock_time: Option<Timestamp>,
+}
+
+/// A later airdrop round, run from the same instance as the collection set up at init.
+#[derive(Serial, Deserial, Clone)]
+pub struct Round {
+    /// Max number of nfts that can be claimed in this round
+    nft_limit: u32,
+    /// Max number of nfts that can be claimed per address in this round
+    nft_limit_per_address: Option<u32>,
+    /// Time at which the round opens
+    start_time: Timestamp,
+    /// Time at which the round closes
+    end_time: Option<Timestamp>,
+    /// Whitelist proof.  Anyone can claim when this is None.
+    merkle_tree: Option<MerkleTree>,
+    /// Base url for the NFTs of this round
+    base_url: String,
+    /// Price in CCD of a single token
+    mint_price: Amount,
+    /// Number of nfts claimed in this round
+    minted: u32,
+}
+
+/// Hash function used to build the whitelist merkle trees.  Proofs are
+/// checked with the host's crypto primitive for it.
+#[derive(Debug, Serialize, SchemaType, Clone, Copy, PartialEq, Eq)]
+pub enum MerkleHash {
+    Sha256,
+    Keccak256,
+    Sha3_256,
+}
+
+/// How the mint price rises as the collection is claimed.  The price of a
+/// token starts at mint_price and goes up by `increment`, counted on the
+/// number of tokens claimed before it.
+#[derive(Debug, Serialize, SchemaType, Clone, Copy, PartialEq, Eq)]
+pub enum PriceCurve {
+    /// The price goes up with every token claimed
+    Linear { increment: Amount },
+    /// The price goes up once every `step` tokens claimed
+    Step { step: u32, increment: Amount },
+}
+
+/// How an account is written in the whitelist leaves.
+#[derive(Debug, Serialize, SchemaType, Clone, Copy, PartialEq, Eq)]
+pub enum LeafEncoding {
+    /// The node_string supplied by the claimer, as whitelisted.  Kept for
+    /// existing whitelists; the contract writes accounts as lowercase hex.
+    NodeString,
+    /// The 32 bytes of the account address
+    Raw,
+    /// The account address as lowercase hex
+    Hex,
+    /// The base58check address wallets show, such as `3XSLuJcX...`
+    Base58Check,
+}
+
+impl LeafEncoding {
+    /// Encodes a whitelist entry.  Raw entries are given as hex and decoded.
+    /// Entries with an allocation keep their `:max_claims` suffix as text.
+    fn entry_leaf(self, entry: &str) -> Vec<u8> {
+        merkle_core::LeafEncoding::from(self).entry_leaf(entry)
+    }
+
+    /// Encodes an address.  Contracts are written as `<index,subindex>` whatever
+    /// the encoding, and NodeString writes accounts as lowercase hex.
+    fn address_leaf(self, address: Address) -> Vec<u8> {
+        match (self, address) {
+            (LeafEncoding::Raw, Address::Account(account)) => account.0.to_vec(),
+            (LeafEncoding::Base58Check, Address::Account(account)) => {
+                account_address_to_base58(account).into_bytes()
             }
+            _ => address_to_string(address).into_bytes(),
+        }
+    }
 
-            if nodes[startpoint + index] == hunted {
-                if index % 2 == 1 {
-                    // it is on the right hand side
-                    hunted =
-                        digest(nodes[startpoint + index - 1].clone() + &nodes[startpoint + index]);
-                } else {
-                    // it is on the left hand side
-                    hunted =
-                        digest(nodes[startpoint + index].clone() + &nodes[startpoint + index + 1]);
+    /// Encodes the claimer's address without its allocation.
+    fn node_leaf(self, params: &ClaimNFTParams) -> Vec<u8> {
+        match self {
+            LeafEncoding::NodeString => params.node_string.as_bytes().to_vec(),
+            _ => self.address_leaf(params.node),
+        }
+    }
+
+    /// Encodes the claimer's whitelist entry, with its allocation if it has one.
+    fn claimer_leaf(self, params: &ClaimNFTParams) -> Vec<u8> {
+        match self {
+            LeafEncoding::NodeString => params.leaf_node().into_bytes(),
+            _ => {
+                let mut leaf = self.address_leaf(params.node);
+                if let Some(max_claims) = params.max_claims {
+                    leaf.extend_from_slice(format!(":{}", max_claims).as_bytes());
                 }
-                startpoint = end_point;
-                end_point += steps[step_number] as usize;
-                step_number += 1;
-                index = 0;
-                continue;
+                leaf
             }
-
-            index += 1;
         }
-        false
     }
 }
 
-/// Your smart contract errors.
-#[derive(Debug, PartialEq, Eq, Reject, Serial, SchemaType)]
-enum Error {
-    /// Failed parsing the parameter.
-    #[from(ParseError)]
-    NFTLimitReached,
-    AddressNotOnWhitelist,
-    AirdropNowClosed,
-    MintingLogMalformed,
-    MintingLogFull,
-    MetaDataLogMalformed,
-    MetaDataLogFull,
-    IndexAlreadyClaimed,
+impl From<LeafEncoding> for merkle_core::LeafEncoding {
+    fn from(encoding: LeafEncoding) -> Self {
+        match encoding {
+            LeafEncoding::NodeString => merkle_core::LeafEncoding::NodeString,
+            LeafEncoding::Raw => merkle_core::LeafEncoding::Raw,
+            LeafEncoding::Hex => merkle_core::LeafEncoding::Hex,
+            LeafEncoding::Base58Check => merkle_core::LeafEncoding::Base58Check,
+        }
+    }
 }
 
-/// Init function that creates a new smart contract.
-#[init(contract = "airdrop_project", parameter = "InitParams")]
-fn init<S: HasStateApi>(
-    ctx: &impl HasInitContext,
-    state_builder: &mut StateBuilder<S>,
-) -> InitResult<State<S>> {
-    let params: InitParams = ctx.parameter_cursor().get()?;
-    let mut state: State<S> = State::empty(state_builder);
+impl From<MerkleHash> for merkle_core::MerkleHash {
+    fn from(hash: MerkleHash) -> Self {
+        match hash {
+            MerkleHash::Sha256 => merkle_core::MerkleHash::Sha256,
+            MerkleHash::Keccak256 => merkle_core::MerkleHash::Keccak256,
+            MerkleHash::Sha3_256 => merkle_core::MerkleHash::Sha3_256,
+        }
+    }
+}
 
-    state.nft_limit = params.nft_limit;
-    state.base_url = params.base_url;
+impl MerkleHash {
+    /// Hashes an encoded whitelist entry into a leaf of the tree.
+    fn hash_leaf(self, node: impl AsRef<[u8]>) -> String {
+        merkle_core::MerkleHash::from(self).hash_leaf(node)
+    }
 
-    state.metadata = params.metadata;
-    state.whitelist = params.whitelist_file;
+    /// Hashes some bytes with the host's crypto primitive, giving lowercase hex.
+    fn digest_with(self, crypto_primitives: &impl HasCryptoPrimitives, input: &[u8]) -> String {
+        let hash = match self {
+            MerkleHash::Sha256 => crypto_primitives.hash_sha2_256(input).0,
+            MerkleHash::Keccak256 => crypto_primitives.hash_keccak_256(input).0,
+            MerkleHash::Sha3_256 => crypto_primitives.hash_sha3_256(input).0,
+        };
+        merkle_core::encode_hex(&hash)
+    }
+}
 
-    if params.nft_time_limit != 0 {
-        state.nft_time_limit = Some(Timestamp::from_timestamp_millis(params.nft_time_limit));
+impl MerkleTree {
+    // Builds the tree with merkle_core, as the merkle_tree tool does, so the
+    // proofs the tool gives are the ones checked here.  See merkle_core for
+    // the layout.  With sorted_pairs each pair is hashed smallest first, as
+    // OpenZeppelin style trees do.  An empty whitelist gives an empty tree.
+    pub fn new(
+        nodes: Vec<String>,
+        sorted_pairs: bool,
+        hash: MerkleHash,
+        encoding: LeafEncoding,
+    ) -> Self {
+        let leaves: Vec<String> = nodes
+            .iter()
+            .map(|node| hash.hash_leaf(encoding.entry_leaf(node)))
+            .collect();
+        let tree = merkle_core::FlatTree::build(leaves, |level| {
+            merkle_core::hash_level(level, sorted_pairs, hash.into())
+        })
+        .unwrap_or_default();
+        MerkleTree {
+            length: tree.length as u32,
+            hashroot: tree.hash_tree.last().cloned().unwrap_or_default(),
+            steps: tree.steps.iter().map(|step| *step as u32).collect(),
+            hash_tree: tree.hash_tree,
+            sorted_pairs,
+            hash,
+        }
     }
 
-    if params.nft_limit_per_address != 0 {
-        state.nft_limit_per_address = Some(params.nft_limit_per_address);
+    /// Checks a claimer's proof for the given encoded whitelist entry, hashing
+    /// with the host's crypto primitives.
+    pub fn check_proof(
+        &self,
+        leaf_node: &[u8],
+        proof: &[String],
+        crypto_primitives: &impl HasCryptoPrimitives,
+    ) -> bool {
+        let digest = |input: &[u8]| self.hash.digest_with(crypto_primitives, input);
+        !proof.is_empty()
+            && merkle_core::path_with(
+                &self.hash_tree,
+                self.length as usize,
+                &self.steps,
+                digest(&merkle_core::leaf_input(leaf_node)),
+                self.sorted_pairs,
+                digest,
+            )
+            .as_deref()
+                == Some(proof)
     }
 
-    if params.reserve != 0 {
-        state.nft_reserve = Some(params.reserve);
+    /// Rejects proofs longer than a path through the tree, or holding anything
+    /// but lowercase hex hashes, before any of them is hashed.
+    fn validate_proof(&self, proof: &[String]) -> Result<(), Error> {
+        let well_formed = |node: &String| {
+            node.len() == 64
+                && node
+                    .bytes()
+                    .all(|byte| matches!(byte, b'0'..=b'9' | b'a'..=b'f'))
+        };
+        if proof.len() > self.steps.len() + 1 || !proof.iter().all(well_formed) {
+            return Err(Error::InvalidProof);
+        }
+        Ok(())
     }
 
-    if params.selected_index {
-        state.taken_indexes = Some(HashMap::default());
+    // Use this to get the node chain for a given value.
+    // Returns None if the value is not found.
+    pub fn get_hash_proof(&self, test: String) -> Option<Vec<String>> {
+        merkle_core::path(
+            &self.hash_tree,
+            self.length as usize,
+            &self.steps,
+            test,
+            self.sorted_pairs,
+            self.hash.into(),
+        )
     }
 
-    if !params.whitelist.is_empty() {
-        state.create_hash_tree(params.whitelist);
+    // Checks to see whether a given value is in the tree
+    // Generally used in testing
+    pub fn check_hash_value(&self, test_address: String) -> bool {
+        self.get_hash_proof(test_address).is_some()
     }
+}
 
-    Ok(state)
+/// Your smart contract state.
+#[derive(Serial, DeserialWithState, StateClone)]
+#[concordium(state_parameter = "S")]
+pub struct State<S> {
+    /// Version of the state layout.  This is kept as the first field so a
+    /// migration in a later module can read it before the rest of the state.
+    version: u32,
+    /// Next token ID.  Used if the user is just claiming tokens in sequential order.
+    next_token_id: u32,
+    /// Whether the user is claiming specific indexes rather than claiming in sequential order.
+    selected_index: bool,
+    /// Map of taken indexes to the owner string supplied by the claimer.
+    /// Used if the user is claiming specific indexes.
+    taken_indexes: StateMap<ContractTokenId, String, S>,
+    /// Number of tokens claimed by index, counting every copy of an edition
+    taken_count: u32,
+    /// Ranges of indexes only the whitelist can select, when claiming by index
+    reserved_ranges: Vec<IndexRange>,
+    /// Whether token IDs are drawn at random from the unclaimed ones
+    random_index: bool,
+    /// SHA256 hash of the salt used to seed the random draws, committed at init
+    random_salt_commitment: String,
+    /// The salt used to seed the random draws, once revealed by the admin
+    random_salt: Option<String>,
+    /// Token IDs swapped into drawn positions of the random pool.
+    /// Positions without an entry hold their own index.
+    random_pool: StateMap<u32, u32, S>,
+    /// Number of token IDs drawn from the random pool
+    random_drawn: u32,
+    /// Key of the backend which signs claim vouchers.  Vouchers are rejected when this is None.
+    voucher_key: Option<PublicKeyEd25519>,
+    /// Map containing how many tokens each address has claimed with vouchers.
+    voucher_claims: StateMap<Address, u32, S>,
+    /// Number of copies of each token ID, indexed by token ID.  When this is not
+    /// empty each token ID is an edition which can have many holders.
+    edition_sizes: Vec<u32>,
+    /// Map of how many copies of each edition have been minted.
+    edition_minted: StateMap<ContractTokenId, u32, S>,
+    /// Map of how many copies of each edition each address holds.
+    edition_balances: StateMap<(ContractTokenId, Address), u32, S>,
+    /// Keys of edition_balances in the order they were added, so snapshot can page through them.
+    edition_holders: StateMap<u32, (ContractTokenId, Address), S>,
+    /// Number of entries in edition_holders.
+    edition_holder_count: u32,
+    /// Whether merkle trees hash sibling pairs in sorted order
+    sorted_pairs: bool,
+    /// Hash function merkle trees are built with
+    merkle_hash: MerkleHash,
+    /// How accounts are written in the whitelist leaves
+    leaf_encoding: LeafEncoding,
+    /// Map containing how many claims each address has made.
+    claimed_nfts: StateMap<Address, u32, S>,
+    /// Keys of claimed_nfts in the order they were added, so snapshot can page through them.
+    claim_addresses: StateMap<u32, Address, S>,
+    /// Number of entries in claim_addresses.
+    claim_address_count: u32,
+    /// Whether each address can only claim once from the collection set up at init
+    one_claim_per_account: bool,
+    /// Set of the addresses which have claimed, when only one claim is allowed
+    claimants: StateSet<Address, S>,
+    /// Max number of nfts that can be minted before hitting reserve
+    nft_limit: u32,
+    /// Max number of nfts that can be claimed per address
+    nft_limit_per_address: Option<u32>,
+    /// Number of nfts which are held in reserve
+    nft_reserve: Option<u32>,
+    /// Time after which unclaimed reserve tokens can be claimed by anyone
+    reserve_release_time: Option<Timestamp>,
+    /// Number of reserve tokens each address can still claim from its allocation
+    reserve_allocations: StateMap<Address, u32, S>,
+    /// Total of the unclaimed reserve allocations
+    reserve_allocated: u32,
+    /// Airdrop time limit
+    nft_time_limit: Option<Timestamp>,
+    /// Time at which the airdrop opens
+    nft_start_time: Option<Timestamp>,
+    /// Time until which claimed tokens are locked, unless their tier sets its own
+    unlock_time: Option<Timestamp>,
+    /// Whitelist proof
+    merkle_tree: Option<MerkleTree>,
+    /// Base url for these NFTs
+    base_url: String, // something like "https://some.example/token/";
+    /// Whether token IDs are written in URLs as little-endian hex, as CIS-2 encodes
+    /// them, rather than as decimal numbers.  Kept for collections hosted that way.
+    hex_token_ids: bool,
+    /// Metadata URL in IPFS
+    metadata: String,
+    /// Whitelist URL in IPFS
+    whitelist: String,
+    /// Whether claiming is currently paused by the admin
+    paused: bool,
+    /// Whether the admin has ended the airdrop.  This can not be undone.
+    closed: bool,
+    /// Price in CCD of a single token
+    mint_price: Amount,
+    /// Token claims are paid in instead of CCD, if any
+    payment_token: Option<PaymentToken>,
+    /// Token claimers have to hold to be eligible, if any
+    token_gate: Option<TokenGate>,
+    /// Identity attribute claimers have to reveal, with its blocked values
+    identity_policy: Option<IdentityPolicy>,
+    /// Admins approving the sensitive admin actions.  When empty the contract
+    /// owner runs them directly.
+    admins: Vec<AccountAddress>,
+    /// Number of distinct admins which have to approve an action
+    admin_threshold: u32,
+    /// Admin actions waiting for approvals, by action ID
+    pending_actions: StateMap<u64, PendingAction, S>,
+    /// ID of the next proposed admin action
+    next_action_id: u64,
+    /// Upgrade approved by the admins, which any admin can then run
+    approved_upgrade: Option<UpgradeParams>,
+    /// How the mint price rises with the supply claimed, if at all
+    price_curve: Option<PriceCurve>,
+    /// Key of the bridge which attests claims made on other chains.  Bridged
+    /// claims are rejected when this is None.
+    bridge_key: Option<PublicKeyEd25519>,
+    /// Claims already bridged, by source chain and ID on that chain
+    bridged_claims: StateSet<(String, String), S>,
+    /// Token claims transfer instead of minting NFTs, if any
+    airdrop_token: Option<AirdropToken>,
+    /// CCD sent for each unit of an allocation, or 0 outside of CCD airdrop mode
+    ccd_unit: Amount,
+    /// CCD funded for claims in CCD airdrop mode, less what has been claimed
+    ccd_pool: Amount,
+    /// CCD received from paid claims
+    collected_funds: Amount,
+    /// Number of successful claims, numbering the ClaimEvents
+    claim_count: u64,
+    /// Claims made each day, by day of slot time since the epoch
+    daily_claims: StateMap<u64, DailyClaims, S>,
+    /// Number of reserved tokens each partner contract can still mint
+    partner_quotas: StateMap<ContractAddress, u32, S>,
+    /// Accounts the proceeds are split between.  When empty the admin withdraws them.
+    beneficiaries: Vec<Beneficiary>,
+    /// Map of how much CCD has been paid out to each beneficiary
+    released: StateMap<AccountAddress, Amount, S>,
+    /// Marketplace contract made an operator of every claimer
+    marketplace: Option<ContractAddress>,
+    /// Max number of nfts that can be claimed in a single transaction
+    max_per_tx: Option<u32>,
+    /// Cap on the number of claims in each window of slot time
+    rate_limit: Option<RateLimit>,
+    /// Index of the window the last claim was made in, counting from the epoch
+    rate_window: u64,
+    /// Number of claims made in that window
+    rate_window_claims: u32,
+    /// Sale phases.  When empty the whitelist and reserve settings decide who can claim.
+    phases: Vec<Phase>,
+    /// Map containing how many claims each address has made in each phase.
+    phase_claims: StateMap<(u32, Address), u32, S>,
+    /// Whitelist tiers, each with their own merkle tree, per address limit and reserve.
+    tiers: Vec<Tier>,
+    /// Map containing how many claims each address has made in each tier.
+    tier_claims: StateMap<(u32, Address), u32, S>,
+    /// Whether tokens are non-transferable badges.  Burning is still allowed.
+    soulbound: bool,
+    /// Map of the current owner of each token.
+    token_owners: StateMap<ContractTokenId, Address, S>,
+    /// Slot time at which each token with a single owner was claimed or minted
+    claimed_at: StateMap<ContractTokenId, Timestamp, S>,
+    /// Time until which each locked token can't be transferred
+    token_unlock_times: StateMap<ContractTokenId, Timestamp, S>,
+    /// Set of (owner, operator) pairs.  Operators can transfer and burn tokens on behalf of the owner.
+    operators: StateSet<(Address, Address), S>,
+    /// Map of the next permit nonce for each account.
+    nonces: StateMap<AccountAddress, u64, S>,
+    /// Map of the keys used to verify permit signatures for each account.
+    public_keys: StateMap<AccountAddress, PublicKeyEd25519, S>,
+    /// Whitelist entries uploaded with `add_whitelist_chunk` waiting to be
+    /// built into the merkle tree by `finalize_whitelist`
+    pending_whitelist: Vec<String>,
+    /// Metadata URL served for every token until the collection is revealed
+    placeholder_url: String,
+    /// Whether the real metadata URLs are being served
+    revealed: bool,
+    /// Whether the metadata URLs are fixed for good.  This can not be undone.
+    metadata_frozen: bool,
+    /// Whether nft_limit is fixed for good and the reserve can no longer be
+    /// minted by the admin.  This can not be undone.
+    supply_locked: bool,
+    /// SHA256 hashes of the token metadata, indexed by token ID
+    metadata_hashes: Vec<[u8; 32]>,
+    /// SHA256 hash of the concatenated metadata hashes in token ID order, committed at init
+    provenance_hash: Option<String>,
+    /// SHA256 hash of the seed which shuffles the metadata, committed at init
+    shuffle_seed_commitment: Option<String>,
+    /// The metadata shuffle, once the seed has been revealed
+    metadata_shuffle: Option<MetadataShuffle>,
+    /// Whether `claim_nft` can be sent by an account other than the claimer,
+    /// such as a relayer paying for the transaction
+    sponsored_claims: bool,
+    /// Airdrop rounds created after init, by round ID
+    rounds: StateMap<RoundId, Round, S>,
+    /// Number of rounds created after init
+    round_count: u32,
+    /// Map containing how many claims each address has made in each round.
+    round_claims: StateMap<(RoundId, Address), u32, S>,
+    /// Addresses waiting for freed tokens after the drop sold out, by queue position
+    waitlist: StateMap<u32, Address, S>,
+    /// Set of the addresses currently on the waitlist
+    waitlisted: StateSet<Address, S>,
+    /// Queue position of the first waiting address
+    waitlist_head: u32,
+    /// Queue position the next address to join the waitlist gets
+    waitlist_tail: u32,
+    /// Sequential token IDs which were burned, to be claimed again from the waitlist
+    freed_token_ids: StateSet<ContractTokenId, S>,
+    /// Number of IDs in freed_token_ids
+    freed_count: u32,
+    /// Set of addresses which can not claim or transfer tokens, even if whitelisted
+    denylist: StateSet<Address, S>,
+    /// Raffle deciding who can claim, if any
+    raffle: Option<Raffle>,
+    /// Accounts registered for the raffle, by registration order.  The draw
+    /// shuffles the winners to the front.
+    raffle_entries: StateMap<u32, AccountAddress, S>,
+    /// Registered accounts which have not been drawn or refunded yet
+    raffle_registered: StateSet<AccountAddress, S>,
+    /// Accounts drawn as raffle winners
+    raffle_winners: StateSet<AccountAddress, S>,
+    /// Raffle deposits paid in, less those of the winners and the refunds
+    raffle_deposits: Amount,
+    /// Whether the SoldOut event has been logged for the current nft_limit
+    sold_out_logged: bool,
 }
 
-/// Claims an NFT
-#[receive(
-    contract = "airdrop_project",
-    name = "claim_nft",
-    parameter = "ClaimNFTParams",
-    error = "Error",
-    mutable,
-    enable_logger
-)]
-fn claim_nft<S: HasStateApi>(
-    ctx: &impl HasReceiveContext,
-    host: &mut impl HasHost<State<S>, StateApiType = S>,
-    logger: &mut impl HasLogger,
-) -> Result<(), Error> {
-    let state = host.state_mut();
+impl<S: HasStateApi> State<S> {
+    /// Creates a new state with no tokens.
+    fn empty(state_builder: &mut StateBuilder<S>) -> Self {
+        State {
+            version: STATE_VERSION,
+            next_token_id: 0,
+            nft_limit: 1,
+            merkle_tree: None,
+            nft_time_limit: None,
+            nft_start_time: None,
+            unlock_time: None,
+            nft_reserve: None,
+            reserve_release_time: None,
+            reserve_allocations: state_builder.new_map(),
+            reserve_allocated: 0,
+            base_url: String::new(),
+            hex_token_ids: false,
+            metadata: String::new(),
+            whitelist: String::new(),
+            selected_index: false,
+            taken_indexes: state_builder.new_map(),
+            taken_count: 0,
+            reserved_ranges: Vec::new(),
+            random_index: false,
+            random_salt_commitment: String::new(),
+            random_salt: None,
+            random_pool: state_builder.new_map(),
+            random_drawn: 0,
+            voucher_key: None,
+            voucher_claims: state_builder.new_map(),
+            edition_sizes: Vec::new(),
+            edition_minted: state_builder.new_map(),
+            edition_balances: state_builder.new_map(),
+            edition_holders: state_builder.new_map(),
+            edition_holder_count: 0,
+            sorted_pairs: false,
+            merkle_hash: MerkleHash::Sha256,
+            leaf_encoding: LeafEncoding::NodeString,
+            nft_limit_per_address: None,
+            claimed_nfts: state_builder.new_map(),
+            claim_addresses: state_builder.new_map(),
+            claim_address_count: 0,
+            one_claim_per_account: false,
+            claimants: state_builder.new_set(),
+            paused: false,
+            closed: false,
+            pending_whitelist: Vec::new(),
+            mint_price: Amount::zero(),
+            payment_token: None,
+            token_gate: None,
+            identity_policy: None,
+            admins: Vec::new(),
+            admin_threshold: 0,
+            pending_actions: state_builder.new_map(),
+            next_action_id: 0,
+            approved_upgrade: None,
+            price_curve: None,
+            bridge_key: None,
+            bridged_claims: state_builder.new_set(),
+            airdrop_token: None,
+            ccd_unit: Amount::zero(),
+            ccd_pool: Amount::zero(),
+            collected_funds: Amount::zero(),
+            claim_count: 0,
+            daily_claims: state_builder.new_map(),
+            partner_quotas: state_builder.new_map(),
+            beneficiaries: Vec::new(),
+            released: state_builder.new_map(),
+            marketplace: None,
+            max_per_tx: None,
+            rate_limit: None,
+            rate_window: 0,
+            rate_window_claims: 0,
+            phases: Vec::new(),
+            phase_claims: state_builder.new_map(),
+            tiers: Vec::new(),
+            tier_claims: state_builder.new_map(),
+            soulbound: false,
+            token_owners: state_builder.new_map(),
+            claimed_at: state_builder.new_map(),
+            token_unlock_times: state_builder.new_map(),
+            operators: state_builder.new_set(),
+            nonces: state_builder.new_map(),
+            public_keys: state_builder.new_map(),
+            placeholder_url: String::new(),
+            revealed: true,
+            metadata_frozen: false,
+            supply_locked: false,
+            metadata_hashes: Vec::new(),
+            provenance_hash: None,
+            shuffle_seed_commitment: None,
+            metadata_shuffle: None,
+            sponsored_claims: false,
+            rounds: state_builder.new_map(),
+            round_count: 0,
+            round_claims: state_builder.new_map(),
+            waitlist: state_builder.new_map(),
+            waitlisted: state_builder.new_set(),
+            waitlist_head: 0,
+            waitlist_tail: 0,
+            freed_token_ids: state_builder.new_set(),
+            freed_count: 0,
+            denylist: state_builder.new_set(),
+            raffle: None,
+            raffle_entries: state_builder.new_map(),
+            raffle_registered: state_builder.new_set(),
+            raffle_winners: state_builder.new_set(),
+            raffle_deposits: Amount::zero(),
+            sold_out_logged: false,
+        }
+    }
 
-    if let Some(time_limit) = state.nft_time_limit {
-        if time_limit > Timestamp::from_timestamp_millis(0)
-            && ctx.metadata().slot_time() > state.nft_time_limit.unwrap()
-        {
-            return Err(Error::AirdropNowClosed);
+    /// Returns the owner string of a token, or None if it has not been claimed.
+    /// Tokens claimed by index keep the string supplied by the claimer.
+    fn owner_string(&self, token_id: &ContractTokenId) -> Option<String> {
+        if let Some(owner) = self.taken_indexes.get(token_id) {
+            return Some(owner.as_str().to_string());
         }
+        self.token_owners
+            .get(token_id)
+            .map(|owner| address_to_string(*owner))
     }
 
-    let params: ClaimNFTParams = ctx.parameter_cursor().get()?;
-    let current_token_id = state.next_token_id;
-    let amount_of_tokens = params.amount_of_tokens;
-    if current_token_id + params.amount_of_tokens > state.nft_limit {
-        return Err(Error::NFTLimitReached);
+    /// Formats a token ID for its metadata URL.
+    fn token_id_string(&self, token_id: ContractTokenId) -> String {
+        if self.hex_token_ids {
+            token_id.to_string()
+        } else {
+            token_id.0.to_string()
+        }
     }
 
-    if let Some(max_claims_per_address) = state.nft_limit_per_address {
-        let val = *(state.claimed_nfts.entry(params.node).or_insert(0));
-        if val + amount_of_tokens > max_claims_per_address {
-            return Err(Error::NFTLimitReached);
-        };
+    /// Builds a token's URL from a base URL.  Base URLs containing an `{id}`
+    /// placeholder are templates, such as `ipfs://CID/{id}.json`, and `{id:N}`
+    /// pads the ID with zeros to N digits.  Otherwise the ID is appended.
+    fn format_url(&self, base_url: &str, token_id: ContractTokenId) -> String {
+        let id = self.token_id_string(token_id);
+        let placeholder = base_url.find("{id").and_then(|start| {
+            let end = start + base_url[start..].find('}')?;
+            Some(&base_url[start..=end])
+        });
+        match placeholder {
+            Some(placeholder) => {
+                let width = placeholder[3..placeholder.len() - 1]
+                    .strip_prefix(':')
+                    .and_then(|width| width.parse::<usize>().ok())
+                    .unwrap_or(0);
+                base_url.replace(placeholder, &format!("{:0>width$}", id, width = width))
+            }
+            None => format!("{}{}", base_url, id),
+        }
     }
 
-    // if there is a whitelist and no reserve only whitelist can by
-    // if there is no whitelist everyone can buy
-    // if there is a reserve and a whitelist only whitelist can by reserve
-    // Presence of a whitelist is determined by the presence of the merkle tree
-    if ((state.merkle_tree.is_some() && state.nft_reserve.is_none())  // whitelist and no reserve
-        || (state.merkle_tree.is_some()
-            && state.next_token_id + amount_of_tokens > (state.nft_limit - state.nft_reserve.unwrap_or(0))))  // whitelist and only reserve left
-        && (params.proof.is_empty() || !state.check_proof(&params))
-    {
-        return Err(Error::AddressNotOnWhitelist);
+    /// Returns the metadata URL for a token, which is the placeholder until the
+    /// collection is revealed.  The hash is only given for the real metadata.
+    /// With a shuffle committed at init the collection stays hidden until the
+    /// seed is revealed, and its tokens are then given the shuffled metadata.
+    fn metadata_url(&self, token_id: ContractTokenId) -> MetadataUrl {
+        let shuffle_revealed =
+            self.shuffle_seed_commitment.is_none() || self.metadata_shuffle.is_some();
+        if self.revealed && shuffle_revealed {
+            let round_id = round_of(&token_id);
+            let (base_url, metadata_token_id) = if round_id == 0 {
+                let metadata_token_id = match self.metadata_shuffle.as_ref() {
+                    Some(shuffle) => {
+                        let index = shuffle.metadata_index(token_index(&token_id));
+                        index_token_id(index as u32)
+                    }
+                    None => token_id,
+                };
+                (self.base_url.clone(), metadata_token_id)
+            } else {
+                let base_url = self
+                    .rounds
+                    .get(&round_id)
+                    .map(|round| round.base_url.clone())
+                    .unwrap_or_default();
+                (base_url, token_id)
+            };
+            MetadataUrl {
+                url: self.format_url(&base_url, metadata_token_id),
+                hash: self
+                    .metadata_hashes
+                    .get(metadata_token_id.0 as usize)
+                    .copied(),
+            }
+        } else {
+            MetadataUrl {
+                url: self.placeholder_url.clone(),
+                hash: None,
+            }
+        }
     }
 
-    // This is where the code differentiates between the user claiming the next available token
-    // and the user claiming a specific one they have requested.
-    let token_id_to_use = if state.taken_indexes.is_some() {
-        if state
-            .taken_indexes
-            .as_ref()
-            .unwrap()
-            .contains_key(&params.selected_token)
-        {
-            return Err(Error::IndexAlreadyClaimed);
+    /// Adds to the number of tokens claimed by an address, recording the
+    /// address in claim_addresses the first time it claims.
+    fn add_claimed(&mut self, address: Address, tokens: u32) {
+        if self.claimed_nfts.get(&address).is_none() {
+            self.claim_addresses
+                .insert(self.claim_address_count, address);
+            self.claim_address_count += 1;
         }
-        params.selected_token
-    } else {
-        ContractTokenId::from(current_token_id)
-    };
+        let mut claimed = self.claimed_nfts.entry(address).or_insert(0);
+        *claimed += tokens;
+    }
 
-    // Event for minted token.
-    let log_mint_result = logger.log(&Cis2Event::Mint(MintEvent {
-        token_id: token_id_to_use,
-        amount: ContractTokenAmount::from(amount_of_tokens),
-        owner: concordium_std::Address::Account(params.node),
-    }));
+    /// Adds copies of an edition to the balance of a holder, recording the
+    /// holder in edition_holders the first time it holds the edition.
+    fn add_edition_balance(&mut self, token_id: ContractTokenId, holder: Address, copies: u32) {
+        if self.edition_balances.get(&(token_id, holder)).is_none() {
+            self.edition_holders
+                .insert(self.edition_holder_count, (token_id, holder));
+            self.edition_holder_count += 1;
+        }
+        let mut balance = self.edition_balances.entry((token_id, holder)).or_insert(0);
+        *balance += copies;
+    }
+
+    /// Returns the first token ID from the given index of a round on which
+    /// can have been claimed, moving on to the next round at the end of each
+    /// one.  None once past the last round.
+    fn claimable_token_id(&self, mut round_id: RoundId, mut index: u32) -> Option<ContractTokenId> {
+        while round_id <= self.round_count {
+            let size = if round_id == 0 {
+                if self.is_edition_mode() {
+                    // Editions have many holders and no single owner to list
+                    0
+                } else {
+                    self.nft_limit.max(self.next_token_id).max(self.taken_count)
+                }
+            } else {
+                self.rounds.get(&round_id).map_or(0, |round| round.minted)
+            };
+            if index < size {
+                return Some(round_token_id(round_id, index));
+            }
+            round_id += 1;
+            index = 0;
+        }
+        None
+    }
+
+    /// Returns whether tokens are recorded in `taken_indexes` rather than claimed in sequential order.
+    fn tracks_indexes(&self) -> bool {
+        self.selected_index || self.random_index
+    }
+
+    /// Returns whether the token IDs are editions with many holders.
+    fn is_edition_mode(&self) -> bool {
+        !self.edition_sizes.is_empty()
+    }
+
+    /// Returns whether the token has been minted.
+    fn token_exists(&self, token_id: &ContractTokenId) -> bool {
+        self.token_owners.get(token_id).is_some() || self.edition_minted.get(token_id).is_some()
+    }
+
+    /// Returns how many copies of the token the address holds.
+    fn token_balance(&self, token_id: &ContractTokenId, address: &Address) -> ContractTokenAmount {
+        match self.token_owners.get(token_id) {
+            Some(owner) if *owner == *address => ContractTokenAmount::from(1),
+            Some(_) => ContractTokenAmount::from(0),
+            None => ContractTokenAmount::from(
+                self.edition_balances
+                    .get(&(*token_id, *address))
+                    .map_or(0, |balance| *balance),
+            ),
+        }
+    }
+
+    /// Returns how many more copies of an edition can be minted.
+    fn edition_remaining(&self, token_id: &ContractTokenId) -> u32 {
+        let size = self
+            .edition_sizes
+            .get(token_id.0 as usize)
+            .copied()
+            .unwrap_or(0);
+        let minted = self
+            .edition_minted
+            .get(token_id)
+            .map_or(0, |minted| *minted);
+        size.saturating_sub(minted)
+    }
+
+    /// Checks that `amount_of_tokens` copies of the token can still be minted.
+    /// Always succeeds when the token IDs are not editions.
+    fn check_edition_supply(
+        &self,
+        token_id: &ContractTokenId,
+        amount_of_tokens: u32,
+    ) -> Result<(), Error> {
+        if self.is_edition_mode() && amount_of_tokens > self.edition_remaining(token_id) {
+            return Err(Error::EditionSoldOut);
+        }
+        Ok(())
+    }
+
+    /// Returns the number of tokens which have been claimed so far.
+    fn claimed_count(&self) -> u32 {
+        if self.tracks_indexes() {
+            self.taken_count
+        } else {
+            self.next_token_id
+        }
+    }
+
+    /// Returns the number of tokens which are held, leaving out the burned
+    /// sequential IDs, which still count as claimed as burns never lower next_token_id.
+    fn held_count(&self) -> u32 {
+        self.claimed_count() - self.freed_count
+    }
+
+    /// Returns the price of the next `amount_of_tokens` tokens outside of
+    /// phases, following the price curve if there is one.  None on overflow.
+    fn total_price(&self, amount_of_tokens: u32) -> Option<u64> {
+        let claimed = self.claimed_count();
+        (0..amount_of_tokens).try_fold(0u64, |total, offset| {
+            let steps = match self.price_curve {
+                None => 0,
+                Some(PriceCurve::Linear { increment }) => {
+                    u64::from(claimed + offset).checked_mul(increment.micro_ccd)?
+                }
+                Some(PriceCurve::Step { step, increment }) => {
+                    u64::from((claimed + offset) / step).checked_mul(increment.micro_ccd)?
+                }
+            };
+            total.checked_add(self.mint_price.micro_ccd.checked_add(steps)?)
+        })
+    }
+
+    /// Returns whether the token is in one of the index ranges only the whitelist can claim.
+    fn is_reserved_index(&self, token_id: &ContractTokenId) -> bool {
+        let index = token_index(token_id);
+        self.selected_index
+            && self
+                .reserved_ranges
+                .iter()
+                .any(|range| u64::from(range.start) <= index && index <= u64::from(range.end))
+    }
+
+    /// Returns the ID of the token to mint next.
+    /// This is where the code differentiates between the user claiming the next available token
+    /// and the user claiming a specific one they have requested.
+    fn token_id_to_use(&self, selected_token: ContractTokenId) -> Result<ContractTokenId, Error> {
+        if self.selected_index {
+            // Editions are bounded by edition_sizes rather than nft_limit
+            if !self.is_edition_mode() && token_index(&selected_token) >= u64::from(self.nft_limit)
+            {
+                return Err(Error::InvalidTokenIndex);
+            }
+            if self.taken_indexes.get(&selected_token).is_some() {
+                return Err(Error::IndexAlreadyClaimed);
+            }
+            Ok(selected_token)
+        } else {
+            Ok(index_token_id(self.next_token_id))
+        }
+    }
+
+    /// Returns the lowest token ID which has not been claimed yet.
+    fn next_free_token_id(&self) -> ContractTokenId {
+        if self.is_edition_mode() {
+            let mut index: u32 = 0;
+            while (index as usize) < self.edition_sizes.len()
+                && self.edition_remaining(&index_token_id(index)) == 0
+            {
+                index += 1;
+            }
+            index_token_id(index)
+        } else if self.tracks_indexes() {
+            let mut index: u32 = 0;
+            while self.taken_indexes.get(&index_token_id(index)).is_some() {
+                index += 1;
+            }
+            index_token_id(index)
+        } else {
+            index_token_id(self.next_token_id)
+        }
+    }
+
+    /// Draws a token ID at random from the ones which have not been claimed yet.
+    /// The pool is shuffled lazily: the drawn position is filled with the last
+    /// remaining ID.  IDs already taken by reserve mints or airdrops are skipped.
+    fn draw_random_token_id(&mut self, slot_time: Timestamp) -> Result<ContractTokenId, Error> {
+        let salt = self
+            .random_salt
+            .clone()
+            .ok_or(Error::RandomSaltNotRevealed)?;
+        loop {
+            let remaining = self.nft_limit.saturating_sub(self.random_drawn);
+            if remaining == 0 {
+                return Err(Error::NFTLimitReached);
+            }
+
+            let seed = digest(format!(
+                "{}{}{}",
+                salt,
+                slot_time.timestamp_millis(),
+                self.random_drawn
+            ));
+            let position = u32::from_str_radix(&seed[..8], 16).unwrap_or(0) % remaining;
+            let last = remaining - 1;
+
+            let token_id = self.random_pool.get(&position).map_or(position, |id| *id);
+            let last_id = self.random_pool.get(&last).map_or(last, |id| *id);
+            self.random_pool.insert(position, last_id);
+            self.random_pool.remove(&last);
+            self.random_drawn += 1;
+
+            let token_id = index_token_id(token_id);
+            if self.taken_indexes.get(&token_id).is_none() {
+                return Ok(token_id);
+            }
+        }
+    }
+
+    /// Returns the index of the phase which is open at the given time.
+    fn active_phase(&self, now: Timestamp) -> Result<u32, Error> {
+        match self
+            .phases
+            .iter()
+            .position(|phase| phase.start <= now && now < phase.end)
+        {
+            Some(index) => Ok(index as u32),
+            None if self.phases.iter().any(|phase| now < phase.start) => {
+                Err(Error::AirdropNotStarted)
+            }
+            None => Err(Error::AirdropNowClosed),
+        }
+    }
+
+    /// Checks whether the reserves have been released to the public.
+    fn reserve_released(&self, slot_time: Timestamp) -> bool {
+        self.reserve_release_time
+            .is_some_and(|release_time| slot_time >= release_time)
+    }
+
+    /// Returns the number of tokens still held back for whitelist tiers.
+    fn unclaimed_tier_reserve(&self) -> u32 {
+        self.tiers
+            .iter()
+            .map(|tier| tier.reserve.saturating_sub(tier.claimed))
+            .sum()
+    }
+
+    /// The settings of the airdrop, as returned by the `config` view.
+    fn config(&self, slot_time: Timestamp) -> ConfigReply {
+        ConfigReply {
+            nft_limit: self.nft_limit,
+            nft_limit_per_address: self.nft_limit_per_address,
+            nft_reserve: self.nft_reserve,
+            reserve_release_time: self.reserve_release_time,
+            nft_time_limit: self.nft_time_limit,
+            nft_start_time: self.nft_start_time,
+            unlock_time: self.unlock_time,
+            base_url: self.base_url.clone(),
+            metadata: self.metadata.clone(),
+            whitelist: self.whitelist.clone(),
+            selected_index: self.selected_index,
+            random_index: self.random_index,
+            paused: self.paused,
+            closed: self.closed || self.nft_time_limit.is_some_and(|limit| slot_time > limit),
+            mint_price: self.mint_price,
+            phases: self.phases.clone(),
+            tiers: self.tiers.len() as u32,
+            soulbound: self.soulbound,
+            placeholder_url: self.placeholder_url.clone(),
+            revealed: self.revealed,
+            sponsored_claims: self.sponsored_claims,
+            voucher_key: self.voucher_key,
+            sorted_pairs: self.sorted_pairs,
+            merkle_hash: self.merkle_hash,
+            beneficiaries: self.beneficiaries.clone(),
+            marketplace: self.marketplace,
+            max_per_tx: self.max_per_tx,
+            one_claim_per_account: self.one_claim_per_account,
+            metadata_frozen: self.metadata_frozen,
+            hex_token_ids: self.hex_token_ids,
+            payment_token: self.payment_token.clone(),
+            raffle: self.raffle.clone(),
+            rate_limit: self.rate_limit,
+            leaf_encoding: self.leaf_encoding,
+            supply_locked: self.supply_locked,
+            token_gate: self.token_gate.clone(),
+            identity_policy: self.identity_policy.clone(),
+            admins: self.admins.clone(),
+            admin_threshold: self.admin_threshold,
+            price_curve: self.price_curve,
+            bridge_key: self.bridge_key,
+            airdrop_token: self.airdrop_token.clone(),
+            ccd_unit: self.ccd_unit,
+            ccd_pool: self.ccd_pool,
+        }
+    }
+
+    /// Checks whether the address can claim `amount_of_tokens` more tokens.
+    fn check_address_limit(&self, address: &Address, amount_of_tokens: u32) -> Result<(), Error> {
+        if let Some(max_claims_per_address) = self.nft_limit_per_address {
+            let val = self.claimed_nfts.get(address).map_or(0, |claimed| *claimed);
+            if val + amount_of_tokens > max_claims_per_address {
+                return Err(Error::WalletLimitReached);
+            };
+        }
+        Ok(())
+    }
+
+    /// Checks the per address limits of the collection set up at init, set by
+    /// nft_limit_per_address and one_claim_per_account.
+    fn check_claimer_limits(&self, address: &Address, amount_of_tokens: u32) -> Result<(), Error> {
+        self.check_address_limit(address, amount_of_tokens)?;
+        if self.one_claim_per_account && self.claimants.contains(address) {
+            return Err(Error::AlreadyClaimed);
+        }
+        Ok(())
+    }
+
+    /// Checks the conditions every claimer has to meet: in raffle mode only the
+    /// drawn winners can claim, and nobody can while a whitelist upload is unfinished.
+    fn check_claim_eligibility(&self, claimer: &Address) -> Result<(), Error> {
+        if let Some(raffle) = self.raffle.as_ref() {
+            if !raffle.drawn {
+                return Err(Error::RaffleNotDrawn);
+            }
+            let winner = match claimer {
+                Address::Account(account) => self.raffle_winners.contains(account),
+                Address::Contract(_) => false,
+            };
+            if !winner {
+                return Err(Error::NotRaffleWinner);
+            }
+        }
+        if !self.pending_whitelist.is_empty() {
+            return Err(Error::WhitelistNotFinalized);
+        }
+        Ok(())
+    }
+
+    /// Returns whether a claim of the collection set up at init has to prove the
+    /// claimer is on the whitelist.
+    fn whitelist_required(
+        &self,
+        active_phase: Option<u32>,
+        selected_token: &ContractTokenId,
+        amount_of_tokens: u32,
+        slot_time: Timestamp,
+    ) -> bool {
+        let whitelist_required = match active_phase {
+            Some(index) => self.phases[index as usize].whitelist_required,
+            // if there is a whitelist and no reserve only whitelist can by
+            // if there is no whitelist everyone can buy
+            // if there is a reserve and a whitelist only whitelist can by reserve
+            // Presence of a whitelist is determined by the presence of the merkle tree
+            // Once the reserve is released anyone can claim what is left of it
+            None => {
+                // whitelist and no reserve
+                (self.merkle_tree.is_some() && self.nft_reserve.is_none())
+                    // whitelist and only reserve left
+                    || (self.merkle_tree.is_some()
+                        && !self.reserve_released(slot_time)
+                        && self.next_token_id + amount_of_tokens
+                            > (self.nft_limit - self.nft_reserve.unwrap_or(0)))
+            }
+        };
+        whitelist_required
+            || (self.is_reserved_index(selected_token) && !self.reserve_released(slot_time))
+    }
+
+    /// Checks that the slot time is within the claim window of the collection
+    /// set up at init.
+    fn check_claim_window(&self, slot_time: Timestamp) -> Result<(), Error> {
+        if let Some(time_limit) = self.nft_time_limit {
+            if time_limit > Timestamp::from_timestamp_millis(0) && slot_time > time_limit {
+                return Err(Error::AirdropNowClosed);
+            }
+        }
+        if let Some(start_time) = self.nft_start_time {
+            if slot_time < start_time {
+                return Err(Error::AirdropNotStarted);
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the number of tokens which can be claimed without a tier, as the
+    /// tokens held back for the tiers are left until the reserve is released.
+    fn open_supply(&self, slot_time: Timestamp) -> u32 {
+        let tier_reserve = if self.reserve_released(slot_time) {
+            0
+        } else {
+            self.unclaimed_tier_reserve()
+        };
+        self.nft_limit.saturating_sub(tier_reserve)
+    }
+
+    /// The last tokens are held for the reserve allocations, which only their
+    /// addresses can claim until the reserve is released.  Returns how many of
+    /// the claimed tokens come out of the address's allocation.
+    fn reserve_allocation_used(
+        &self,
+        address: &Address,
+        amount_of_tokens: u32,
+        slot_time: Timestamp,
+    ) -> Result<u32, Error> {
+        if self.reserve_allocated == 0 || self.reserve_released(slot_time) {
+            return Ok(0);
+        }
+        let used = (self.held_count() + amount_of_tokens)
+            .saturating_sub(self.nft_limit.saturating_sub(self.reserve_allocated));
+        let allocation = self
+            .reserve_allocations
+            .get(address)
+            .map_or(0, |allocation| *allocation);
+        if used > allocation {
+            return Err(Error::ReserveAllocationExceeded);
+        }
+        Ok(used)
+    }
+
+    /// Returns the active phase, if the drop has phases, after checking the
+    /// address can claim `amount_of_tokens` more tokens in it.
+    fn check_phase_limit(
+        &self,
+        address: &Address,
+        amount_of_tokens: u32,
+        slot_time: Timestamp,
+    ) -> Result<Option<u32>, Error> {
+        if self.phases.is_empty() {
+            return Ok(None);
+        }
+        let index = self.active_phase(slot_time)?;
+        let phase = &self.phases[index as usize];
+        if phase.max_per_wallet != 0 {
+            let val = self
+                .phase_claims
+                .get(&(index, *address))
+                .map_or(0, |claimed| *claimed);
+            if val + amount_of_tokens > phase.max_per_wallet {
+                return Err(Error::WalletLimitReached);
+            }
+        }
+        Ok(Some(index))
+    }
+
+    /// Counts a claim against the phase, one_claim_per_account and reserve
+    /// allocation limits it was checked against.
+    fn record_claim_limits(
+        &mut self,
+        address: Address,
+        amount_of_tokens: u32,
+        active_phase: Option<u32>,
+        reserve_allocation_used: u32,
+    ) {
+        if let Some(index) = active_phase {
+            let mut phase_tokens = self.phase_claims.entry((index, address)).or_insert(0);
+            *phase_tokens += amount_of_tokens;
+        }
+        if self.one_claim_per_account {
+            self.claimants.insert(address);
+        }
+        if reserve_allocation_used > 0 {
+            *self.reserve_allocations.entry(address).or_insert(0) -= reserve_allocation_used;
+            self.reserve_allocated -= reserve_allocation_used;
+        }
+    }
+
+    /// Returns whether an address is listed more than once in a whitelist,
+    /// counting entries which only differ in their allocation or in the case of hex.
+    fn has_duplicates(&self, whitelist: &[String]) -> bool {
+        let mut addresses: Vec<Vec<u8>> = whitelist
+            .iter()
+            .map(|entry| {
+                let address = entry
+                    .split_once(':')
+                    .map_or(entry.as_str(), |(address, _)| address);
+                self.leaf_encoding.entry_leaf(address)
+            })
+            .collect();
+        addresses.sort_unstable();
+        addresses.windows(2).any(|pair| pair[0] == pair[1])
+    }
+
+    /// Builds the whitelist merkle tree from the given addresses.
+    pub fn create_hash_tree(&mut self, nodes: Vec<String>) {
+        self.merkle_tree = Some(MerkleTree::new(
+            nodes,
+            self.sorted_pairs,
+            self.merkle_hash,
+            self.leaf_encoding,
+        ));
+    }
+
+    // Use this to get the node chain for a given value.
+    // Returns None if the value is not found.
+    pub fn get_hash_proof(&self, test: String) -> Option<Vec<String>> {
+        self.merkle_tree.as_ref()?.get_hash_proof(test)
+    }
+
+    // Use this to compare the user's proof with our's
+    pub fn check_proof(
+        &self,
+        test: &ClaimNFTParams,
+        crypto_primitives: &impl HasCryptoPrimitives,
+    ) -> bool {
+        match self.merkle_tree.as_ref() {
+            Some(tree) => tree.check_proof(
+                &self.leaf_encoding.claimer_leaf(test),
+                &test.proof,
+                crypto_primitives,
+            ),
+            None => false,
+        }
+    }
+
+    /// Checks the size and format of a claim's proof against the tree the claim
+    /// is for, so garbage input fails before any of it is hashed.
+    fn validate_proof(&self, params: &ClaimNFTParams) -> Result<(), Error> {
+        if let Some(round_id) = params.round {
+            if let Some(round) = self.rounds.get(&round_id) {
+                if let Some(tree) = round.merkle_tree.as_ref() {
+                    tree.validate_proof(&params.proof)?;
+                }
+            }
+        } else if let Some(tier_index) = params.tier {
+            if let Some(tier) = self.tiers.get(tier_index as usize) {
+                tier.merkle_tree.validate_proof(&params.proof)?;
+            }
+        } else if let Some(tree) = self.merkle_tree.as_ref() {
+            tree.validate_proof(&params.proof)?;
+        }
+        Ok(())
+    }
+
+    /// Checks the proof of a claim against the tree the claim is for: the round's,
+    /// the tier's or the main whitelist.  Claims against a round or collection
+    /// without a whitelist need no proof.
+    fn verify_proof(
+        &self,
+        params: &ClaimNFTParams,
+        crypto_primitives: &impl HasCryptoPrimitives,
+    ) -> Result<(), Error> {
+        self.validate_proof(params)?;
+        let valid = if let Some(round_id) = params.round {
+            let round = self.rounds.get(&round_id).ok_or(Error::InvalidRound)?;
+            round.merkle_tree.as_ref().is_none_or(|tree| {
+                tree.check_proof(
+                    &self.leaf_encoding.node_leaf(params),
+                    &params.proof,
+                    crypto_primitives,
+                )
+            })
+        } else if let Some(tier_index) = params.tier {
+            let tier = self
+                .tiers
+                .get(tier_index as usize)
+                .ok_or(Error::InvalidTier)?;
+            tier.merkle_tree.check_proof(
+                &self.leaf_encoding.claimer_leaf(params),
+                &params.proof,
+                crypto_primitives,
+            )
+        } else {
+            self.merkle_tree.is_none() || self.check_proof(params, crypto_primitives)
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(Error::AddressNotOnWhitelist)
+        }
+    }
+
+    // Checks to see whether a given value is in the tree
+    // Generally used in testing
+    pub fn check_hash_value(&self, test_address: String) -> bool {
+        match self.merkle_tree.as_ref() {
+            Some(tree) => tree.check_hash_value(test_address),
+            None => false,
+        }
+    }
+
+    /// Returns whether the address has a leaf in the stored whitelist.
+    fn is_whitelisted(&self, address: Address) -> bool {
+        let leaf = self
+            .merkle_hash
+            .hash_leaf(self.leaf_encoding.address_leaf(address));
+        self.check_hash_value(leaf)
+    }
+
+    /// Returns the price in CCD of the next token claimed, following the open
+    /// phase or the price curve.
+    fn current_price(&self, now: Timestamp) -> Result<Amount, Error> {
+        if !self.phases.is_empty() {
+            let index = self.active_phase(now)?;
+            return Ok(self.phases[index as usize].price);
+        }
+        let price = self.total_price(1).ok_or(Error::InsufficientFunds)?;
+        Ok(Amount::from_micro_ccd(price))
+    }
+}
+
+/// Your smart contract errors.
+#[derive(Debug, PartialEq, Eq, Reject, Serial, SchemaType)]
+enum Error {
+    NFTLimitReached,
+    AddressNotOnWhitelist,
+    AirdropNowClosed,
+    MintingLogMalformed,
+    MintingLogFull,
+    MetaDataLogMalformed,
+    MetaDataLogFull,
+    IndexAlreadyClaimed,
+    Unauthorized,
+    ContractPaused,
+    LogFull,
+    LogMalformed,
+    WhitelistNotFinalized,
+    InsufficientFunds,
+    #[from(TransferError)]
+    TransferFailed,
+    WalletLimitReached,
+    AirdropNotStarted,
+    InvalidTier,
+    UnknownToken,
+    TokenIsSoulbound,
+    InvalidTransfer,
+    WrongContract,
+    NonceMismatch,
+    PermitExpired,
+    MissingPublicKey,
+    WrongSignature,
+    WrongEntrypoint,
+    ReceiveHookFailed,
+    SenderMismatch,
+    UpgradeFailed,
+    MigrationFailed,
+    InvalidRound,
+    ConflictingClaimModes,
+    RandomSaltNotRevealed,
+    WrongRandomSalt,
+    InvalidVoucher,
+    VoucherExpired,
+    EditionSoldOut,
+    InvalidShares,
+    PaymentSplitterActive,
+    TransactionLimitReached,
+    WaitlistNotOpen,
+    AlreadyOnWaitlist,
+    WaitlistActive,
+    NotFirstOnWaitlist,
+    AlreadyClaimed,
+    ProvenanceMismatch,
+    InvalidIndexRange,
+    /// Failed parsing the parameter.
+    #[from(ParseError)]
+    ParseParams,
+    AddressDenied,
+    MetadataFrozen,
+    MetadataNotRevealed,
+    ConflictingPaymentModes,
+    TokenPaymentFailed,
+    RaffleNotOpen,
+    AlreadyRegistered,
+    RaffleStillOpen,
+    RaffleNotDrawn,
+    NotRaffleWinner,
+    NotRegistered,
+    RateLimited,
+    InvalidTokenIndex,
+    InvalidReserveAllocations,
+    ReserveAllocationExceeded,
+    InvalidNftLimit,
+    SupplyLocked,
+    NotSoldOut,
+    ShuffleAlreadyRevealed,
+    NotTokenHolder,
+    IdentityPolicyNotMet,
+    InvalidAdminThreshold,
+    MultisigRequired,
+    UnknownAction,
+    AlreadyApproved,
+    UpgradeNotApproved,
+    RerollUnavailable,
+    InvalidPriceCurve,
+    InvalidProof,
+    DuplicateWhitelistEntry,
+    PartnerQuotaExceeded,
+    InvalidBridgeAttestation,
+    AirdropTransferFailed,
+    AirdropFundsExhausted,
+    AirdropStillOpen,
+    TokenLocked,
+    WaitlistEmpty,
+}
+
+impl From<LogError> for Error {
+    fn from(error: LogError) -> Self {
+        match error {
+            LogError::Full => Error::LogFull,
+            LogError::Malformed => Error::LogMalformed,
+        }
+    }
+}
+
+impl From<UpgradeError> for Error {
+    fn from(_: UpgradeError) -> Self {
+        Error::UpgradeFailed
+    }
+}
+
+/// Checks that the transaction was sent by the owner of the contract instance.
+fn check_admin(ctx: &impl HasReceiveContext) -> Result<(), Error> {
+    if !ctx.sender().matches_account(&ctx.owner()) {
+        return Err(Error::Unauthorized);
+    }
+    Ok(())
+}
+
+/// Checks the sender can run a sensitive admin action directly, which is only
+/// the case when no admins were set at init.
+fn check_direct_admin<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    state: &State<S>,
+) -> Result<(), Error> {
+    check_admin(ctx)?;
+    if !state.admins.is_empty() {
+        return Err(Error::MultisigRequired);
+    }
+    Ok(())
+}
+
+/// Returns the admin sending the transaction, or Unauthorized for anyone else.
+fn sending_admin<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    state: &State<S>,
+) -> Result<AccountAddress, Error> {
+    match ctx.sender() {
+        Address::Account(account) if state.admins.contains(&account) => Ok(account),
+        _ => Err(Error::Unauthorized),
+    }
+}
+
+/// Init function that creates a new smart contract.  CCD sent along funds the
+/// claims in CCD airdrop mode.
+#[init(contract = "airdrop_project", parameter = "InitParams", payable)]
+fn init<S: HasStateApi>(
+    ctx: &impl HasInitContext,
+    state_builder: &mut StateBuilder<S>,
+    amount: Amount,
+) -> InitResult<State<S>> {
+    let params: InitParams = ctx.parameter_cursor().get()?;
+    let mut state: State<S> = State::empty(state_builder);
+
+    state.nft_limit = params.nft_limit;
+    state.base_url = params.base_url;
+    state.hex_token_ids = params.hex_token_ids;
+    state.mint_price = params.mint_price;
+    state.phases = params.phases;
+    state.soulbound = params.soulbound;
+    state.metadata_hashes = params.metadata_hashes;
+
+    // Metadata hashes given at init have to match the provenance committed to
+    if !params.provenance_hash.is_empty() {
+        if !state.metadata_hashes.is_empty()
+            && digest(state.metadata_hashes.concat().as_slice()) != params.provenance_hash
+        {
+            return Err(Error::ProvenanceMismatch.into());
+        }
+        state.provenance_hash = Some(params.provenance_hash);
+    }
+    state.sponsored_claims = params.allow_sponsored_claims;
+    state.voucher_key = params.voucher_key;
+    state.bridge_key = params.bridge_key;
+    state.sorted_pairs = params.sorted_pairs;
+    state.merkle_hash = params.merkle_hash;
+    state.leaf_encoding = params.leaf_encoding;
+    state.marketplace = params.marketplace;
+    state.one_claim_per_account = params.one_claim_per_account;
+
+    if !params.placeholder_url.is_empty() {
+        state.placeholder_url = params.placeholder_url;
+        state.revealed = false;
+    }
+
+    for tier in params.tiers {
+        if tier.whitelist.is_empty() {
+            return Err(Error::InvalidTier.into());
+        }
+        if state.has_duplicates(&tier.whitelist) {
+            return Err(Error::DuplicateWhitelistEntry.into());
+        }
+        state.tiers.push(Tier {
+            merkle_tree: MerkleTree::new(
+                tier.whitelist,
+                state.sorted_pairs,
+                state.merkle_hash,
+                state.leaf_encoding,
+            ),
+            max_per_wallet: tier.max_per_wallet,
+            reserve: tier.reserve,
+            claimed: 0,
+            unlock_time: Some(tier.unlock_time)
+                .filter(|unlock_time| *unlock_time != 0)
+                .map(Timestamp::from_timestamp_millis),
+        });
+    }
+
+    state.metadata = params.metadata;
+    state.whitelist = params.whitelist_file;
+
+    if params.nft_time_limit != 0 {
+        state.nft_time_limit = Some(Timestamp::from_timestamp_millis(params.nft_time_limit));
+    }
+
+    if params.start_time != 0 {
+        state.nft_start_time = Some(Timestamp::from_timestamp_millis(params.start_time));
+    }
+
+    if params.unlock_time != 0 {
+        state.unlock_time = Some(Timestamp::from_timestamp_millis(params.unlock_time));
+    }
+
+    if params.nft_limit_per_address != 0 {
+        state.nft_limit_per_address = Some(params.nft_limit_per_address);
+    }
+
+    if params.reserve != 0 {
+        state.nft_reserve = Some(params.reserve);
+    }
+
+    // Allocations partition the reserve, so together they can't exceed it
+    for (address, allocation) in params.reserve_allocations {
+        state.reserve_allocated = state
+            .reserve_allocated
+            .checked_add(allocation)
+            .filter(|allocated| *allocated <= params.reserve)
+            .ok_or(Error::InvalidReserveAllocations)?;
+        *state.reserve_allocations.entry(address).or_insert(0) += allocation;
+    }
+
+    if params.max_per_tx != 0 {
+        state.max_per_tx = Some(params.max_per_tx);
+    }
+
+    state.rate_limit = params
+        .rate_limit
+        .filter(|rate_limit| rate_limit.window.millis() > 0);
+
+    // The curve prices the claims outside of phases, which have their own prices
+    if let Some(price_curve) = params.price_curve {
+        if !state.phases.is_empty() || matches!(price_curve, PriceCurve::Step { step: 0, .. }) {
+            return Err(Error::InvalidPriceCurve.into());
+        }
+        state.price_curve = Some(price_curve);
+    }
+
+    // Claims are paid in either CCD or the payment token, never both
+    if params.payment_token.is_some() {
+        if state.mint_price > Amount::zero()
+            || state.price_curve.is_some()
+            || state
+                .phases
+                .iter()
+                .any(|phase| phase.price > Amount::zero())
+        {
+            return Err(Error::ConflictingPaymentModes.into());
+        }
+        state.payment_token = params.payment_token;
+    }
+
+    state.token_gate = params.token_gate;
+    state.identity_policy = params.identity_policy;
+    state.airdrop_token = params.airdrop_token;
+
+    // Claims either mint NFTs, send the airdrop token or send CCD
+    if params.ccd_unit > Amount::zero() {
+        if state.airdrop_token.is_some() {
+            return Err(Error::ConflictingClaimModes.into());
+        }
+        state.ccd_unit = params.ccd_unit;
+        state.ccd_pool = amount;
+    } else if amount > Amount::zero() {
+        return Err(Error::ConflictingClaimModes.into());
+    }
+
+    // Every admin counts once, and the threshold has to be reachable
+    if !params.admins.is_empty() {
+        let distinct = params
+            .admins
+            .iter()
+            .enumerate()
+            .all(|(index, admin)| !params.admins[..index].contains(admin));
+        if !distinct
+            || params.admin_threshold == 0
+            || params.admin_threshold as usize > params.admins.len()
+        {
+            return Err(Error::InvalidAdminThreshold.into());
+        }
+        state.admins = params.admins;
+        state.admin_threshold = params.admin_threshold;
+    }
+
+    // The shares must add up to the whole of the proceeds, with one share per account
+    if !params.beneficiaries.is_empty() {
+        let total: u64 = params
+            .beneficiaries
+            .iter()
+            .map(|beneficiary| u64::from(beneficiary.share))
+            .sum();
+        let mut accounts: Vec<AccountAddress> = params
+            .beneficiaries
+            .iter()
+            .map(|beneficiary| beneficiary.account)
+            .collect();
+        accounts.sort();
+        accounts.dedup();
+        if total != 100 || accounts.len() != params.beneficiaries.len() {
+            return Err(Error::InvalidShares.into());
+        }
+        state.beneficiaries = params.beneficiaries;
+    }
+
+    if params.reserve_release_time != 0 {
+        state.reserve_release_time = Some(Timestamp::from_timestamp_millis(
+            params.reserve_release_time,
+        ));
+    }
+
+    if let Some(raffle) = params.raffle {
+        state.raffle = Some(Raffle {
+            registration_end: raffle.registration_end,
+            winners: raffle.winners,
+            deposit: raffle.deposit,
+            salt_commitment: raffle.salt_commitment,
+            entries: 0,
+            drawn: false,
+        });
+    }
+
+    if params.selected_index && params.random_index {
+        return Err(Error::ConflictingClaimModes.into());
+    }
+
+    if params.selected_index {
+        state.selected_index = true;
+    }
+
+    // Reserved ranges only apply when claimers select the index of their token
+    if !params.reserved_ranges.is_empty() {
+        if !params.selected_index
+            || params
+                .reserved_ranges
+                .iter()
+                .any(|range| range.start > range.end)
+        {
+            return Err(Error::InvalidIndexRange.into());
+        }
+        state.reserved_ranges = params.reserved_ranges;
+    }
+
+    if params.random_index {
+        state.random_index = true;
+        state.random_salt_commitment = params.random_salt_commitment;
+    }
+
+    // Editions are picked by index, so they need the selected index mode
+    if !params.edition_sizes.is_empty() {
+        if !params.selected_index {
+            return Err(Error::ConflictingClaimModes.into());
+        }
+        state.edition_sizes = params.edition_sizes;
+    }
+
+    // Editions share their metadata between holders, so there is nothing to shuffle
+    if !params.shuffle_seed_commitment.is_empty() {
+        if state.is_edition_mode() {
+            return Err(Error::ConflictingClaimModes.into());
+        }
+        state.shuffle_seed_commitment = Some(params.shuffle_seed_commitment);
+    }
+
+    if state.has_duplicates(&params.whitelist) {
+        return Err(Error::DuplicateWhitelistEntry.into());
+    }
+    if !params.whitelist.is_empty() {
+        state.create_hash_tree(params.whitelist);
+    }
+
+    Ok(state)
+}
+
+/// Claims an NFT.  The sender must be the claimer or one of its CIS-2 operators,
+/// unless sponsored claims are allowed.  Kept for existing integrations, as
+/// `claim_next` and `claim_selected` say which claiming mode they expect.
+#[receive(
+    contract = "airdrop_project",
+    name = "claim_nft",
+    parameter = "ClaimNFTParams",
+    error = "Error",
+    mutable,
+    payable,
+    enable_logger,
+    crypto_primitives
+)]
+fn claim_nft<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    amount: Amount,
+    logger: &mut impl HasLogger,
+    crypto_primitives: &impl HasCryptoPrimitives,
+) -> Result<(), Error> {
+    let params: ClaimNFTParams = ctx.parameter_cursor().get()?;
+    claim_for_sender(ctx, host, params, amount, logger, crypto_primitives)?;
+    Ok(())
+}
+
+/// Claims the next tokens, for claimers which do not pick their token.
+/// Fails with ConflictingClaimModes when selected_index is set.
+#[receive(
+    contract = "airdrop_project",
+    name = "claim_next",
+    parameter = "ClaimNextParams",
+    error = "Error",
+    mutable,
+    payable,
+    enable_logger,
+    crypto_primitives
+)]
+fn claim_next<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    amount: Amount,
+    logger: &mut impl HasLogger,
+    crypto_primitives: &impl HasCryptoPrimitives,
+) -> Result<(), Error> {
+    let params: ClaimNextParams = ctx.parameter_cursor().get()?;
+    if host.state().selected_index && params.round.is_none() {
+        return Err(Error::ConflictingClaimModes);
+    }
+    claim_for_sender(ctx, host, params.into(), amount, logger, crypto_primitives)?;
+    Ok(())
+}
+
+/// Claims the selected token.  Fails with ConflictingClaimModes unless
+/// selected_index is set.
+#[receive(
+    contract = "airdrop_project",
+    name = "claim_selected",
+    parameter = "ClaimSelectedParams",
+    error = "Error",
+    mutable,
+    payable,
+    enable_logger,
+    crypto_primitives
+)]
+fn claim_selected<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    amount: Amount,
+    logger: &mut impl HasLogger,
+    crypto_primitives: &impl HasCryptoPrimitives,
+) -> Result<(), Error> {
+    let params: ClaimSelectedParams = ctx.parameter_cursor().get()?;
+    if !host.state().selected_index {
+        return Err(Error::ConflictingClaimModes);
+    }
+    claim_for_sender(ctx, host, params.into(), amount, logger, crypto_primitives)?;
+    Ok(())
+}
+
+/// Claims tokens for the whitelisted claimer and sends them on to another
+/// receiver, such as a friend being gifted the token or an escrow contract.
+/// The claim is checked and counted against the claimer exactly as with
+/// `claim_nft`, then the tokens are transferred to the receiver, calling its
+/// receive hook when it is a contract.  Not available for soulbound collections.
+#[receive(
+    contract = "airdrop_project",
+    name = "claim_to",
+    parameter = "ClaimToParams",
+    error = "Error",
+    mutable,
+    payable,
+    enable_logger,
+    crypto_primitives
+)]
+fn claim_to<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    amount: Amount,
+    logger: &mut impl HasLogger,
+    crypto_primitives: &impl HasCryptoPrimitives,
+) -> Result<(), Error> {
+    let params: ClaimToParams = ctx.parameter_cursor().get()?;
+    if host.state().soulbound {
+        return Err(Error::TokenIsSoulbound);
+    }
+    let claimer = params.claim.node;
+
+    let claimed = claim_for_sender(ctx, host, params.claim, amount, logger, crypto_primitives)?;
+    for (token_id, token_amount) in claimed {
+        move_token(
+            host.state_mut(),
+            token_id,
+            token_amount,
+            claimer,
+            &params.to,
+        )?;
+        logger.log(&Cis2Event::Transfer(TransferEvent {
+            token_id,
+            amount: token_amount,
+            from: claimer,
+            to: receiver_address(&params.to),
+        }))?;
+        invoke_receive_hook(
+            host,
+            &params.to,
+            token_id,
+            token_amount,
+            claimer,
+            params.data.clone(),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Checks the sender may claim for the claimer, runs the claim and takes the payment.
+/// Returns the tokens minted to the claimer.
+/// Shared by `claim_nft`, `claim_next`, `claim_selected` and `claim_to`.
+fn claim_for_sender<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    params: ClaimNFTParams,
+    amount: Amount,
+    logger: &mut impl HasLogger,
+    crypto_primitives: &impl HasCryptoPrimitives,
+) -> Result<ClaimedTokens, Error> {
+    // Operators claim on behalf of the claimer, such as a custodial wallet.
+    // The tokens are still minted to the claimer.
+    let state = host.state();
+    if !state.sponsored_claims
+        && ctx.sender() != params.node
+        && !state.operators.contains(&(params.node, ctx.sender()))
+    {
+        return Err(Error::SenderMismatch);
+    }
+    check_token_gate(host, params.node)?;
+    // The credentials checked are the sender's, so it has to be the claimer
+    if host.state().identity_policy.is_some() && ctx.sender() != params.node {
+        return Err(Error::IdentityPolicyNotMet);
+    }
+    check_identity_policy(ctx, host.state())?;
+
+    let amount_of_tokens = params.amount_of_tokens;
+    let (price, claimed) = claim(ctx, host, params, amount, logger, crypto_primitives)?;
+
+    collect_token_payment(ctx, host, ctx.sender(), amount_of_tokens)?;
+    refund_overpayment(ctx, host, amount, price)?;
+    Ok(claimed)
+}
+
+/// Checks the identity policy against the attributes revealed in the sender's
+/// account credentials.  The attribute has to be revealed by at least one
+/// credential and no credential can reveal a blocked value.  Contracts have no
+/// credentials, so they can not claim while a policy is set.
+fn check_identity_policy<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    state: &State<S>,
+) -> Result<(), Error> {
+    let policy = match state.identity_policy.as_ref() {
+        Some(policy) => policy,
+        None => return Ok(()),
+    };
+    let mut revealed = false;
+    for mut credential in ctx.policies() {
+        let mut value = [0u8; 31];
+        while let Some((tag, length)) = credential.next_item(&mut value) {
+            if tag.0 != policy.attribute {
+                continue;
+            }
+            revealed = true;
+            let value = &value[..usize::from(length)];
+            if policy
+                .blocked
+                .iter()
+                .any(|blocked| blocked.as_bytes() == value)
+            {
+                return Err(Error::IdentityPolicyNotMet);
+            }
+        }
+    }
+    if !revealed {
+        return Err(Error::IdentityPolicyNotMet);
+    }
+    Ok(())
+}
+
+/// Checks that the claimer holds enough of the gating token, by querying the
+/// balance from the token's contract.  Fails with NotTokenHolder if it does
+/// not, or if the balance can't be read.
+fn check_token_gate<S: HasStateApi>(
+    host: &impl HasHost<State<S>, StateApiType = S>,
+    claimer: Address,
+) -> Result<(), Error> {
+    let token_gate = match host.state().token_gate.as_ref() {
+        Some(token_gate) => token_gate,
+        None => return Ok(()),
+    };
+    let query = BalanceOfQueryParams {
+        queries: vec![BalanceOfQuery {
+            token_id: token_gate.token_id.clone(),
+            address: claimer,
+        }],
+    };
+    let balance = host
+        .invoke_contract_read_only(
+            &token_gate.contract,
+            &query,
+            EntrypointName::new_unchecked("balanceOf"),
+            Amount::zero(),
+        )
+        .ok()
+        .flatten()
+        .and_then(|mut response| {
+            BalanceOfQueryResponse::<TokenAmountU64>::deserial(&mut response).ok()
+        })
+        .and_then(|response| response.0.first().copied())
+        .ok_or(Error::NotTokenHolder)?;
+    if balance < token_gate.min_balance {
+        return Err(Error::NotTokenHolder);
+    }
+    Ok(())
+}
+
+/// Transfers the price of the claimed tokens in the payment token from the payer
+/// to the contract owner.  The payer has to have made this contract an operator
+/// on the payment token contract beforehand.
+fn collect_token_payment<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    payer: Address,
+    amount_of_tokens: u32,
+) -> Result<(), Error> {
+    let payment_token = match host.state().payment_token.clone() {
+        Some(payment_token) => payment_token,
+        None => return Ok(()),
+    };
+    let price = payment_token
+        .price
+        .0
+        .checked_mul(u64::from(amount_of_tokens))
+        .ok_or(Error::InsufficientFunds)?;
+    let transfer = Transfer {
+        token_id: payment_token.token_id,
+        amount: TokenAmountU64(price),
+        from: payer,
+        to: Receiver::Account(ctx.owner()),
+        data: AdditionalData::empty(),
+    };
+    host.invoke_contract(
+        &payment_token.contract,
+        &TransferParams(vec![transfer]),
+        EntrypointName::new_unchecked("transfer"),
+        Amount::zero(),
+    )
+    .map_err(|_| Error::TokenPaymentFailed)?;
+    Ok(())
+}
+
+/// Sends back anything paid above the price, such as after a price drop.
+/// Contracts have no entrypoint to receive CCD on, so their overpayment is kept.
+fn refund_overpayment<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    amount: Amount,
+    price: Amount,
+) -> Result<(), Error> {
+    let overpayment = Amount::from_micro_ccd(amount.micro_ccd - price.micro_ccd);
+    if overpayment > Amount::zero() {
+        match ctx.sender() {
+            Address::Account(payer) => host.invoke_transfer(&payer, overpayment)?,
+            Address::Contract(_) => host.state_mut().collected_funds += overpayment,
+        }
+    }
+
+    Ok(())
+}
+
+/// Puts the sender on the waitlist.  The waitlist opens once every token of the
+/// collection set up at init has been claimed.
+#[receive(
+    contract = "airdrop_project",
+    name = "join_waitlist",
+    error = "Error",
+    mutable
+)]
+fn join_waitlist<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+) -> Result<(), Error> {
+    let sender = ctx.sender();
+    let state = host.state_mut();
+
+    if state.closed {
+        return Err(Error::AirdropNowClosed);
+    }
+    if state.claimed_count() < state.nft_limit {
+        return Err(Error::WaitlistNotOpen);
+    }
+    if !state.waitlisted.insert(sender) {
+        return Err(Error::AlreadyOnWaitlist);
+    }
+
+    state.waitlist.insert(state.waitlist_tail, sender);
+    state.waitlist_tail += 1;
+
+    Ok(())
+}
+
+/// Claims a freed token for the first address on the waitlist, which must be
+/// the sender, and removes it from the waitlist.  The claim is held to the same
+/// eligibility checks, claim window, phases, per address limits and reserves as
+/// `claim_nft`, so claims which need the whitelist need a proof as well.
+#[receive(
+    contract = "airdrop_project",
+    name = "claim_from_waitlist",
+    parameter = "WaitlistClaimParams",
+    error = "Error",
+    mutable,
+    payable,
+    enable_logger,
+    crypto_primitives
+)]
+fn claim_from_waitlist<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    amount: Amount,
+    logger: &mut impl HasLogger,
+    crypto_primitives: &impl HasCryptoPrimitives,
+) -> Result<(), Error> {
+    let params: WaitlistClaimParams = ctx.parameter_cursor().get()?;
+    let sender = ctx.sender();
+    let params = ClaimNFTParams {
+        proof: params.proof,
+        node: sender,
+        node_string: params.node_string,
+        selected_token: params.selected_token,
+        amount_of_tokens: 1,
+        tier: None,
+        round: None,
+        voucher: None,
+        max_claims: params.max_claims,
+    };
+    let slot_time = ctx.metadata().slot_time();
+    check_token_gate(host, sender)?;
+    check_identity_policy(ctx, host.state())?;
+    let state = host.state_mut();
+
+    if state.paused {
+        return Err(Error::ContractPaused);
+    }
+    if state.denylist.contains(&sender) {
+        return Err(Error::AddressDenied);
+    }
+    if state.closed {
+        return Err(Error::AirdropNowClosed);
+    }
+    if state.waitlist.get(&state.waitlist_head).map(|first| *first) != Some(sender) {
+        return Err(Error::NotFirstOnWaitlist);
+    }
+    state.validate_proof(&params)?;
+    state.check_claim_eligibility(&sender)?;
+    state.check_claim_window(slot_time)?;
+    if state.held_count() >= state.open_supply(slot_time) {
+        return Err(Error::NFTLimitReached);
+    }
+    state.check_claimer_limits(&sender, 1)?;
+    let reserve_allocation_used = state.reserve_allocation_used(&sender, 1, slot_time)?;
+    let active_phase = state.check_phase_limit(&sender, 1, slot_time)?;
+
+    // An allocation only counts once it is proven to be in the claimer's whitelist leaf
+    if let Some(max_claims) = params.max_claims {
+        if !state.check_proof(&params, crypto_primitives) {
+            return Err(Error::AddressNotOnWhitelist);
+        }
+        if state
+            .claimed_nfts
+            .get(&sender)
+            .map_or(0, |claimed| *claimed)
+            + 1
+            > max_claims
+        {
+            return Err(Error::WalletLimitReached);
+        }
+    }
+    if state.whitelist_required(active_phase, &params.selected_token, 1, slot_time)
+        && !state.check_proof(&params, crypto_primitives)
+    {
+        return Err(Error::AddressNotOnWhitelist);
+    }
+
+    let price = match active_phase {
+        Some(index) => state.phases[index as usize].price.micro_ccd,
+        None => state.total_price(1).ok_or(Error::InsufficientFunds)?,
+    };
+    let price = Amount::from_micro_ccd(price);
+    if amount < price {
+        return Err(Error::InsufficientFunds);
+    }
+
+    let freed_token_id = state
+        .freed_token_ids
+        .iter()
+        .next()
+        .map(|token_id| *token_id);
+    let token_id = if state.random_index {
+        state.draw_random_token_id(slot_time)?
+    } else if let Some(token_id) = freed_token_id {
+        token_id
+    } else {
+        let token_id = state.token_id_to_use(params.selected_token)?;
+        state.check_edition_supply(&token_id, 1)?;
+        token_id
+    };
+    mint_tokens(
+        state,
+        logger,
+        token_id,
+        1,
+        sender,
+        address_to_string(sender),
+        slot_time,
+        state.unlock_time,
+    )?;
+    log_claim(state, logger, token_id, sender, 1, slot_time)?;
+    log_sold_out(state, logger, slot_time)?;
+    approve_marketplace(state, logger, sender)?;
+    state.record_claim_limits(sender, 1, active_phase, reserve_allocation_used);
+
+    state.waitlist.remove(&state.waitlist_head);
+    state.waitlisted.remove(&sender);
+    state.waitlist_head += 1;
+    state.collected_funds += price;
+
+    collect_token_payment(ctx, host, ctx.sender(), 1)?;
+    refund_overpayment(ctx, host, amount, price)
+}
+
+/// Removes the first address from the waitlist, such as one which is not
+/// claiming, so the next address can claim.  Only the contract owner can call this.
+#[receive(
+    contract = "airdrop_project",
+    name = "skip_waitlist",
+    error = "Error",
+    mutable
+)]
+fn skip_waitlist<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+) -> Result<(), Error> {
+    check_admin(ctx)?;
+    let state = host.state_mut();
+
+    let first = state
+        .waitlist
+        .remove_and_get(&state.waitlist_head)
+        .ok_or(Error::WaitlistEmpty)?;
+    state.waitlisted.remove(&first);
+    state.waitlist_head += 1;
+
+    Ok(())
+}
+
+/// Registers the sender for the raffle.  The raffle deposit has to be attached,
+/// and anything above it is sent back.  Only accounts can register.
+#[receive(
+    contract = "airdrop_project",
+    name = "register",
+    error = "Error",
+    mutable,
+    payable
+)]
+fn register<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    amount: Amount,
+) -> Result<(), Error> {
+    let account = match ctx.sender() {
+        Address::Account(account) => account,
+        Address::Contract(_) => return Err(Error::SenderMismatch),
+    };
+    let slot_time = ctx.metadata().slot_time();
+    let state = host.state_mut();
+
+    if state.denylist.contains(&ctx.sender()) {
+        return Err(Error::AddressDenied);
+    }
+    let (entries, deposit) = match state.raffle.as_ref() {
+        Some(raffle) if slot_time <= raffle.registration_end => (raffle.entries, raffle.deposit),
+        _ => return Err(Error::RaffleNotOpen),
+    };
+    if amount < deposit {
+        return Err(Error::InsufficientFunds);
+    }
+    if !state.raffle_registered.insert(account) {
+        return Err(Error::AlreadyRegistered);
+    }
+
+    state.raffle_entries.insert(entries, account);
+    if let Some(raffle) = state.raffle.as_mut() {
+        raffle.entries += 1;
+    }
+    state.raffle_deposits += deposit;
+
+    refund_overpayment(ctx, host, amount, deposit)
+}
+
+/// Draws the raffle winners once registration has closed, seeded by the salt
+/// committed at init and the slot time.  The deposits of the winners are added
+/// to the proceeds.  Only the contract owner can call this.
+#[receive(
+    contract = "airdrop_project",
+    name = "draw",
+    parameter = "RandomSaltParams",
+    error = "Error",
+    mutable
+)]
+fn draw<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+) -> Result<(), Error> {
+    check_admin(ctx)?;
+    let params: RandomSaltParams = ctx.parameter_cursor().get()?;
+    let slot_time = ctx.metadata().slot_time();
+    let state = host.state_mut();
+
+    let raffle = state.raffle.clone().ok_or(Error::RaffleNotOpen)?;
+    if raffle.drawn {
+        return Err(Error::RaffleNotOpen);
+    }
+    if slot_time <= raffle.registration_end {
+        return Err(Error::RaffleStillOpen);
+    }
+    if digest(params.salt.clone()) != raffle.salt_commitment {
+        return Err(Error::WrongRandomSalt);
+    }
+
+    // Partial Fisher-Yates shuffle: each winner is swapped into the next position
+    let winners = raffle.winners.min(raffle.entries);
+    for position in 0..winners {
+        let seed = digest(format!(
+            "{}{}{}",
+            params.salt,
+            slot_time.timestamp_millis(),
+            position
+        ));
+        let remaining = raffle.entries - position;
+        let drawn = position + u32::from_str_radix(&seed[..8], 16).unwrap_or(0) % remaining;
+
+        let winner = *state
+            .raffle_entries
+            .get(&drawn)
+            .ok_or(Error::RaffleNotOpen)?;
+        let current = *state
+            .raffle_entries
+            .get(&position)
+            .ok_or(Error::RaffleNotOpen)?;
+        state.raffle_entries.insert(drawn, current);
+        state.raffle_entries.insert(position, winner);
+        state.raffle_registered.remove(&winner);
+        state.raffle_winners.insert(winner);
+    }
+
+    let winner_deposits = raffle.deposit * u64::from(winners);
+    state.collected_funds += winner_deposits;
+    state.raffle_deposits -= winner_deposits;
+    if let Some(raffle) = state.raffle.as_mut() {
+        raffle.drawn = true;
+    }
+
+    Ok(())
+}
+
+/// Sends the raffle deposit back to a registered account which was not drawn.
+#[receive(
+    contract = "airdrop_project",
+    name = "refund_deposit",
+    error = "Error",
+    mutable
+)]
+fn refund_deposit<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+) -> Result<(), Error> {
+    let account = match ctx.sender() {
+        Address::Account(account) => account,
+        Address::Contract(_) => return Err(Error::NotRegistered),
+    };
+    let state = host.state_mut();
+
+    let deposit = match state.raffle.as_ref() {
+        Some(raffle) if raffle.drawn => raffle.deposit,
+        Some(_) => return Err(Error::RaffleNotDrawn),
+        None => return Err(Error::RaffleNotOpen),
+    };
+    if !state.raffle_registered.remove(&account) {
+        return Err(Error::NotRegistered);
+    }
+    state.raffle_deposits -= deposit;
+
+    if deposit > Amount::zero() {
+        host.invoke_transfer(&account, deposit)?;
+    }
+
+    Ok(())
+}
+
+/// View function that returns whether an account was drawn as a raffle winner.
+#[receive(
+    contract = "airdrop_project",
+    name = "is_raffle_winner",
+    parameter = "AccountAddress",
+    return_value = "bool"
+)]
+fn is_raffle_winner<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ReceiveResult<bool> {
+    let account: AccountAddress = ctx.parameter_cursor().get()?;
+    Ok(host.state().raffle_winners.contains(&account))
+}
+
+/// Runs the claim checks and mints the claimed tokens.  Returns the price of the claimed tokens
+/// and the tokens minted.  Shared by `claim_nft` and claims submitted through `permit`.
+fn claim<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    params: ClaimNFTParams,
+    amount: Amount,
+    logger: &mut impl HasLogger,
+    crypto_primitives: &impl HasCryptoPrimitives,
+) -> Result<(Amount, ClaimedTokens), Error> {
+    let slot_time = ctx.metadata().slot_time();
+    let state = host.state_mut();
+
+    if state.paused {
+        return Err(Error::ContractPaused);
+    }
+
+    if state.denylist.contains(&params.node) {
+        return Err(Error::AddressDenied);
+    }
+
+    if state.closed {
+        return Err(Error::AirdropNowClosed);
+    }
+
+    state.validate_proof(&params)?;
+
+    if let Some(max_per_tx) = state.max_per_tx {
+        if params.amount_of_tokens > max_per_tx {
+            return Err(Error::TransactionLimitReached);
+        }
+    }
+
+    // Claims are counted per window of slot time.  A failed claim rolls the count back.
+    if let Some(rate_limit) = state.rate_limit {
+        let window = slot_time.timestamp_millis() / rate_limit.window.millis();
+        if window != state.rate_window {
+            state.rate_window = window;
+            state.rate_window_claims = 0;
+        }
+        if state.rate_window_claims >= rate_limit.max_claims {
+            return Err(Error::RateLimited);
+        }
+        state.rate_window_claims += 1;
+    }
+
+    if state.airdrop_token.is_some() || state.ccd_unit > Amount::zero() {
+        return claim_allocation(ctx, host, params, amount, logger, crypto_primitives);
+    }
+
+    if let Some(round_id) = params.round {
+        return claim_round(
+            state,
+            round_id,
+            params,
+            slot_time,
+            amount,
+            logger,
+            crypto_primitives,
+        );
+    }
+
+    // Tokens freed after the drop sold out go to the waitlist first
+    if state.waitlist_head != state.waitlist_tail {
+        return Err(Error::WaitlistActive);
+    }
+
+    state.check_claim_eligibility(&params.node)?;
+
+    state.check_claim_window(slot_time)?;
+
+    let current_token_id = state.next_token_id;
+    let amount_of_tokens = params.amount_of_tokens;
+    if current_token_id + params.amount_of_tokens > state.nft_limit {
+        return Err(Error::NFTLimitReached);
+    }
+
+    state.check_claimer_limits(&params.node, amount_of_tokens)?;
+
+    // Claims through a tier need a proof against that tier's tree, everyone else
+    // has to leave the tokens held back for the tiers.
+    if let Some(tier_index) = params.tier {
+        let tier = state
+            .tiers
+            .get(tier_index as usize)
+            .ok_or(Error::InvalidTier)?;
+        if !tier.merkle_tree.check_proof(
+            &state.leaf_encoding.claimer_leaf(&params),
+            &params.proof,
+            crypto_primitives,
+        ) {
+            return Err(Error::AddressNotOnWhitelist);
+        }
+        if tier.max_per_wallet != 0 {
+            let val = state
+                .tier_claims
+                .get(&(tier_index, params.node))
+                .map_or(0, |claimed| *claimed);
+            if val + amount_of_tokens > tier.max_per_wallet {
+                return Err(Error::WalletLimitReached);
+            }
+        }
+    } else if state.claimed_count() + amount_of_tokens > state.open_supply(slot_time) {
+        return Err(Error::NFTLimitReached);
+    }
+
+    let reserve_allocation_used =
+        state.reserve_allocation_used(&params.node, amount_of_tokens, slot_time)?;
+
+    // An allocation only counts once it is proven to be in the claimer's whitelist leaf
+    if let Some(max_claims) = params.max_claims {
+        if params.tier.is_none() && !state.check_proof(&params, crypto_primitives) {
+            return Err(Error::AddressNotOnWhitelist);
+        }
+        let claimed = state
+            .claimed_nfts
+            .get(&params.node)
+            .map_or(0, |claimed| *claimed);
+        if claimed + amount_of_tokens > max_claims {
+            return Err(Error::WalletLimitReached);
+        }
+    }
+
+    let active_phase = state.check_phase_limit(&params.node, amount_of_tokens, slot_time)?;
+
+    let mint_price = match active_phase {
+        Some(index) => state.phases[index as usize].price,
+        None => state.mint_price,
+    };
+    let whitelist_required = state.whitelist_required(
+        active_phase,
+        &params.selected_token,
+        amount_of_tokens,
+        slot_time,
+    );
+
+    // A valid voucher stands in for a whitelist proof, up to the amount it allows
+    let voucher_claimed = match params.voucher.as_ref() {
+        Some(voucher) => {
+            let voucher_key = state.voucher_key.ok_or(Error::InvalidVoucher)?;
+            if voucher.expiry < slot_time {
+                return Err(Error::VoucherExpired);
+            }
+            let message = VoucherMessage {
+                contract_address: ctx.self_address(),
+                claimer: params.node,
+                max_amount: voucher.max_amount,
+                expiry: voucher.expiry,
+            };
+            if !crypto_primitives.verify_ed25519_signature(
+                voucher_key,
+                voucher.signature,
+                &to_bytes(&message),
+            ) {
+                return Err(Error::InvalidVoucher);
+            }
+            let claimed = state
+                .voucher_claims
+                .get(&params.node)
+                .map_or(0, |claimed| *claimed);
+            if claimed + amount_of_tokens > voucher.max_amount {
+                return Err(Error::WalletLimitReached);
+            }
+            Some(claimed)
+        }
+        None => None,
+    };
+
+    if whitelist_required
+        && params.tier.is_none()
+        && voucher_claimed.is_none()
+        && (params.proof.is_empty() || !state.check_proof(&params, crypto_primitives))
+    {
+        return Err(Error::AddressNotOnWhitelist);
+    }
+
+    // The attached CCD must cover the price of every token being claimed
+    let price = match active_phase {
+        Some(_) => mint_price
+            .micro_ccd
+            .checked_mul(u64::from(amount_of_tokens)),
+        None => state.total_price(amount_of_tokens),
+    }
+    .ok_or(Error::InsufficientFunds)?;
+    if amount.micro_ccd < price {
+        return Err(Error::InsufficientFunds);
+    }
+
+    // Claimed tokens stay locked until the tier's unlock time, or the global one
+    let unlock_time = params
+        .tier
+        .and_then(|tier_index| state.tiers[tier_index as usize].unlock_time)
+        .or(state.unlock_time);
+
+    let mut claimed = ClaimedTokens::new();
+    let first_token_id = if state.random_index {
+        let mut first_token_id = None;
+        for _ in 0..amount_of_tokens {
+            let token_id = state.draw_random_token_id(slot_time)?;
+            first_token_id.get_or_insert(token_id);
+            claimed.push((token_id, ContractTokenAmount::from(1)));
+            mint_tokens(
+                state,
+                logger,
+                token_id,
+                1,
+                params.node,
+                params.node_string.clone(),
+                slot_time,
+                unlock_time,
+            )?;
+        }
+        first_token_id
+    } else {
+        let token_id_to_use = state.token_id_to_use(params.selected_token)?;
+        state.check_edition_supply(&token_id_to_use, amount_of_tokens)?;
+
+        mint_tokens(
+            state,
+            logger,
+            token_id_to_use,
+            amount_of_tokens,
+            params.node,
+            params.node_string,
+            slot_time,
+            unlock_time,
+        )?;
+        if state.is_edition_mode() {
+            claimed.push((token_id_to_use, ContractTokenAmount::from(amount_of_tokens)));
+        } else {
+            claimed.extend((0..amount_of_tokens).map(|offset| {
+                (
+                    offset_token_id(token_id_to_use, offset),
+                    ContractTokenAmount::from(1),
+                )
+            }));
+        }
+        Some(token_id_to_use)
+    };
+
+    if let Some(token_id) = first_token_id {
+        log_claim(
+            state,
+            logger,
+            token_id,
+            params.node,
+            amount_of_tokens,
+            slot_time,
+        )?;
+    }
+    log_sold_out(state, logger, slot_time)?;
+    approve_marketplace(state, logger, params.node)?;
+
+    if let Some(tier_index) = params.tier {
+        state.tiers[tier_index as usize].claimed += amount_of_tokens;
+        let mut tier_tokens = state
+            .tier_claims
+            .entry((tier_index, params.node))
+            .or_insert(0);
+        *tier_tokens += amount_of_tokens;
+    }
+
+    state.record_claim_limits(
+        params.node,
+        amount_of_tokens,
+        active_phase,
+        reserve_allocation_used,
+    );
+
+    if let Some(claimed) = voucher_claimed {
+        state
+            .voucher_claims
+            .insert(params.node, claimed + amount_of_tokens);
+    }
+
+    let price = Amount::from_micro_ccd(price);
+    state.collected_funds += price;
+
+    Ok((price, claimed))
+}
+
+/// Runs the claim checks in airdrop token and CCD airdrop modes, and sends the
+/// claimed units of the claimer's allocation out of this contract's balance.
+/// Allocations come from the whitelist leaves, so every claim needs a proof.
+/// Returns the price of the claim, and no tokens as none are minted.
+fn claim_allocation<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    params: ClaimNFTParams,
+    amount: Amount,
+    logger: &mut impl HasLogger,
+    crypto_primitives: &impl HasCryptoPrimitives,
+) -> Result<(Amount, ClaimedTokens), Error> {
+    let slot_time = ctx.metadata().slot_time();
+    let state = host.state_mut();
+
+    if let Some(time_limit) = state.nft_time_limit {
+        if time_limit > Timestamp::from_timestamp_millis(0) && slot_time > time_limit {
+            return Err(Error::AirdropNowClosed);
+        }
+    }
+    if let Some(start_time) = state.nft_start_time {
+        if slot_time < start_time {
+            return Err(Error::AirdropNotStarted);
+        }
+    }
+
+    let allocation = params.max_claims.ok_or(Error::AddressNotOnWhitelist)?;
+    if !state.check_proof(&params, crypto_primitives) {
+        return Err(Error::AddressNotOnWhitelist);
+    }
+    let units = params.amount_of_tokens;
+    let claimed = state
+        .claimed_nfts
+        .get(&params.node)
+        .map_or(0, |claimed| *claimed);
+    if claimed + units > allocation {
+        return Err(Error::WalletLimitReached);
+    }
+
+    let price = state
+        .mint_price
+        .micro_ccd
+        .checked_mul(u64::from(units))
+        .ok_or(Error::InsufficientFunds)?;
+    if amount.micro_ccd < price {
+        return Err(Error::InsufficientFunds);
+    }
+
+    state.add_claimed(params.node, units);
+    count_claim(state, units, slot_time);
+    let price = Amount::from_micro_ccd(price);
+    state.collected_funds += price;
+
+    // The state is updated before anything is sent, so the receiver can't
+    // call back into a claim which has not been counted yet
+    match state.airdrop_token.clone() {
+        Some(airdrop_token) => {
+            let token_amount = airdrop_token
+                .unit
+                .0
+                .checked_mul(u64::from(units))
+                .ok_or(Error::AirdropTransferFailed)?;
+            logger.log(&AirdropEvent::TokensClaimed(TokensClaimedEvent {
+                claimer: params.node,
+                units,
+                amount: TokenAmountU64(token_amount),
+                slot_time,
+                claim_number: state.claim_count,
+            }))?;
+            let to = match params.node {
+                Address::Account(account) => Receiver::Account(account),
+                Address::Contract(contract) => Receiver::Contract(
+                    contract,
+                    OwnedEntrypointName::new_unchecked("onReceivingCIS2".to_string()),
+                ),
+            };
+            let transfer = Transfer {
+                token_id: airdrop_token.token_id,
+                amount: TokenAmountU64(token_amount),
+                from: Address::Contract(ctx.self_address()),
+                to,
+                data: AdditionalData::empty(),
+            };
+            host.invoke_contract(
+                &airdrop_token.contract,
+                &TransferParams(vec![transfer]),
+                EntrypointName::new_unchecked("transfer"),
+                Amount::zero(),
+            )
+            .map_err(|_| Error::AirdropTransferFailed)?;
+        }
+        None => {
+            // Contracts have no entrypoint to receive CCD on
+            let account = match params.node {
+                Address::Account(account) => account,
+                Address::Contract(_) => return Err(Error::TransferFailed),
+            };
+            let ccd_amount = state
+                .ccd_unit
+                .micro_ccd
+                .checked_mul(u64::from(units))
+                .map(Amount::from_micro_ccd)
+                .filter(|ccd_amount| *ccd_amount <= state.ccd_pool)
+                .ok_or(Error::AirdropFundsExhausted)?;
+            state.ccd_pool -= ccd_amount;
+            logger.log(&AirdropEvent::CcdClaimed(CcdClaimedEvent {
+                claimer: account,
+                units,
+                amount: ccd_amount,
+                slot_time,
+                claim_number: state.claim_count,
+            }))?;
+            host.invoke_transfer(&account, ccd_amount)?;
+        }
+    }
+    Ok((price, ClaimedTokens::new()))
+}
+
+/// Accepts deposits of the airdropped token, which the admin sends to this
+/// contract with a CIS-2 transfer before the claims open.  Any other token is
+/// rejected, so nothing else can get stuck in the contract.
+#[receive(
+    contract = "airdrop_project",
+    name = "onReceivingCIS2",
+    parameter = "OnReceivingCis2Params<TokenIdVec, TokenAmountU64>",
+    error = "Error"
+)]
+fn on_receiving_cis2<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> Result<(), Error> {
+    let params: OnReceivingCis2Params<TokenIdVec, TokenAmountU64> = ctx.parameter_cursor().get()?;
+    match host.state().airdrop_token.as_ref() {
+        Some(airdrop_token)
+            if ctx.sender() == Address::Contract(airdrop_token.contract)
+                && params.token_id == airdrop_token.token_id =>
+        {
+            Ok(())
+        }
+        _ => Err(Error::Unauthorized),
+    }
+}
+
+/// Adds the CCD sent along to the funds for claims in CCD airdrop mode.
+/// Anyone can top up the funds, such as a sponsor of the drop.
+#[receive(
+    contract = "airdrop_project",
+    name = "fund_ccd_airdrop",
+    error = "Error",
+    mutable,
+    payable
+)]
+fn fund_ccd_airdrop<S: HasStateApi>(
+    _ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    amount: Amount,
+) -> Result<(), Error> {
+    let state = host.state_mut();
+    if state.ccd_unit == Amount::zero() {
+        return Err(Error::ConflictingClaimModes);
+    }
+    state.ccd_pool += amount;
+    Ok(())
+}
+
+/// Sends the CCD left unclaimed in CCD airdrop mode to the owner, once the
+/// airdrop is closed or its deadline has passed.  Only the contract owner can
+/// call this, and only when no admins were set at init.  Otherwise it goes
+/// through `propose_action`.
+#[receive(
+    contract = "airdrop_project",
+    name = "sweep_ccd_airdrop",
+    error = "Error",
+    mutable,
+    enable_logger
+)]
+fn sweep_ccd_airdrop<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+) -> Result<(), Error> {
+    check_direct_admin(ctx, host.state())?;
+    sweep_ccd_airdrop_to(host, ctx.owner(), ctx.metadata().slot_time(), logger)
+}
+
+/// Sends the CCD left unclaimed in CCD airdrop mode and logs a Withdraw event.
+fn sweep_ccd_airdrop_to<S: HasStateApi>(
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    to: AccountAddress,
+    slot_time: Timestamp,
+    logger: &mut impl HasLogger,
+) -> Result<(), Error> {
+    let state = host.state_mut();
+    let deadline_passed = match state.nft_time_limit {
+        Some(time_limit) => {
+            time_limit > Timestamp::from_timestamp_millis(0) && slot_time > time_limit
+        }
+        None => false,
+    };
+    if !state.closed && !deadline_passed {
+        return Err(Error::AirdropStillOpen);
+    }
+
+    let amount = state.ccd_pool;
+    state.ccd_pool = Amount::zero();
+    if amount > Amount::zero() {
+        host.invoke_transfer(&to, amount)?;
+        logger.log(&AirdropEvent::Withdraw(WithdrawEvent { amount, to }))?;
+    }
+    Ok(())
+}
+
+/// Runs the claim checks for a round created with `create_round` and mints the
+/// claimed tokens.  Round tokens are always claimed in sequential order.
+/// Returns the price of the claimed tokens and the tokens minted.
+fn claim_round<S: HasStateApi>(
+    state: &mut State<S>,
+    round_id: RoundId,
+    params: ClaimNFTParams,
+    slot_time: Timestamp,
+    amount: Amount,
+    logger: &mut impl HasLogger,
+    crypto_primitives: &impl HasCryptoPrimitives,
+) -> Result<(Amount, ClaimedTokens), Error> {
+    let amount_of_tokens = params.amount_of_tokens;
+    let claimed = state
+        .round_claims
+        .get(&(round_id, params.node))
+        .map_or(0, |claimed| *claimed);
+
+    let (first_token_id, mint_price) = {
+        let round = state.rounds.get(&round_id).ok_or(Error::InvalidRound)?;
+
+        if slot_time < round.start_time {
+            return Err(Error::AirdropNotStarted);
+        }
+        if let Some(end_time) = round.end_time {
+            if slot_time > end_time {
+                return Err(Error::AirdropNowClosed);
+            }
+        }
+        if round.minted + amount_of_tokens > round.nft_limit {
+            return Err(Error::NFTLimitReached);
+        }
+        if let Some(limit) = round.nft_limit_per_address {
+            if claimed + amount_of_tokens > limit {
+                return Err(Error::WalletLimitReached);
+            }
+        }
+        if let Some(tree) = round.merkle_tree.as_ref() {
+            if !tree.check_proof(
+                &state.leaf_encoding.node_leaf(&params),
+                &params.proof,
+                crypto_primitives,
+            ) {
+                return Err(Error::AddressNotOnWhitelist);
+            }
+        }
+
+        (round_token_id(round_id, round.minted), round.mint_price)
+    };
+
+    let price = mint_price
+        .micro_ccd
+        .checked_mul(u64::from(amount_of_tokens))
+        .ok_or(Error::InsufficientFunds)?;
+    if amount.micro_ccd < price {
+        return Err(Error::InsufficientFunds);
+    }
+
+    mint_tokens(
+        state,
+        logger,
+        first_token_id,
+        amount_of_tokens,
+        params.node,
+        params.node_string,
+        slot_time,
+        state.unlock_time,
+    )?;
+    log_claim(
+        state,
+        logger,
+        first_token_id,
+        params.node,
+        amount_of_tokens,
+        slot_time,
+    )?;
+    approve_marketplace(state, logger, params.node)?;
+
+    if let Some(mut round) = state.rounds.get_mut(&round_id) {
+        round.minted += amount_of_tokens;
+    }
+    state
+        .round_claims
+        .insert((round_id, params.node), claimed + amount_of_tokens);
+    let price = Amount::from_micro_ccd(price);
+    state.collected_funds += price;
+
+    let claimed = (0..amount_of_tokens)
+        .map(|offset| {
+            (
+                offset_token_id(first_token_id, offset),
+                ContractTokenAmount::from(1),
+            )
+        })
+        .collect();
+    Ok((price, claimed))
+}
+
+/// Executes a message signed off-chain by an account, following the CIS-3 standard.
+/// This lets a sponsor pay for the transaction of an account holding no CCD.
+/// Only `claim_nft` can be invoked this way, for the signer's own address.  No CCD
+/// is attached, so claims with a CCD price fail, while a price in the payment token
+/// is taken from the signer.  The signer's key must be registered with `register_public_keys`.
+#[receive(
+    contract = "airdrop_project",
+    name = "permit",
+    parameter = "PermitParam",
+    error = "Error",
+    mutable,
+    enable_logger,
+    crypto_primitives
+)]
+fn permit<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+    crypto_primitives: &impl HasCryptoPrimitives,
+) -> Result<(), Error> {
+    let params: PermitParam = ctx.parameter_cursor().get()?;
+    let message = params.message;
+    let slot_time = ctx.metadata().slot_time();
+    let state = host.state_mut();
+
+    if message.contract_address != ctx.self_address() {
+        return Err(Error::WrongContract);
+    }
+    if message.timestamp < slot_time {
+        return Err(Error::PermitExpired);
+    }
+
+    let nonce = state.nonces.get(&params.signer).map_or(0, |nonce| *nonce);
+    if message.nonce != nonce {
+        return Err(Error::NonceMismatch);
+    }
+
+    let public_key = match state.public_keys.get(&params.signer) {
+        Some(public_key) => *public_key,
+        None => return Err(Error::MissingPublicKey),
+    };
+    if !crypto_primitives.verify_ed25519_signature(
+        public_key,
+        params.signature,
+        &to_bytes(&message),
+    ) {
+        return Err(Error::WrongSignature);
+    }
+
+    state.nonces.insert(params.signer, nonce + 1);
+
+    if message.entry_point.as_entrypoint_name() != EntrypointName::new_unchecked("claim_nft") {
+        return Err(Error::WrongEntrypoint);
+    }
+    let claim_params: ClaimNFTParams = from_bytes(&message.payload)?;
+    if claim_params.node != Address::Account(params.signer) {
+        return Err(Error::Unauthorized);
+    }
+    check_token_gate(host, claim_params.node)?;
+    // The credentials checked are the sender's, which is the relayer here
+    if host.state().identity_policy.is_some() {
+        return Err(Error::IdentityPolicyNotMet);
+    }
+    let amount_of_tokens = claim_params.amount_of_tokens;
+    claim(
+        ctx,
+        host,
+        claim_params,
+        Amount::zero(),
+        logger,
+        crypto_primitives,
+    )?;
+    // The sponsor pays for the transaction, the signer for the tokens
+    collect_token_payment(ctx, host, Address::Account(params.signer), amount_of_tokens)?;
+
+    logger.log(&NonceEvent {
+        nonce,
+        sponsoree: params.signer,
+    })?;
+
+    Ok(())
+}
+
+/// View function that returns, for each queried entrypoint, whether it can be invoked through `permit`.
+#[receive(
+    contract = "airdrop_project",
+    name = "supportsPermit",
+    parameter = "SupportsPermitQueryParams",
+    return_value = "Vec<bool>"
+)]
+fn supports_permit<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    _host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ReceiveResult<Vec<bool>> {
+    let params: SupportsPermitQueryParams = ctx.parameter_cursor().get()?;
+
+    Ok(params
+        .queries
+        .iter()
+        .map(|entry_point| {
+            entry_point.as_entrypoint_name() == EntrypointName::new_unchecked("claim_nft")
+        })
+        .collect())
+}
+
+/// View function that returns the next permit nonce of an account
+#[receive(
+    contract = "airdrop_project",
+    name = "nonceOf",
+    parameter = "AccountAddress",
+    return_value = "u64"
+)]
+fn nonce_of<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ReceiveResult<u64> {
+    let account: AccountAddress = ctx.parameter_cursor().get()?;
+    Ok(host.state().nonces.get(&account).map_or(0, |nonce| *nonce))
+}
+
+/// Registers the key used to verify the `permit` signatures of the sending
+/// account, replacing any key it registered before.  Only accounts can
+/// register a key, and only for themselves.
+#[receive(
+    contract = "airdrop_project",
+    name = "register_public_keys",
+    parameter = "RegisterPublicKeysParams",
+    error = "Error",
+    mutable
+)]
+fn register_public_keys<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+) -> Result<(), Error> {
+    let account = match ctx.sender() {
+        Address::Account(account) => account,
+        Address::Contract(_) => return Err(Error::Unauthorized),
+    };
+    let params: RegisterPublicKeysParams = ctx.parameter_cursor().get()?;
+    host.state_mut().public_keys.insert(account, params.key);
+
+    Ok(())
+}
+
+/// Counts a successful claim, in total and for the day it was made on.
+fn count_claim<S: HasStateApi>(state: &mut State<S>, amount_of_tokens: u32, slot_time: Timestamp) {
+    state.claim_count += 1;
+    let mut day = state
+        .daily_claims
+        .entry(slot_time.timestamp_millis() / MILLIS_PER_DAY)
+        .or_default();
+    day.claims += 1;
+    day.tokens += amount_of_tokens;
+}
+
+/// Counts a successful claim and logs its ClaimEvent.
+fn log_claim<S: HasStateApi>(
+    state: &mut State<S>,
+    logger: &mut impl HasLogger,
+    token_id: ContractTokenId,
+    claimer: Address,
+    amount_of_tokens: u32,
+    slot_time: Timestamp,
+) -> Result<(), Error> {
+    count_claim(state, amount_of_tokens, slot_time);
+    logger.log(&AirdropEvent::Claim(ClaimEvent {
+        token_id,
+        claimer,
+        amount: amount_of_tokens,
+        slot_time,
+        claim_number: state.claim_count,
+    }))?;
+    Ok(())
+}
+
+/// Logs a SoldOut event once a claim has taken the last token of the collection set up at init.
+/// Tokens claimed again after a burn don't log it a second time.
+fn log_sold_out<S: HasStateApi>(
+    state: &mut State<S>,
+    logger: &mut impl HasLogger,
+    slot_time: Timestamp,
+) -> Result<(), Error> {
+    if !state.sold_out_logged && state.claimed_count() >= state.nft_limit {
+        state.sold_out_logged = true;
+        logger.log(&AirdropEvent::SoldOut(SoldOutEvent {
+            claim_count: state.claim_count,
+            slot_time,
+        }))?;
+    }
+    Ok(())
+}
+
+/// Makes the trusted marketplace an operator of the claimer, if one was set at
+/// init, so the claimed tokens can be listed straight away.
+fn approve_marketplace<S: HasStateApi>(
+    state: &mut State<S>,
+    logger: &mut impl HasLogger,
+    claimer: Address,
+) -> Result<(), Error> {
+    let operator = match state.marketplace {
+        Some(marketplace) => Address::Contract(marketplace),
+        None => return Ok(()),
+    };
+    if state.operators.insert((claimer, operator)) {
+        logger.log(
+            &Cis2Event::<ContractTokenId, ContractTokenAmount>::UpdateOperator(
+                UpdateOperatorEvent {
+                    owner: claimer,
+                    operator,
+                    update: OperatorUpdate::Add,
+                },
+            ),
+        )?;
+    }
+    Ok(())
+}
+
+/// Logs the CIS-2 events for newly minted tokens and records them against the owner.
+/// Claimed tokens pass their unlock time and stay locked until then, editions by
+/// their token ID.
+#[allow(clippy::too_many_arguments)]
+fn mint_tokens<S: HasStateApi>(
+    state: &mut State<S>,
+    logger: &mut impl HasLogger,
+    token_id: ContractTokenId,
+    amount_of_tokens: u32,
+    owner: Address,
+    owner_string: String,
+    slot_time: Timestamp,
+    unlock_time: Option<Timestamp>,
+) -> Result<(), Error> {
+    // Event for minted token.
+    let log_mint_result = logger.log(&Cis2Event::Mint(MintEvent {
+        token_id,
+        amount: ContractTokenAmount::from(amount_of_tokens),
+        owner,
+    }));
+
+    match log_mint_result {
+        Ok(_) => (),
+        Err(error) => match error {
+            LogError::Full => {
+                return Err(Error::MintingLogFull);
+            }
+            LogError::Malformed => {
+                return Err(Error::MintingLogMalformed);
+            }
+        },
+    }
+
+    // Metadata URL for the token.
+    let log_meta_result = logger.log(&Cis2Event::TokenMetadata::<_, ContractTokenAmount>(
+        TokenMetadataEvent {
+            token_id,
+            metadata_url: state.metadata_url(token_id),
+        },
+    ));
+
+    match log_meta_result {
+        Ok(_) => (),
+        Err(error) => match error {
+            LogError::Full => {
+                return Err(Error::MetaDataLogFull);
+            }
+            LogError::Malformed => {
+                return Err(Error::MetaDataLogMalformed);
+            }
+        },
+    }
+
+    if round_of(&token_id) == 0 && state.is_edition_mode() {
+        // Editions can have many holders, so copies are counted per holder instead of recording an owner
+        state.add_edition_balance(token_id, owner, amount_of_tokens);
+        let mut minted = state.edition_minted.entry(token_id).or_insert(0);
+        *minted += amount_of_tokens;
+        state.taken_count += amount_of_tokens;
+    } else if round_of(&token_id) != 0 {
+        // Tokens of later rounds are counted by the round rather than the main collection
+        for offset in 0..amount_of_tokens {
+            state
+                .token_owners
+                .insert(offset_token_id(token_id, offset), owner);
+            state
+                .claimed_at
+                .insert(offset_token_id(token_id, offset), slot_time);
+        }
+    } else if state.tracks_indexes() {
+        state.taken_indexes.insert(token_id, owner_string);
+        state.taken_count += 1;
+        state.token_owners.insert(token_id, owner);
+        state.claimed_at.insert(token_id, slot_time);
+    } else if state.freed_token_ids.remove(&token_id) {
+        // A burned sequential ID claimed again from the waitlist
+        state.freed_count -= 1;
+        state.token_owners.insert(token_id, owner);
+        state.claimed_at.insert(token_id, slot_time);
+    } else {
+        for offset in 0..amount_of_tokens {
+            state
+                .token_owners
+                .insert(offset_token_id(token_id, offset), owner);
+            state
+                .claimed_at
+                .insert(offset_token_id(token_id, offset), slot_time);
+        }
+        state.next_token_id += amount_of_tokens;
+    }
+
+    if let Some(unlock_time) = unlock_time {
+        let locked = if round_of(&token_id) == 0 && state.is_edition_mode() {
+            1
+        } else {
+            amount_of_tokens
+        };
+        for offset in 0..locked {
+            state
+                .token_unlock_times
+                .insert(offset_token_id(token_id, offset), unlock_time);
+        }
+    }
+
+    state.add_claimed(owner, amount_of_tokens);
+
+    Ok(())
+}
+
+/// Returns the address of a CIS-2 receiver.
+fn receiver_address(receiver: &Receiver) -> Address {
+    match receiver {
+        Receiver::Account(account) => Address::Account(*account),
+        Receiver::Contract(contract, _) => Address::Contract(*contract),
+    }
+}
+
+/// Moves tokens from one holder to a receiver, checking that `from` holds them
+/// and that neither is denylisted.  Events and receive hooks are left to the caller.
+fn move_token<S: HasStateApi>(
+    state: &mut State<S>,
+    token_id: ContractTokenId,
+    amount: ContractTokenAmount,
+    from: Address,
+    to: &Receiver,
+) -> Result<(), Error> {
+    let receiver = receiver_address(to);
+    if state.denylist.contains(&from) || state.denylist.contains(&receiver) {
+        return Err(Error::AddressDenied);
+    }
+
+    if state.is_edition_mode() {
+        let balance = state
+            .edition_balances
+            .get(&(token_id, from))
+            .map_or(0, |balance| *balance);
+        if amount.0 == 0 || amount.0 > balance {
+            return Err(Error::InvalidTransfer);
+        }
+        state
+            .edition_balances
+            .insert((token_id, from), balance - amount.0);
+        state.add_edition_balance(token_id, receiver, amount.0);
+    } else {
+        let owner = match state.token_owners.get(&token_id) {
+            Some(owner) => *owner,
+            None => return Err(Error::UnknownToken),
+        };
+        if from != owner || amount != ContractTokenAmount::from(1) {
+            return Err(Error::InvalidTransfer);
+        }
+
+        state.token_owners.insert(token_id, receiver);
+        if state.tracks_indexes() {
+            state
+                .taken_indexes
+                .insert(token_id, address_to_string(receiver));
+        }
+    }
+
+    Ok(())
+}
+
+/// Calls the CIS-2 `onReceivingCIS2` hook when tokens are sent to a contract.
+fn invoke_receive_hook<S: HasStateApi>(
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    receiver: &Receiver,
+    token_id: ContractTokenId,
+    amount: ContractTokenAmount,
+    from: Address,
+    data: AdditionalData,
+) -> Result<(), Error> {
+    if let Receiver::Contract(address, function) = receiver {
+        let parameter = OnReceivingCis2Params {
+            token_id,
+            amount,
+            from,
+            data,
+        };
+        host.invoke_contract(
+            address,
+            &parameter,
+            function.as_entrypoint_name(),
+            Amount::zero(),
+        )
+        .map_err(|_| Error::ReceiveHookFailed)?;
+    }
+    Ok(())
+}
+
+/// Mints a token directly to each recipient.  The token IDs are only used in
+/// selected index mode, and random index mode draws them as claims do.
+/// Reserved tokens cannot be airdropped.
+/// Only the contract owner can call this.
+#[receive(
+    contract = "airdrop_project",
+    name = "airdrop_to",
+    parameter = "AirdropParams",
+    error = "Error",
+    mutable,
+    enable_logger
+)]
+fn airdrop_to<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+) -> Result<(), Error> {
+    check_admin(ctx)?;
+    let params: AirdropParams = ctx.parameter_cursor().get()?;
+    let slot_time = ctx.metadata().slot_time();
+    let state = host.state_mut();
+
+    for (recipient, selected_token) in params.recipients {
+        if state.claimed_count() + 1
+            > state
+                .nft_limit
+                .saturating_sub(state.nft_reserve.unwrap_or(0))
+        {
+            return Err(Error::NFTLimitReached);
+        }
+        state.check_address_limit(&Address::Account(recipient), 1)?;
+
+        // Tokens are picked as claims pick them, drawing at random in random index mode
+        let token_id = if state.random_index {
+            state.draw_random_token_id(slot_time)?
+        } else if state.selected_index {
+            state.token_id_to_use(selected_token)?
+        } else {
+            state.next_free_token_id()
+        };
+        state.check_edition_supply(&token_id, 1)?;
+        mint_tokens(
+            state,
+            logger,
+            token_id,
+            1,
+            Address::Account(recipient),
+            account_address_to_string(recipient),
+            slot_time,
+            None,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Mints the tokens of a claim made on another chain, attested by the bridge key
+/// set at init.  Each claim on the source chain can only be bridged once.  The
+/// nft limit, the per address limit and the reserve apply, while the whitelist
+/// and the price are left to the source chain.  Anyone can submit the attestation.
+#[receive(
+    contract = "airdrop_project",
+    name = "claim_bridged",
+    parameter = "BridgeClaimParams",
+    error = "Error",
+    mutable,
+    enable_logger,
+    crypto_primitives
+)]
+fn claim_bridged<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+    crypto_primitives: &impl HasCryptoPrimitives,
+) -> Result<(), Error> {
+    let params: BridgeClaimParams = ctx.parameter_cursor().get()?;
+    let slot_time = ctx.metadata().slot_time();
+    let state = host.state_mut();
+
+    if state.paused {
+        return Err(Error::ContractPaused);
+    }
+    if state.closed {
+        return Err(Error::AirdropNowClosed);
+    }
+    if state.denylist.contains(&params.claimer) {
+        return Err(Error::AddressDenied);
+    }
+
+    let bridge_key = state.bridge_key.ok_or(Error::InvalidBridgeAttestation)?;
+    let message = BridgeMessage {
+        contract_address: ctx.self_address(),
+        source_chain: params.source_chain,
+        source_id: params.source_id,
+        claimer: params.claimer,
+        amount: params.amount,
+    };
+    if !crypto_primitives.verify_ed25519_signature(
+        bridge_key,
+        params.signature,
+        &to_bytes(&message),
+    ) {
+        return Err(Error::InvalidBridgeAttestation);
+    }
+    if !state
+        .bridged_claims
+        .insert((message.source_chain, message.source_id))
+    {
+        return Err(Error::AlreadyClaimed);
+    }
+
+    if params.amount == 0
+        || state.claimed_count() + params.amount
+            > state
+                .nft_limit
+                .saturating_sub(state.nft_reserve.unwrap_or(0))
+    {
+        return Err(Error::NFTLimitReached);
+    }
+    state.check_address_limit(&params.claimer, params.amount)?;
+
+    let first_token_id = state.next_free_token_id();
+    for _ in 0..params.amount {
+        let token_id = state.next_free_token_id();
+        state.check_edition_supply(&token_id, 1)?;
+        mint_tokens(
+            state,
+            logger,
+            token_id,
+            1,
+            params.claimer,
+            address_to_string(params.claimer),
+            slot_time,
+            state.unlock_time,
+        )?;
+    }
+    log_claim(
+        state,
+        logger,
+        first_token_id,
+        params.claimer,
+        params.amount,
+        slot_time,
+    )?;
+    log_sold_out(state, logger, slot_time)
+}
+
+/// Mints tokens from the reserve, reducing the reserve by the amount minted.
+/// The receive hook is called for each token when minting to a contract.
+/// Only the contract owner can call this.
+#[receive(
+    contract = "airdrop_project",
+    name = "mint_reserve",
+    parameter = "MintReserveParams",
+    error = "Error",
+    mutable,
+    enable_logger
+)]
+fn mint_reserve<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+) -> Result<(), Error> {
+    check_admin(ctx)?;
+    let params: MintReserveParams = ctx.parameter_cursor().get()?;
+    mint_from_reserve(ctx, host, params, logger)
+}
+
+/// Mints tokens from the reserve to the receiver, reducing the reserve by the
+/// amount minted.  The unclaimed reserve allocations are left for their addresses.
+fn mint_from_reserve<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    params: MintReserveParams,
+    logger: &mut impl HasLogger,
+) -> Result<(), Error> {
+    if host.state().supply_locked {
+        return Err(Error::SupplyLocked);
+    }
+    let owner = receiver_address(&params.to);
+
+    // Tokens allocated to addresses in the reserve are left for them to claim
+    let reserve = host.state().nft_reserve.unwrap_or(0);
+    if params.count > reserve
+        || host.state().claimed_count() + params.count
+            > host
+                .state()
+                .nft_limit
+                .saturating_sub(host.state().reserve_allocated)
+    {
+        return Err(Error::NFTLimitReached);
+    }
+
+    for _ in 0..params.count {
+        let state = host.state_mut();
+        let token_id = state.next_free_token_id();
+        state.check_edition_supply(&token_id, 1)?;
+        mint_tokens(
+            state,
+            logger,
+            token_id,
+            1,
+            owner,
+            address_to_string(owner),
+            ctx.metadata().slot_time(),
+            None,
+        )?;
+        invoke_receive_hook(
+            host,
+            &params.to,
+            token_id,
+            ContractTokenAmount::from(1),
+            Address::Contract(ctx.self_address()),
+            params.data.clone(),
+        )?;
+    }
+
+    let state = host.state_mut();
+    state.nft_reserve = if reserve == params.count {
+        None
+    } else {
+        Some(reserve - params.count)
+    };
+
+    Ok(())
+}
+
+/// Lets a partner contract, such as a game or a marketplace, mint up to the
+/// given number of tokens from the reserve with `mint_reserve_for`.  A quota of 0
+/// removes the partner.  Only the contract owner can call this.
+#[receive(
+    contract = "airdrop_project",
+    name = "set_partner_quota",
+    parameter = "PartnerQuotaParams",
+    error = "Error",
+    mutable
+)]
+fn set_partner_quota<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+) -> Result<(), Error> {
+    check_admin(ctx)?;
+    let params: PartnerQuotaParams = ctx.parameter_cursor().get()?;
+    let state = host.state_mut();
+    if params.quota == 0 {
+        state.partner_quotas.remove(&params.partner);
+    } else {
+        state.partner_quotas.insert(params.partner, params.quota);
+    }
+    Ok(())
+}
+
+/// Mints tokens from the reserve on behalf of the partner contract sending the
+/// transaction, within the quota set for it with `set_partner_quota`.
+#[receive(
+    contract = "airdrop_project",
+    name = "mint_reserve_for",
+    parameter = "MintReserveParams",
+    error = "Error",
+    mutable,
+    enable_logger
+)]
+fn mint_reserve_for<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+) -> Result<(), Error> {
+    let partner = match ctx.sender() {
+        Address::Contract(partner) => partner,
+        Address::Account(_) => return Err(Error::Unauthorized),
+    };
+    let params: MintReserveParams = ctx.parameter_cursor().get()?;
+    let state = host.state_mut();
+    let quota = state
+        .partner_quotas
+        .get(&partner)
+        .map(|quota| *quota)
+        .ok_or(Error::Unauthorized)?;
+    if params.count > quota {
+        return Err(Error::PartnerQuotaExceeded);
+    }
+    // The quota is used up before the receive hooks run, so a hook can't mint
+    // against it again
+    if params.count == quota {
+        state.partner_quotas.remove(&partner);
+    } else {
+        state.partner_quotas.insert(partner, quota - params.count);
+    }
+    mint_from_reserve(ctx, host, params, logger)
+}
+
+/// View function that returns the number of reserved tokens a partner contract
+/// can still mint with `mint_reserve_for`
+#[receive(
+    contract = "airdrop_project",
+    name = "partner_quota",
+    parameter = "ContractAddress",
+    return_value = "u32"
+)]
+fn partner_quota<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ReceiveResult<u32> {
+    let partner: ContractAddress = ctx.parameter_cursor().get()?;
+    Ok(host
+        .state()
+        .partner_quotas
+        .get(&partner)
+        .map_or(0, |quota| *quota))
+}
+
+/// Burns a token.  Can be called by the owner of the token or one of their operators.
+#[receive(
+    contract = "airdrop_project",
+    name = "burn",
+    parameter = "TokenParam",
+    error = "Error",
+    mutable,
+    enable_logger
+)]
+fn burn<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+) -> Result<(), Error> {
+    let params: TokenParam = ctx.parameter_cursor().get()?;
+    let state = host.state_mut();
+    let sender = ctx.sender();
+
+    // Editions burn one of the sender's copies
+    if state.is_edition_mode() {
+        let balance = state
+            .edition_balances
+            .get(&(params.token, sender))
+            .map_or(0, |balance| *balance);
+        if balance == 0 {
+            return Err(Error::UnknownToken);
+        }
+        state
+            .edition_balances
+            .insert((params.token, sender), balance - 1);
+
+        logger.log(&Cis2Event::Burn(BurnEvent {
+            token_id: params.token,
+            amount: ContractTokenAmount::from(1),
+            owner: sender,
+        }))?;
+
+        return Ok(());
+    }
+
+    let owner = match state.token_owners.get(&params.token) {
+        Some(owner) => *owner,
+        None => return Err(Error::UnknownToken),
+    };
+    if sender != owner && !state.operators.contains(&(owner, sender)) {
+        return Err(Error::Unauthorized);
+    }
+
+    burn_token(state, logger, params.token, owner)
+}
+
+/// Removes a token which is not an edition from its owner and logs a CIS-2 Burn event.
+fn burn_token<S: HasStateApi>(
+    state: &mut State<S>,
+    logger: &mut impl HasLogger,
+    token_id: ContractTokenId,
+    owner: Address,
+) -> Result<(), Error> {
+    state.token_owners.remove(&token_id);
+    state.claimed_at.remove(&token_id);
+    state.token_unlock_times.remove(&token_id);
+    if state.taken_indexes.remove_and_get(&token_id).is_some() {
+        state.taken_count -= 1;
+    } else if !state.tracks_indexes() && round_of(&token_id) == 0 {
+        // Burns never lower next_token_id, so the ID is kept for the waitlist
+        state.freed_token_ids.insert(token_id);
+        state.freed_count += 1;
+    }
+
+    logger.log(&Cis2Event::Burn(BurnEvent {
+        token_id,
+        amount: ContractTokenAmount::from(1),
+        owner,
+    }))?;
+
+    Ok(())
+}
+
+/// Burns a claimed token and mints a new token drawn at random from the
+/// unclaimed ones to its owner, in random_index mode.  The burned token ID
+/// is not put back in the pool.  Locked tokens can't be rerolled until their
+/// unlock time.  Can be called by the owner of the token or one of their operators.
+#[receive(
+    contract = "airdrop_project",
+    name = "reroll",
+    parameter = "TokenParam",
+    error = "Error",
+    mutable,
+    enable_logger
+)]
+fn reroll<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+) -> Result<(), Error> {
+    let params: TokenParam = ctx.parameter_cursor().get()?;
+    let state = host.state_mut();
+    let sender = ctx.sender();
+    let slot_time = ctx.metadata().slot_time();
+
+    if !state.random_index || round_of(&params.token) != 0 {
+        return Err(Error::RerollUnavailable);
+    }
+    if state.closed {
+        return Err(Error::AirdropNowClosed);
+    }
+    let owner = match state.token_owners.get(&params.token) {
+        Some(owner) => *owner,
+        None => return Err(Error::UnknownToken),
+    };
+    if sender != owner && !state.operators.contains(&(owner, sender)) {
+        return Err(Error::Unauthorized);
+    }
+    // Rerolling would swap a locked token for an unlocked one
+    if let Some(unlock_time) = state.token_unlock_times.get(&params.token) {
+        if slot_time < *unlock_time {
+            return Err(Error::TokenLocked);
+        }
+    }
+
+    // Draw first, so the reroll fails with NFTLimitReached once the pool is empty
+    let token_id = state.draw_random_token_id(slot_time)?;
+    burn_token(state, logger, params.token, owner)?;
+    mint_tokens(
+        state,
+        logger,
+        token_id,
+        1,
+        owner,
+        address_to_string(owner),
+        slot_time,
+        None,
+    )?;
+
+    // The new token replaces the burned one in the owner's claims
+    if let Some(mut tokens) = state.claimed_nfts.get_mut(&owner) {
+        *tokens -= 1;
+    }
+
+    logger.log(&AirdropEvent::Reroll(RerollEvent {
+        burned: params.token,
+        minted: token_id,
+        owner,
+    }))?;
+
+    Ok(())
+}
+
+/// Transfers tokens, following the CIS-2 `transfer` standard.  Can be called by
+/// the owner of the token or one of their operators.  Transfers are rejected
+/// entirely while the contract is paused and for soulbound collections, and for
+/// locked tokens until their unlock time.
+#[receive(
+    contract = "airdrop_project",
+    name = "transfer",
+    parameter = "TransferParameter",
+    error = "Error",
+    mutable,
+    enable_logger
+)]
+fn transfer<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+) -> Result<(), Error> {
+    let TransferParams(transfers): TransferParameter = ctx.parameter_cursor().get()?;
+    let sender = ctx.sender();
+
+    if host.state().paused {
+        return Err(Error::ContractPaused);
+    }
+    if host.state().soulbound {
+        return Err(Error::TokenIsSoulbound);
+    }
+
+    for Transfer {
+        token_id,
+        amount,
+        from,
+        to,
+        data,
+    } in transfers
+    {
+        let state = host.state_mut();
+        if sender != from && !state.operators.contains(&(from, sender)) {
+            return Err(Error::Unauthorized);
+        }
+        if let Some(unlock_time) = state.token_unlock_times.get(&token_id) {
+            if ctx.metadata().slot_time() < *unlock_time {
+                return Err(Error::TokenLocked);
+            }
+        }
+        move_token(state, token_id, amount, from, &to)?;
+
+        let receiver = receiver_address(&to);
+        logger.log(&Cis2Event::Transfer(TransferEvent {
+            token_id,
+            amount,
+            from,
+            to: receiver,
+        }))?;
+
+        invoke_receive_hook(host, &to, token_id, amount, from, data)?;
+    }
+
+    Ok(())
+}
+
+/// Adds or removes operators for the sender, following the CIS-2 `updateOperator` standard.
+#[receive(
+    contract = "airdrop_project",
+    name = "updateOperator",
+    parameter = "UpdateOperatorParams",
+    error = "Error",
+    mutable,
+    enable_logger
+)]
+fn update_operator<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+) -> Result<(), Error> {
+    let UpdateOperatorParams(params) = ctx.parameter_cursor().get()?;
+    let owner = ctx.sender();
+    let state = host.state_mut();
+
+    for param in params {
+        match param.update {
+            OperatorUpdate::Add => state.operators.insert((owner, param.operator)),
+            OperatorUpdate::Remove => state.operators.remove(&(owner, param.operator)),
+        };
+
+        logger.log(
+            &Cis2Event::<ContractTokenId, ContractTokenAmount>::UpdateOperator(
+                UpdateOperatorEvent {
+                    owner,
+                    operator: param.operator,
+                    update: param.update,
+                },
+            ),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Adds addresses to the denylist, so they can no longer claim or transfer
+/// tokens even if whitelisted.  Only the contract owner can call this.
+#[receive(
+    contract = "airdrop_project",
+    name = "add_to_denylist",
+    parameter = "DenylistParams",
+    error = "Error",
+    mutable,
+    enable_logger
+)]
+fn add_to_denylist<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+) -> Result<(), Error> {
+    update_denylist(ctx, host, logger, true)
+}
+
+/// Removes addresses from the denylist.  Only the contract owner can call this.
+#[receive(
+    contract = "airdrop_project",
+    name = "remove_from_denylist",
+    parameter = "DenylistParams",
+    error = "Error",
+    mutable,
+    enable_logger
+)]
+fn remove_from_denylist<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+) -> Result<(), Error> {
+    update_denylist(ctx, host, logger, false)
+}
+
+fn update_denylist<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+    denied: bool,
+) -> Result<(), Error> {
+    check_admin(ctx)?;
+    let params: DenylistParams = ctx.parameter_cursor().get()?;
+    let state = host.state_mut();
+
+    for address in params.addresses {
+        if denied {
+            state.denylist.insert(address);
+        } else {
+            state.denylist.remove(&address);
+        }
+        logger.log(&AirdropEvent::DenylistUpdated(DenylistUpdatedEvent {
+            address,
+            denied,
+        }))?;
+    }
+
+    Ok(())
+}
+
+/// Pauses claiming.  Only the contract owner can call this.
+#[receive(contract = "airdrop_project", name = "pause", error = "Error", mutable)]
+fn pause<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+) -> Result<(), Error> {
+    check_admin(ctx)?;
+    host.state_mut().paused = true;
+    Ok(())
+}
+
+/// Resumes claiming after a pause.  Only the contract owner can call this.
+#[receive(
+    contract = "airdrop_project",
+    name = "unpause",
+    error = "Error",
+    mutable
+)]
+fn unpause<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+) -> Result<(), Error> {
+    check_admin(ctx)?;
+    host.state_mut().paused = false;
+    Ok(())
+}
+
+/// Ends the airdrop early.  Claims are rejected with AirdropNowClosed from then
+/// on, and this can not be undone.  Only the contract owner can call this.
+#[receive(
+    contract = "airdrop_project",
+    name = "close_airdrop",
+    error = "Error",
+    mutable
+)]
+fn close_airdrop<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+) -> Result<(), Error> {
+    check_admin(ctx)?;
+    host.state_mut().closed = true;
+    Ok(())
+}
+
+/// Fixes the metadata URLs for good, so collectors know they can not change.
+/// `reveal` is rejected with MetadataFrozen from then on, and this can not be
+/// undone.  The collection must be revealed first.  Only the contract owner can call this.
+#[receive(
+    contract = "airdrop_project",
+    name = "freeze_metadata",
+    error = "Error",
+    mutable
+)]
+fn freeze_metadata<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+) -> Result<(), Error> {
+    check_admin(ctx)?;
+    let state = host.state_mut();
+    if !state.revealed {
+        return Err(Error::MetadataNotRevealed);
+    }
+    state.metadata_frozen = true;
+    Ok(())
+}
+
+/// Fixes nft_limit for good and stops the admin minting the reserve, giving
+/// collectors a hard guarantee of the maximum supply.  `set_nft_limit` and
+/// `mint_reserve` are rejected with SupplyLocked from then on, and this can
+/// not be undone.  Only the contract owner can call this.
+#[receive(
+    contract = "airdrop_project",
+    name = "lock_supply",
+    error = "Error",
+    mutable
+)]
+fn lock_supply<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+) -> Result<(), Error> {
+    check_admin(ctx)?;
+    host.state_mut().supply_locked = true;
+    Ok(())
+}
+
+/// Raises or lowers the supply cap of the collection set up at init, for drops
+/// which decide to expand.  The cap can't go below the tokens already claimed
+/// and the unclaimed reserve allocations.  Only the contract owner can call this.
+#[receive(
+    contract = "airdrop_project",
+    name = "set_nft_limit",
+    parameter = "SetNftLimitParams",
+    error = "Error",
+    mutable,
+    enable_logger
+)]
+fn set_nft_limit<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+) -> Result<(), Error> {
+    check_admin(ctx)?;
+    let params: SetNftLimitParams = ctx.parameter_cursor().get()?;
+    let state = host.state_mut();
+
+    if state.supply_locked {
+        return Err(Error::SupplyLocked);
+    }
+
+    // The random draw shuffles a pool sized by the cap, which can't be resized
+    if state.random_index {
+        return Err(Error::ConflictingClaimModes);
+    }
+    // Token IDs of the collection must stay below those of the rounds
+    if params.nft_limit < state.claimed_count() + state.reserve_allocated
+        || u64::from(params.nft_limit) > 1u64 << ROUND_TOKEN_ID_SHIFT
+    {
+        return Err(Error::InvalidNftLimit);
+    }
+
+    let previous = state.nft_limit;
+    state.nft_limit = params.nft_limit;
+    // A raised cap can sell out again
+    if params.nft_limit > state.claimed_count() {
+        state.sold_out_logged = false;
+    }
+    logger.log(&AirdropEvent::NftLimitUpdated(NftLimitUpdatedEvent {
+        previous,
+        nft_limit: params.nft_limit,
+    }))?;
+
+    Ok(())
+}
+
+/// Replaces the whitelist.  Only the contract owner can call this.
+/// An empty whitelist removes the merkle tree, opening the airdrop to everyone.
+#[receive(
+    contract = "airdrop_project",
+    name = "set_whitelist",
+    parameter = "SetWhitelistParams",
+    error = "Error",
+    mutable,
+    enable_logger
+)]
+fn set_whitelist<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+) -> Result<(), Error> {
+    check_direct_admin(ctx, host.state())?;
+    let params: SetWhitelistParams = ctx.parameter_cursor().get()?;
+    replace_whitelist(
+        host.state_mut(),
+        params.whitelist,
+        params.whitelist_file,
+        logger,
+    )
+}
+
+/// Replaces the whitelist and logs the new root.  An empty whitelist removes the merkle tree.
+fn replace_whitelist<S: HasStateApi>(
+    state: &mut State<S>,
+    whitelist: Vec<String>,
+    whitelist_file: String,
+    logger: &mut impl HasLogger,
+) -> Result<(), Error> {
+    if state.has_duplicates(&whitelist) {
+        return Err(Error::DuplicateWhitelistEntry);
+    }
+    if whitelist.is_empty() {
+        state.merkle_tree = None;
+    } else {
+        state.create_hash_tree(whitelist);
+    }
+    state.whitelist = whitelist_file.clone();
+
+    logger.log(&AirdropEvent::WhitelistUpdated(WhitelistUpdatedEvent {
+        root: state.merkle_tree.as_ref().map(|tree| tree.hashroot.clone()),
+        whitelist_file,
+    }))?;
+
+    Ok(())
+}
+
+/// Uploads part of a whitelist which is too large to fit in a single parameter.
+/// Claims are rejected until the upload is completed with `finalize_whitelist`.
+/// Only the contract owner can call this.
+#[receive(
+    contract = "airdrop_project",
+    name = "add_whitelist_chunk",
+    parameter = "WhitelistChunkParams",
+    error = "Error",
+    mutable
+)]
+fn add_whitelist_chunk<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+) -> Result<(), Error> {
+    check_admin(ctx)?;
+    let mut params: WhitelistChunkParams = ctx.parameter_cursor().get()?;
+    host.state_mut()
+        .pending_whitelist
+        .append(&mut params.whitelist);
+    Ok(())
+}
+
+/// Builds the merkle tree from the uploaded whitelist chunks, replacing the
+/// current whitelist.  Only the contract owner can call this.
+#[receive(
+    contract = "airdrop_project",
+    name = "finalize_whitelist",
+    parameter = "FinalizeWhitelistParams",
+    error = "Error",
+    mutable,
+    enable_logger
+)]
+fn finalize_whitelist<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+) -> Result<(), Error> {
+    check_direct_admin(ctx, host.state())?;
+    let params: FinalizeWhitelistParams = ctx.parameter_cursor().get()?;
+    let state = host.state_mut();
+
+    let whitelist = core::mem::take(&mut state.pending_whitelist);
+    replace_whitelist(state, whitelist, params.whitelist_file, logger)
+}
+
+/// Transfers CCD collected from paid claims to a beneficiary account.
+/// Only the contract owner can call this, and only when no admins were set
+/// at init.  Otherwise it goes through `propose_action`.
+#[receive(
+    contract = "airdrop_project",
+    name = "withdraw",
+    parameter = "WithdrawParams",
+    error = "Error",
+    mutable,
+    enable_logger
+)]
+fn withdraw<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+) -> Result<(), Error> {
+    check_direct_admin(ctx, host.state())?;
+    let params: WithdrawParams = ctx.parameter_cursor().get()?;
+    withdraw_to(host, params, logger)
+}
+
+/// Transfers collected CCD and logs a Withdraw event.
+fn withdraw_to<S: HasStateApi>(
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    params: WithdrawParams,
+    logger: &mut impl HasLogger,
+) -> Result<(), Error> {
+    // Proceeds which are split between beneficiaries are paid out by release
+    if !host.state().beneficiaries.is_empty() {
+        return Err(Error::PaymentSplitterActive);
+    }
+    // The funds for claims in CCD airdrop mode are only paid out by sweep_ccd_airdrop,
+    // and the deposits of raffle entries which were not drawn by refund_deposit
+    let state = host.state();
+    let held = state
+        .ccd_pool
+        .checked_add(state.raffle_deposits)
+        .ok_or(Error::InsufficientFunds)?;
+    if held > Amount::zero() {
+        let needed = params
+            .amount
+            .checked_add(held)
+            .ok_or(Error::InsufficientFunds)?;
+        if needed > host.self_balance() {
+            return Err(Error::InsufficientFunds);
+        }
+    }
+
+    host.invoke_transfer(&params.to, params.amount)?;
+
+    logger.log(&AirdropEvent::Withdraw(WithdrawEvent {
+        amount: params.amount,
+        to: params.to,
+    }))?;
+
+    Ok(())
+}
+
+/// Pays every beneficiary their share of the proceeds collected so far, less
+/// what has already been paid to them.  Anyone can call this.
+#[receive(
+    contract = "airdrop_project",
+    name = "release",
+    error = "Error",
+    mutable,
+    enable_logger
+)]
+fn release<S: HasStateApi>(
+    _ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+) -> Result<(), Error> {
+    let collected = u128::from(host.state().collected_funds.micro_ccd);
+
+    for beneficiary in host.state().beneficiaries.clone() {
+        let released = host
+            .state()
+            .released
+            .get(&beneficiary.account)
+            .map_or(Amount::zero(), |released| *released);
+        let owed = (collected * u128::from(beneficiary.share) / 100) as u64;
+        let amount = Amount::from_micro_ccd(owed.saturating_sub(released.micro_ccd));
+        if amount == Amount::zero() {
+            continue;
+        }
+
+        host.invoke_transfer(&beneficiary.account, amount)?;
+        host.state_mut()
+            .released
+            .insert(beneficiary.account, released + amount);
+
+        logger.log(&AirdropEvent::Withdraw(WithdrawEvent {
+            amount,
+            to: beneficiary.account,
+        }))?;
+    }
+
+    Ok(())
+}
+
+/// Reveals a hidden drop by setting the real base URL and logging the new
+/// metadata URL of every minted token.  Only the contract owner can call this.
+#[receive(
+    contract = "airdrop_project",
+    name = "reveal",
+    parameter = "RevealParams",
+    error = "Error",
+    mutable,
+    enable_logger
+)]
+fn reveal<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+) -> Result<(), Error> {
+    check_admin(ctx)?;
+    let params: RevealParams = ctx.parameter_cursor().get()?;
+    let state = host.state_mut();
+
+    if state.metadata_frozen {
+        return Err(Error::MetadataFrozen);
+    }
+
+    state.base_url = params.base_url;
+    state.revealed = true;
+
+    let token_ids: Vec<ContractTokenId> = state
+        .token_owners
+        .iter()
+        .map(|(token_id, _)| *token_id)
+        .collect();
+    for token_id in token_ids {
+        logger.log(&Cis2Event::TokenMetadata::<_, ContractTokenAmount>(
+            TokenMetadataEvent {
+                token_id,
+                metadata_url: state.metadata_url(token_id),
+            },
+        ))?;
+    }
+
+    Ok(())
+}
+
+/// Reveals the salt committed at init, which seeds the random token ID draws.
+/// Random claims fail until this is called.  Only the contract owner can call this.
+#[receive(
+    contract = "airdrop_project",
+    name = "reveal_random_salt",
+    parameter = "RandomSaltParams",
+    error = "Error",
+    mutable
+)]
+fn reveal_random_salt<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+) -> Result<(), Error> {
+    check_admin(ctx)?;
+    let params: RandomSaltParams = ctx.parameter_cursor().get()?;
+    let state = host.state_mut();
+
+    if digest(params.salt.clone()) != state.random_salt_commitment {
+        return Err(Error::WrongRandomSalt);
+    }
+    state.random_salt = Some(params.salt);
+
+    Ok(())
+}
+
+/// Reveals the seed committed at init which shuffles the metadata of the
+/// collection, once it has sold out or closed, and logs the new metadata URL of
+/// every minted token.  Only the contract owner can call this.
+#[receive(
+    contract = "airdrop_project",
+    name = "reveal_shuffle_seed",
+    parameter = "RandomSaltParams",
+    error = "Error",
+    mutable,
+    enable_logger
+)]
+fn reveal_shuffle_seed<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+) -> Result<(), Error> {
+    check_admin(ctx)?;
+    let params: RandomSaltParams = ctx.parameter_cursor().get()?;
+    let slot_time = ctx.metadata().slot_time();
+    let state = host.state_mut();
+
+    if state.metadata_shuffle.is_some() {
+        return Err(Error::ShuffleAlreadyRevealed);
+    }
+    if Some(digest(params.salt.clone())) != state.shuffle_seed_commitment {
+        return Err(Error::WrongRandomSalt);
+    }
+    // Revealing earlier would tell claimers which tokens get which metadata
+    let closed = state.closed || state.nft_time_limit.is_some_and(|limit| slot_time > limit);
+    if state.claimed_count() < state.nft_limit && !closed {
+        return Err(Error::NotSoldOut);
+    }
+    state.metadata_shuffle = Some(MetadataShuffle::new(&params.salt, state.nft_limit));
+
+    let token_ids: Vec<ContractTokenId> = state
+        .token_owners
+        .iter()
+        .map(|(token_id, _)| *token_id)
+        .collect();
+    for token_id in token_ids {
+        logger.log(&Cis2Event::TokenMetadata::<_, ContractTokenAmount>(
+            TokenMetadataEvent {
+                token_id,
+                metadata_url: state.metadata_url(token_id),
+            },
+        ))?;
+    }
+
+    Ok(())
+}
+
+/// Creates a new airdrop round with its own limits, times, whitelist and base URL.
+/// The token IDs of the round start at the round ID shifted into the top bits.
+/// Only the contract owner can call this.
+#[receive(
+    contract = "airdrop_project",
+    name = "create_round",
+    parameter = "CreateRoundParams",
+    error = "Error",
+    mutable,
+    enable_logger
+)]
+fn create_round<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+) -> Result<(), Error> {
+    check_admin(ctx)?;
+    let params: CreateRoundParams = ctx.parameter_cursor().get()?;
+    let state = host.state_mut();
+
+    let round_id = state.round_count + 1;
+    if round_id > MAX_ROUND_ID || u64::from(params.nft_limit) > 1u64 << ROUND_TOKEN_ID_SHIFT {
+        return Err(Error::InvalidRound);
+    }
+    if state.has_duplicates(&params.whitelist) {
+        return Err(Error::DuplicateWhitelistEntry);
+    }
+
+    let round = Round {
+        nft_limit: params.nft_limit,
+        nft_limit_per_address: if params.nft_limit_per_address != 0 {
+            Some(params.nft_limit_per_address)
+        } else {
+            None
+        },
+        start_time: Timestamp::from_timestamp_millis(params.start_time),
+        end_time: if params.end_time != 0 {
+            Some(Timestamp::from_timestamp_millis(params.end_time))
+        } else {
+            None
+        },
+        merkle_tree: if params.whitelist.is_empty() {
+            None
+        } else {
+            Some(MerkleTree::new(
+                params.whitelist,
+                state.sorted_pairs,
+                state.merkle_hash,
+                state.leaf_encoding,
+            ))
+        },
+        base_url: params.base_url,
+        mint_price: params.mint_price,
+        minted: 0,
+    };
+    state.rounds.insert(round_id, round);
+    state.round_count = round_id;
+
+    logger.log(&AirdropEvent::RoundCreated(RoundCreatedEvent {
+        round_id,
+        first_token_id: round_token_id(round_id, 0),
+    }))?;
+
+    Ok(())
+}
+
+/// Upgrades the contract instance to a new module and optionally calls a
+/// migration entrypoint in the new module.  This is a low level entrypoint so
+/// the state is not written back after the upgrade, as the new module may have
+/// changed its layout.  Only the contract owner can call this.
+#[receive(
+    contract = "airdrop_project",
+    name = "upgrade",
+    parameter = "UpgradeParams",
+    error = "Error",
+    low_level
+)]
+fn upgrade<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<S, StateApiType = S>,
+) -> Result<(), Error> {
+    let params: UpgradeParams = ctx.parameter_cursor().get()?;
+
+    // With admins set the upgrade has to be approved through propose_action first
+    let mut state: State<S> = host.state().read_root()?;
+    if state.admins.is_empty() {
+        check_admin(ctx)?;
+    } else {
+        sending_admin(ctx, &state)?;
+        if state.approved_upgrade.as_ref().map(to_bytes) != Some(to_bytes(&params)) {
+            return Err(Error::UpgradeNotApproved);
+        }
+        // An approval is good for a single upgrade
+        state.approved_upgrade = None;
+        host.state_mut().write_root(&state);
+    }
+
+    host.upgrade(params.module)?;
+
+    if let Some((function, parameter)) = params.migrate {
+        host.invoke_contract_raw(
+            &ctx.self_address(),
+            parameter.as_parameter(),
+            function.as_entrypoint_name(),
+            Amount::zero(),
+        )
+        .map_err(|_| Error::MigrationFailed)?;
+    }
+
+    Ok(())
+}
+
+/// View function that returns the price in CCD of the next token claimed,
+/// following the open phase or the price curve.  Fails with AirdropNotStarted
+/// or AirdropNowClosed when phases are set and none is open.
+#[receive(
+    contract = "airdrop_project",
+    name = "current_price",
+    return_value = "Amount"
+)]
+fn current_price<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ReceiveResult<Amount> {
+    Ok(host.state().current_price(ctx.metadata().slot_time())?)
+}
+
+/// View function that returns what a claim page shows for an address in one
+/// call: the remaining supply, the time left, the current price, whether the
+/// address is whitelisted, how many tokens it has claimed and whether claiming
+/// is paused.
+#[receive(
+    contract = "airdrop_project",
+    name = "dashboard",
+    parameter = "Address",
+    return_value = "DashboardReply"
+)]
+fn dashboard<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ReceiveResult<DashboardReply> {
+    let address: Address = ctx.parameter_cursor().get()?;
+    let state = host.state();
+    let slot_time = ctx.metadata().slot_time();
+
+    Ok(DashboardReply {
+        remaining: state.nft_limit.saturating_sub(state.claimed_count()),
+        time_remaining: state.nft_time_limit.map(|limit| {
+            limit
+                .duration_since(slot_time)
+                .unwrap_or_else(|| Duration::from_millis(0))
+        }),
+        current_price: state.current_price(slot_time).ok(),
+        whitelisted: state
+            .merkle_tree
+            .as_ref()
+            .map(|_| state.is_whitelisted(address)),
+        claimed: state
+            .claimed_nfts
+            .get(&address)
+            .map_or(0, |claimed| *claimed),
+        paused: state.paused,
+    })
+}
+
+/// View function that returns the claims made on each day of slot time from
+/// the day of `from`, for charting a drop.  Days without claims are returned
+/// with zero counts.  At most 366 days are returned.
+#[receive(
+    contract = "airdrop_project",
+    name = "claim_stats",
+    parameter = "ClaimStatsParams",
+    return_value = "Vec<(Timestamp, DailyClaims)>"
+)]
+fn claim_stats<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ReceiveResult<Vec<(Timestamp, DailyClaims)>> {
+    let params: ClaimStatsParams = ctx.parameter_cursor().get()?;
+    let state = host.state();
+    let first_day = params.from.timestamp_millis() / MILLIS_PER_DAY;
+    Ok(
+        (first_day..first_day + u64::from(params.days.min(MAX_STATS_DAYS)))
+            .map(|day| {
+                let claims = state
+                    .daily_claims
+                    .get(&day)
+                    .map_or_else(DailyClaims::default, |claims| *claims);
+                (
+                    Timestamp::from_timestamp_millis(day * MILLIS_PER_DAY),
+                    claims,
+                )
+            })
+            .collect(),
+    )
+}
+
+/// Proposes a sensitive admin action, approved by the proposing admin.  The
+/// action runs as soon as admin_threshold admins have approved it.  Only the
+/// admins set at init can call this.
+#[receive(
+    contract = "airdrop_project",
+    name = "propose_action",
+    parameter = "AdminAction",
+    error = "Error",
+    mutable,
+    enable_logger
+)]
+fn propose_action<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+) -> Result<(), Error> {
+    let admin = sending_admin(ctx, host.state())?;
+    let action: AdminAction = ctx.parameter_cursor().get()?;
+
+    let state = host.state_mut();
+    let action_id = state.next_action_id;
+    state.next_action_id += 1;
+    state.pending_actions.insert(
+        action_id,
+        PendingAction {
+            action,
+            approvals: Vec::new(),
+        },
+    );
+    approve_action(ctx, host, action_id, admin, logger)
+}
+
+/// Approves an admin action proposed with `propose_action`, running it once
+/// admin_threshold distinct admins have approved it.  Only the admins set at
+/// init can call this.
+#[receive(
+    contract = "airdrop_project",
+    name = "confirm_action",
+    parameter = "u64",
+    error = "Error",
+    mutable,
+    enable_logger
+)]
+fn confirm_action<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+) -> Result<(), Error> {
+    let admin = sending_admin(ctx, host.state())?;
+    let action_id: u64 = ctx.parameter_cursor().get()?;
+    approve_action(ctx, host, action_id, admin, logger)
+}
+
+/// Adds the admin's approval to a pending action, and runs and removes the
+/// action once it has enough approvals.
+fn approve_action<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    action_id: u64,
+    admin: AccountAddress,
+    logger: &mut impl HasLogger,
+) -> Result<(), Error> {
+    let state = host.state_mut();
+    let approvals = {
+        let mut pending = state
+            .pending_actions
+            .get_mut(&action_id)
+            .ok_or(Error::UnknownAction)?;
+        if pending.approvals.contains(&admin) {
+            return Err(Error::AlreadyApproved);
+        }
+        pending.approvals.push(admin);
+        pending.approvals.len() as u32
+    };
+    let executed = approvals >= state.admin_threshold;
+
+    logger.log(&AirdropEvent::ActionApproved(ActionApprovedEvent {
+        action_id,
+        admin,
+        approvals,
+        executed,
+    }))?;
+    if !executed {
+        return Ok(());
+    }
+
+    let pending = state
+        .pending_actions
+        .remove_and_get(&action_id)
+        .ok_or(Error::UnknownAction)?;
+    match pending.action {
+        AdminAction::Withdraw(params) => withdraw_to(host, params, logger),
+        AdminAction::SetWhitelist(params) => {
+            replace_whitelist(state, params.whitelist, params.whitelist_file, logger)
+        }
+        AdminAction::FinalizeWhitelist(params) => {
+            let whitelist = core::mem::take(&mut state.pending_whitelist);
+            replace_whitelist(state, whitelist, params.whitelist_file, logger)
+        }
+        AdminAction::Upgrade(params) => {
+            state.approved_upgrade = Some(params);
+            Ok(())
+        }
+        AdminAction::SweepCcdAirdrop(to) => {
+            sweep_ccd_airdrop_to(host, to, ctx.metadata().slot_time(), logger)
+        }
+    }
+}
+
+/// View function that returns a pending admin action and who has approved
+/// it, or None if there is no such action or it has already run
+#[receive(
+    contract = "airdrop_project",
+    name = "pending_action",
+    parameter = "u64",
+    return_value = "Option<PendingAction>"
+)]
+fn pending_action<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ReceiveResult<Option<PendingAction>> {
+    let action_id: u64 = ctx.parameter_cursor().get()?;
+    Ok(host
+        .state()
+        .pending_actions
+        .get(&action_id)
+        .map(|pending| PendingAction {
+            action: pending.action.clone(),
+            approvals: pending.approvals.clone(),
+        }))
+}
+
+/// View function that returns the metadata, whitelist and number of NFTs,
+/// along with a page of the claimed tokens
+#[receive(
+    contract = "airdrop_project",
+    name = "view",
+    parameter = "ViewParams",
+    return_value = "ViewResult"
+)]
+fn view<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ReceiveResult<ViewResult> {
+    let params: ViewParams = ctx.parameter_cursor().get()?;
+    let state = host.state();
+
+    // Token IDs are looked up one by one from the cursor rather than collecting
+    // the claimed ones, so the cost of a page is bounded by the limit.
+    let mut next = match params.from {
+        Some(token_id) => state.claimable_token_id(round_of(&token_id), round_index(&token_id)),
+        None => state.claimable_token_id(0, 0),
+    };
+    let mut claimed = Vec::new();
+    for _ in 0..params.limit {
+        let token_id = match next {
+            Some(token_id) => token_id,
+            None => break,
+        };
+        if let Some(owner) = state.owner_string(&token_id) {
+            claimed.push((token_id, owner));
+        }
+        next = state.claimable_token_id(round_of(&token_id), round_index(&token_id) + 1);
+    }
+
+    Ok(ViewResult {
+        metadata: state.metadata.clone(),
+        whitelist: state.whitelist.clone(),
+        number_of_nfts: state.nft_limit,
+        claimed,
+        next,
+    })
+}
+
+/// View function that returns the amount of tokens claimed by the address
+#[receive(
+    contract = "airdrop_project",
+    name = "balance_of",
+    parameter = "BalanceParam",
+    return_value = "u32"
+)]
+fn balance_of<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ReceiveResult<u32> {
+    let state: &State<S> = host.state();
+    let params: BalanceParam = ctx.parameter_cursor().get()?;
+
+    let res = state.claimed_nfts.get(&params.node);
+    if res.is_none() {
+        Ok(0)
+    } else {
+        Ok(*state.claimed_nfts.get(&params.node).unwrap())
+    }
+}
+
+/// View function that returns the total supply of available NFTs
+#[receive(
+    contract = "airdrop_project",
+    name = "total_supply",
+    return_value = "u32"
+)]
+fn total_supply<S: HasStateApi>(
+    _ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ReceiveResult<u32> {
+    Ok(host.state().nft_limit)
+}
+
+/// View function that returns the current supply of available NFTs.
+/// This is the same as the `remaining` field of `remaining_supply`.
+#[receive(
+    contract = "airdrop_project",
+    name = "current_supply",
+    return_value = "u32"
+)]
+fn current_supply<S: HasStateApi>(
+    _ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ReceiveResult<u32> {
+    Ok(host.state().nft_limit - host.state().claimed_count())
+}
+
+/// View function that returns the number of tokens claimed from the collection set up at init
+#[receive(
+    contract = "airdrop_project",
+    name = "claimed_count",
+    return_value = "u32"
+)]
+fn claimed_count<S: HasStateApi>(
+    _ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ReceiveResult<u32> {
+    Ok(host.state().claimed_count())
+}
+
+/// View function that returns how many reserve tokens an address can still
+/// claim from its allocation
+#[receive(
+    contract = "airdrop_project",
+    name = "reserve_allocation",
+    parameter = "Address",
+    return_value = "u32"
+)]
+fn reserve_allocation<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ReceiveResult<u32> {
+    let address: Address = ctx.parameter_cursor().get()?;
+    Ok(host
+        .state()
+        .reserve_allocations
+        .get(&address)
+        .map_or(0, |allocation| *allocation))
+}
+
+/// View function that returns how many tokens are left to claim and how many
+/// of those are held back for the whitelist reserve and the tiers
+#[receive(
+    contract = "airdrop_project",
+    name = "remaining_supply",
+    return_value = "RemainingSupplyReply"
+)]
+fn remaining_supply<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ReceiveResult<RemainingSupplyReply> {
+    let state = host.state();
+    let remaining = state.nft_limit.saturating_sub(state.claimed_count());
+    let reserved = if state.reserve_released(ctx.metadata().slot_time()) {
+        0
+    } else {
+        let reserve = state.nft_reserve.unwrap_or(0).min(remaining);
+        (reserve + state.unclaimed_tier_reserve()).min(remaining)
+    };
+
+    Ok(RemainingSupplyReply {
+        remaining,
+        reserved,
+    })
+}
+
+/// View function that checks the proof in a set of claim parameters the same
+/// way `claim_nft` does, without claiming.  Returns the error the claim would
+/// fail with when the proof is rejected.
+#[receive(
+    contract = "airdrop_project",
+    name = "verify_proof",
+    parameter = "ClaimNFTParams",
+    return_value = "VerifyProofReply",
+    crypto_primitives
+)]
+fn verify_proof<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+    crypto_primitives: &impl HasCryptoPrimitives,
+) -> ReceiveResult<VerifyProofReply> {
+    let params: ClaimNFTParams = ctx.parameter_cursor().get()?;
+    let reason = host.state().verify_proof(&params, crypto_primitives).err();
+    Ok(VerifyProofReply {
+        valid: reason.is_none(),
+        reason,
+    })
+}
+
+/// View function that returns the provenance hash committed at init, or None if there is none
+#[receive(
+    contract = "airdrop_project",
+    name = "provenance",
+    return_value = "Option<String>"
+)]
+fn provenance<S: HasStateApi>(
+    _ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ReceiveResult<Option<String>> {
+    Ok(host.state().provenance_hash.clone())
+}
+
+/// View function that returns the token ID the next sequential claim will receive.
+/// Returns None when claimers select their token, token IDs are drawn at random
+/// or every token has been claimed.
+#[receive(
+    contract = "airdrop_project",
+    name = "next_token_id",
+    return_value = "Option<ContractTokenId>"
+)]
+fn next_token_id<S: HasStateApi>(
+    _ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ReceiveResult<Option<ContractTokenId>> {
+    let state = host.state();
+    if state.tracks_indexes() || state.next_token_id >= state.nft_limit {
+        return Ok(None);
+    }
+    Ok(Some(index_token_id(state.next_token_id)))
+}
+
+/// View function that returns whether an account is on the main whitelist.
+/// The account is looked up by its hex encoded address, as written by the contract.
+#[receive(
+    contract = "airdrop_project",
+    name = "is_whitelisted",
+    parameter = "AccountAddress",
+    return_value = "bool"
+)]
+fn is_whitelisted<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ReceiveResult<bool> {
+    let account: AccountAddress = ctx.parameter_cursor().get()?;
+    Ok(host.state().is_whitelisted(Address::Account(account)))
+}
+
+/// View function that returns the owner of tokens or None if no one owns it
+#[receive(
+    contract = "airdrop_project",
+    name = "check_owner",
+    parameter = "TokenParam",
+    return_value = "CheckOwnerReply"
+)]
+fn check_owner<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ReceiveResult<CheckOwnerReply> {
+    let params: TokenParam = ctx.parameter_cursor().get()?;
+
+    Ok(CheckOwnerReply {
+        address: host.state().owner_string(&params.token),
+    })
+}
+
+/// View function that returns the owner of a token and when it was claimed, or
+/// None if the token has no single owner
+#[receive(
+    contract = "airdrop_project",
+    name = "claim_info",
+    parameter = "ContractTokenId",
+    return_value = "Option<ClaimInfoReply>"
+)]
+fn claim_info<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ReceiveResult<Option<ClaimInfoReply>> {
+    let token_id: ContractTokenId = ctx.parameter_cursor().get()?;
+    let state = host.state();
+
+    let owner = state.token_owners.get(&token_id).map(|owner| *owner);
+    let claimed_at = state
+        .claimed_at
+        .get(&token_id)
+        .map(|claimed_at| *claimed_at);
+    Ok(owner
+        .zip(claimed_at)
+        .map(|(owner, claimed_at)| ClaimInfoReply { owner, claimed_at }))
+}
+
+/// View function that returns the time until which a claimed token can't be
+/// transferred, or None if it was never locked
+#[receive(
+    contract = "airdrop_project",
+    name = "unlock_time",
+    parameter = "ContractTokenId",
+    return_value = "Option<Timestamp>"
+)]
+fn unlock_time<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ReceiveResult<Option<Timestamp>> {
+    let token_id: ContractTokenId = ctx.parameter_cursor().get()?;
+    Ok(host
+        .state()
+        .token_unlock_times
+        .get(&token_id)
+        .map(|unlock_time| *unlock_time))
+}
+
+/// View function that returns the owning account of each of the tokens, in
+/// order, or None for tokens no account owns, so indexers can resolve many
+/// tokens in one call
+#[receive(
+    contract = "airdrop_project",
+    name = "check_owners",
+    parameter = "Vec<ContractTokenId>",
+    return_value = "Vec<Option<AccountAddress>>"
+)]
+fn check_owners<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ReceiveResult<Vec<Option<AccountAddress>>> {
+    let token_ids: Vec<ContractTokenId> = ctx.parameter_cursor().get()?;
+    let state = host.state();
+
+    Ok(token_ids
+        .iter()
+        .map(
+            |token_id| match state.token_owners.get(token_id).map(|owner| *owner) {
+                Some(Address::Account(account)) => Some(account),
+                _ => None,
+            },
+        )
+        .collect())
+}
+
+/// View function that returns the settings of the airdrop
+#[receive(
+    contract = "airdrop_project",
+    name = "config",
+    return_value = "ConfigReply"
+)]
+fn config<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ReceiveResult<ConfigReply> {
+    Ok(host.state().config(ctx.metadata().slot_time()))
+}
+
+/// View function that returns a page of everything needed to rebuild the
+/// contract state elsewhere: the config, the counters, the token owners and
+/// the claims of each address.  Page through the token owners with next_token
+/// and through the other lists with increasing offsets until has_more is false.
+#[receive(
+    contract = "airdrop_project",
+    name = "snapshot",
+    parameter = "SnapshotParams",
+    return_value = "SnapshotReply"
+)]
+fn snapshot<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ReceiveResult<SnapshotReply> {
+    let params: SnapshotParams = ctx.parameter_cursor().get()?;
+    let state = host.state();
+
+    // Every list is read by key from the cursor rather than collecting the
+    // maps, so the cost of a page is bounded by the limit.
+    let mut next_token = match params.from_token {
+        Some(token_id) => state.claimable_token_id(round_of(&token_id), round_index(&token_id)),
+        None => state.claimable_token_id(0, 0),
+    };
+    let mut token_owners = Vec::new();
+    for _ in 0..params.limit {
+        let token_id = match next_token {
+            Some(token_id) => token_id,
+            None => break,
+        };
+        if let Some(owner) = state.token_owners.get(&token_id) {
+            token_owners.push((token_id, *owner));
+        }
+        next_token = state.claimable_token_id(round_of(&token_id), round_index(&token_id) + 1);
+    }
+
+    let page =
+        |len: u32| params.offset.min(len)..params.offset.saturating_add(params.limit).min(len);
+    let mut edition_balances = Vec::new();
+    for position in page(state.edition_holder_count) {
+        if let Some(key) = state.edition_holders.get(&position) {
+            let (token_id, holder) = *key;
+            let balance = state
+                .edition_balances
+                .get(&(token_id, holder))
+                .map_or(0, |balance| *balance);
+            edition_balances.push((token_id, holder, balance));
+        }
+    }
+    let mut claims = Vec::new();
+    for position in page(state.claim_address_count) {
+        if let Some(address) = state.claim_addresses.get(&position) {
+            let claimed = state
+                .claimed_nfts
+                .get(&address)
+                .map_or(0, |claimed| *claimed);
+            claims.push((*address, claimed));
+        }
+    }
+    let longest = state.edition_holder_count.max(state.claim_address_count);
+
+    Ok(SnapshotReply {
+        version: state.version,
+        config: state.config(ctx.metadata().slot_time()),
+        next_token_id: state.next_token_id,
+        claimed_count: state.claimed_count(),
+        claim_count: state.claim_count,
+        collected_funds: state.collected_funds,
+        token_owners,
+        next_token,
+        edition_balances,
+        claims,
+        has_more: page(longest).end < longest,
+    })
+}
+
+/// View function that returns the version of the contract and the behaviour
+/// this instance was set up with, so integrators can check what is deployed
+#[receive(
+    contract = "airdrop_project",
+    name = "contract_version",
+    return_value = "VersionReply"
+)]
+fn contract_version<S: HasStateApi>(
+    _ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ReceiveResult<VersionReply> {
+    let state = host.state();
+    Ok(VersionReply {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        state_version: state.version,
+        u64_token_ids: cfg!(feature = "u64_token_ids"),
+        paid: state.mint_price > Amount::zero()
+            || state.price_curve.is_some()
+            || state.payment_token.is_some()
+            || state
+                .phases
+                .iter()
+                .any(|phase| phase.price > Amount::zero()),
+        soulbound: state.soulbound,
+        selected_index: state.selected_index,
+        random_index: state.random_index,
+        editions: state.is_edition_mode(),
+    })
+}
+
+/// View function that returns the merkle root of the whitelist or None if no whitelist is set
+#[receive(
+    contract = "airdrop_project",
+    name = "merkle_root",
+    return_value = "Option<MerkleRootReply>"
+)]
+fn merkle_root<S: HasStateApi>(
+    _ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ReceiveResult<Option<MerkleRootReply>> {
+    Ok(host
+        .state()
+        .merkle_tree
+        .as_ref()
+        .map(|tree| MerkleRootReply {
+            root: tree.hashroot.clone(),
+            length: tree.length,
+            depth: tree.steps.len() as u32,
+        }))
+}
+
+/// View function that returns the tokens currently owned by the address
+#[receive(
+    contract = "airdrop_project",
+    name = "tokens_of",
+    parameter = "BalanceParam",
+    return_value = "Vec<ContractTokenId>"
+)]
+fn tokens_of<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ReceiveResult<Vec<ContractTokenId>> {
+    let params: BalanceParam = ctx.parameter_cursor().get()?;
+    let owner = params.node;
+
+    let state = host.state();
+
+    let mut tokens: Vec<ContractTokenId> = state
+        .token_owners
+        .iter()
+        .filter(|(_, token_owner)| **token_owner == owner)
+        .map(|(token_id, _)| *token_id)
+        .collect();
+    tokens.extend(
+        state
+            .edition_balances
+            .iter()
+            .filter(|(key, balance)| key.1 == owner && **balance > 0)
+            .map(|(key, _)| key.0),
+    );
+    tokens.sort_by_key(|token_id| token_id.0);
+
+    Ok(tokens)
+}
+
+/// View function that returns how many more copies of an edition can be minted
+#[receive(
+    contract = "airdrop_project",
+    name = "edition_supply",
+    parameter = "TokenParam",
+    return_value = "u32"
+)]
+fn edition_supply<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ReceiveResult<u32> {
+    let params: TokenParam = ctx.parameter_cursor().get()?;
+    Ok(host.state().edition_remaining(&params.token))
+}
+
+/// View function that returns the metadata URL of each token, following the CIS-2 standard
+#[receive(
+    contract = "airdrop_project",
+    name = "tokenMetadata",
+    parameter = "ContractTokenMetadataQueryParams",
+    return_value = "TokenMetadataQueryResponse",
+    error = "Error"
+)]
+fn token_metadata<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> Result<TokenMetadataQueryResponse, Error> {
+    let params: ContractTokenMetadataQueryParams = ctx.parameter_cursor().get()?;
+    let state = host.state();
+
+    let mut response = Vec::with_capacity(params.queries.len());
+    for token_id in params.queries {
+        if !state.token_exists(&token_id) {
+            return Err(Error::UnknownToken);
+        }
+        response.push(state.metadata_url(token_id));
+    }
+
+    Ok(TokenMetadataQueryResponse::from(response))
+}
+
+/// View function that returns the balance of each token for each address,
+/// following the CIS-2 standard
+#[receive(
+    contract = "airdrop_project",
+    name = "balanceOf",
+    parameter = "ContractBalanceOfQueryParams",
+    return_value = "ContractBalanceOfQueryResponse",
+    error = "Error"
+)]
+fn cis2_balance_of<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> Result<ContractBalanceOfQueryResponse, Error> {
+    let params: ContractBalanceOfQueryParams = ctx.parameter_cursor().get()?;
+    let state = host.state();
+
+    let mut response = Vec::with_capacity(params.queries.len());
+    for query in params.queries {
+        if !state.token_exists(&query.token_id) {
+            return Err(Error::UnknownToken);
+        }
+        response.push(state.token_balance(&query.token_id, &query.address));
+    }
+
+    Ok(ContractBalanceOfQueryResponse::from(response))
+}
+
+/// View function that returns whether each address is an operator of the
+/// owner, following the CIS-2 standard
+#[receive(
+    contract = "airdrop_project",
+    name = "operatorOf",
+    parameter = "OperatorOfQueryParams",
+    return_value = "OperatorOfQueryResponse",
+    error = "Error"
+)]
+fn operator_of<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> Result<OperatorOfQueryResponse, Error> {
+    let params: OperatorOfQueryParams = ctx.parameter_cursor().get()?;
+    let state = host.state();
+
+    let response: Vec<bool> = params
+        .queries
+        .iter()
+        .map(|query| state.operators.contains(&(query.owner, query.address)))
+        .collect();
+
+    Ok(OperatorOfQueryResponse::from(response))
+}
+
+/// View function that returns whether each of the given standards is
+/// supported, following the CIS-0 standard
+#[receive(
+    contract = "airdrop_project",
+    name = "supports",
+    parameter = "SupportsQueryParams",
+    return_value = "SupportsQueryResponse",
+    error = "Error"
+)]
+fn supports<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    _host: &impl HasHost<State<S>, StateApiType = S>,
+) -> Result<SupportsQueryResponse, Error> {
+    let params: SupportsQueryParams = ctx.parameter_cursor().get()?;
+
+    let response: Vec<SupportResult> = params
+        .queries
+        .iter()
+        .map(|standard| {
+            let standard = standard.as_standard_identifier();
+            if standard == CIS0_STANDARD_IDENTIFIER
+                || standard == CIS2_STANDARD_IDENTIFIER
+                || standard == CIS3_STANDARD_IDENTIFIER
+            {
+                SupportResult::Support
+            } else {
+                SupportResult::NoSupport
+            }
+        })
+        .collect();
+
+    Ok(SupportsQueryResponse::from(response))
+}
+
+#[concordium_cfg_test]
+mod tests {
+    use super::*;
+    use test_infrastructure::*;
+
+    /// Init parameters for a small open drop with every optional feature off.
+    /// Tests override the fields they need with struct update syntax.
+    fn default_init_params() -> InitParams {
+        InitParams {
+            nft_limit: 3,
+            nft_time_limit: 0,
+            whitelist: vec![],
+            reserve: 0,
+            base_url: String::new(),
+            whitelist_file: String::new(),
+            metadata: String::new(),
+            selected_index: false,
+            nft_limit_per_address: 0,
+            mint_price: Amount::zero(),
+            start_time: 0,
+            phases: vec![],
+            tiers: vec![],
+            soulbound: false,
+            placeholder_url: String::new(),
+            metadata_hashes: vec![],
+            allow_sponsored_claims: false,
+            random_index: false,
+            random_salt_commitment: String::new(),
+            voucher_key: None,
+            edition_sizes: vec![],
+            sorted_pairs: false,
+            merkle_hash: MerkleHash::Sha256,
+            reserve_release_time: 0,
+            beneficiaries: vec![],
+            marketplace: None,
+            max_per_tx: 0,
+            one_claim_per_account: false,
+            provenance_hash: String::new(),
+            reserved_ranges: vec![],
+            hex_token_ids: false,
+            payment_token: None,
+            raffle: None,
+            rate_limit: None,
+            leaf_encoding: LeafEncoding::NodeString,
+            reserve_allocations: vec![],
+            shuffle_seed_commitment: String::new(),
+            token_gate: None,
+            identity_policy: None,
+            admins: vec![],
+            admin_threshold: 0,
+            price_curve: None,
+            bridge_key: None,
+            airdrop_token: None,
+            ccd_unit: Amount::zero(),
+            unlock_time: 0,
+        }
+    }
+
+    /// Hashes an address into a leaf of a sha256 tree.
+    fn leaf(address: &str) -> String {
+        MerkleHash::Sha256.hash_leaf(address)
+    }
+
+    /// Hashes two nodes of a sha256 tree into their parent.
+    fn parent(left: &str, right: &str) -> String {
+        merkle_core::hash_pair(left, right, false, MerkleHash::Sha256.into())
+    }
+
+    /// Crypto primitives hashing as the host does, for claims checking proofs.
+    fn crypto_primitives() -> TestCryptoPrimitives {
+        fn hash(hash: MerkleHash, data: &[u8]) -> [u8; 32] {
+            decode_hex(&merkle_core::MerkleHash::from(hash).digest(data))
+                .and_then(|bytes| bytes.try_into().ok())
+                .unwrap_or_default()
+        }
+        let crypto_primitives = TestCryptoPrimitives::new();
+        crypto_primitives
+            .setup_hash_sha2_256_mock(|data| HashSha2256(hash(MerkleHash::Sha256, data)));
+        crypto_primitives
+            .setup_hash_keccak_256_mock(|data| HashKeccak256(hash(MerkleHash::Keccak256, data)));
+        crypto_primitives
+            .setup_hash_sha3_256_mock(|data| HashSha3256(hash(MerkleHash::Sha3_256, data)));
+        crypto_primitives
+    }
+
+    #[concordium_test]
+    /// Test that initializing the contract succeeds with some state.
+    fn test_init() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        let params = InitParams {
+            nft_limit: 0,
+            ..default_init_params()
+        };
+
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state_result = init(&ctx, &mut state_builder, Amount::zero());
+        state_result.unwrap();
+    }
+
+    #[concordium_test]
+    fn test_parse_params() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        const ADMIN: AccountAddress = AccountAddress([9u8; 32]);
+
+        let parameter_bytes = to_bytes(&default_init_params());
+        ctx.set_parameter(&parameter_bytes);
+
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+        let mut logger = TestLogger::init();
+
+        // malformed parameters fail the same way in every entrypoint, before
+        // anything else is checked
+        let mut ctx_bad = TestReceiveContext::empty();
+        ctx_bad.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        ctx_bad.set_owner(ADMIN);
+        ctx_bad.set_sender(Address::Account(ADMIN));
+        ctx_bad.set_parameter(&[1, 2, 3]);
+        assert_eq!(
+            claim_nft(
+                &ctx_bad,
+                &mut host,
+                Amount::zero(),
+                &mut logger,
+                &crypto_primitives(),
+            ),
+            Err(Error::ParseParams)
+        );
+        assert_eq!(
+            transfer(&ctx_bad, &mut host, &mut logger),
+            Err(Error::ParseParams)
+        );
+        assert_eq!(
+            set_nft_limit(&ctx_bad, &mut host, &mut logger),
+            Err(Error::ParseParams)
+        );
+        assert_eq!(
+            add_to_denylist(&ctx_bad, &mut host, &mut logger),
+            Err(Error::ParseParams)
+        );
+        assert_eq!(host.state().claimed_count(), 0);
+        assert!(logger.logs.is_empty());
+
+        // clients can tell parse failures apart from a sold out drop
+        assert_ne!(
+            Reject::from(Error::ParseParams).error_code,
+            Reject::from(Error::NFTLimitReached).error_code
+        );
+    }
+
+    #[concordium_test]
+    fn test_mint_no_reserve_no_whitelist() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        const ACCOUNT_0: AccountAddress = AccountAddress([1u8; 32]);
+        let account_0_string = "00000000000000000000000000000000000000000000000000".to_string();
+
+        // This should allow anyone to purchase 1 NFT
+        let params = InitParams {
+            nft_limit: 3,
+            ..default_init_params()
+        };
+
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state_result = init(&ctx, &mut state_builder, Amount::zero());
+        let new_state = state_result.unwrap();
+        assert_eq!(new_state.nft_limit, 3);
+
+        let mut ctx_claim = TestReceiveContext::empty();
+        ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut mint_params = ClaimNFTParams {
+            node: Address::Account(ACCOUNT_0),
+            node_string: account_0_string,
+            proof: vec![],
+            selected_token: index_token_id(0),
+            amount_of_tokens: 2,
+            tier: None,
+            round: None,
+            voucher: None,
+            max_claims: None,
+        };
+
+        let mut host = TestHost::new(new_state, state_builder);
+
+        let claim_parameter_bytes = to_bytes(&mint_params);
+        ctx_claim.set_parameter(&claim_parameter_bytes);
+        ctx_claim.set_sender(Address::Account(ACCOUNT_0));
+        let mut logger = TestLogger::init();
+
+        let claim_result = claim_nft(
+            &ctx_claim,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        );
+        assert!(claim_result.is_ok());
+        assert_eq!(
+            logger.logs.last(),
+            Some(&to_bytes(&AirdropEvent::Claim(ClaimEvent {
+                token_id: index_token_id(0),
+                claimer: Address::Account(ACCOUNT_0),
+                amount: 2,
+                slot_time: Timestamp::from_timestamp_millis(1),
+                claim_number: 1,
+            })))
+        );
+
+        // owners are tracked for sequential claims too
+        let mut owner_ctx = TestReceiveContext::empty();
+        let owner_parameter_bytes = to_bytes(&index_token_id(1));
+        owner_ctx.set_parameter(&owner_parameter_bytes);
+        assert_eq!(
+            check_owner(&owner_ctx, &host).unwrap(),
+            CheckOwnerReply {
+                address: Some("01".repeat(32))
+            }
+        );
+        let owner_parameter_bytes = to_bytes(&index_token_id(2));
+        owner_ctx.set_parameter(&owner_parameter_bytes);
+        assert_eq!(
+            check_owner(&owner_ctx, &host).unwrap(),
+            CheckOwnerReply { address: None }
+        );
+        let owners_parameter_bytes = to_bytes(&vec![index_token_id(2), index_token_id(1)]);
+        owner_ctx.set_parameter(&owners_parameter_bytes);
+        assert_eq!(
+            check_owners(&owner_ctx, &host),
+            Ok(vec![None, Some(ACCOUNT_0)])
+        );
+        let info_parameter_bytes = to_bytes(&index_token_id(1));
+        owner_ctx.set_parameter(&info_parameter_bytes);
+        assert_eq!(
+            claim_info(&owner_ctx, &host),
+            Ok(Some(ClaimInfoReply {
+                owner: Address::Account(ACCOUNT_0),
+                claimed_at: Timestamp::from_timestamp_millis(1),
+            }))
+        );
+        let info_parameter_bytes = to_bytes(&index_token_id(2));
+        owner_ctx.set_parameter(&info_parameter_bytes);
+        assert_eq!(claim_info(&owner_ctx, &host), Ok(None));
+        assert_eq!(
+            next_token_id(&owner_ctx, &host),
+            Ok(Some(index_token_id(2)))
+        );
+
+        let view_parameter_bytes = to_bytes(&ViewParams {
+            from: None,
+            limit: 1,
+        });
+        owner_ctx.set_parameter(&view_parameter_bytes);
+        let view_result = view(&owner_ctx, &host).unwrap();
+        assert_eq!(
+            view_result.claimed,
+            vec![(index_token_id(0), "01".repeat(32))]
+        );
+        assert_eq!(view_result.next, Some(index_token_id(1)));
+
+        let view_parameter_bytes = to_bytes(&ViewParams {
+            from: view_result.next,
+            limit: 5,
+        });
+        owner_ctx.set_parameter(&view_parameter_bytes);
+        let view_result = view(&owner_ctx, &host).unwrap();
+        assert_eq!(
+            view_result.claimed,
+            vec![(index_token_id(1), "01".repeat(32))]
+        );
+        assert_eq!(view_result.next, None);
+
+        let claim_result_bad: Result<(), Error> = claim_nft(
+            &ctx_claim,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        );
+        assert_eq!(claim_result_bad, Err(Error::NFTLimitReached));
+
+        // claiming the last token logs that the drop sold out
+        mint_params.amount_of_tokens = 1;
+        let claim_parameter_bytes = to_bytes(&mint_params);
+        ctx_claim.set_parameter(&claim_parameter_bytes);
+        claim_nft(
+            &ctx_claim,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        )
+        .unwrap();
+        assert_eq!(
+            logger.logs.last(),
+            Some(&to_bytes(&AirdropEvent::SoldOut(SoldOutEvent {
+                claim_count: 2,
+                slot_time: Timestamp::from_timestamp_millis(1),
+            })))
+        );
+
+        // both claims were made on the first day
+        let stats_parameter_bytes = to_bytes(&ClaimStatsParams {
+            from: Timestamp::from_timestamp_millis(5),
+            days: 2,
+        });
+        owner_ctx.set_parameter(&stats_parameter_bytes);
+        assert_eq!(
+            claim_stats(&owner_ctx, &host),
+            Ok(vec![
+                (
+                    Timestamp::from_timestamp_millis(0),
+                    DailyClaims {
+                        claims: 2,
+                        tokens: 3,
+                    }
+                ),
+                (
+                    Timestamp::from_timestamp_millis(MILLIS_PER_DAY),
+                    DailyClaims::default()
+                ),
+            ])
+        );
+    }
+
+    #[concordium_test]
+    fn test_whitelist() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        let account_0_string = "00000000000000000000000000000000000000000000000000".to_string();
+        let account_1_string = "11111111111111111111111111111111111111111111111111".to_string();
+        let account_2_string = "22222222222222222222222222222222222222222222222222".to_string();
+
+        let whitelist: Vec<String> =
+            vec![account_0_string.clone(), account_1_string, account_2_string];
+
+        // This should allow anyone to purchase 1 NFT
+        let params = InitParams {
+            nft_limit: 1,
+            whitelist: whitelist.clone(),
+            ..default_init_params()
+        };
+
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        // convert the addresses to strings
+        let mut hashes: Vec<String> = vec![];
+        for address in whitelist {
+            hashes.push(leaf(&address));
+        }
+
+        let bad_address: String = "This address should not work".to_string();
+        assert!(state.check_hash_value(hashes[0].clone()));
+        assert!(state.check_hash_value(hashes[1].clone()));
+        assert!(state.check_hash_value(hashes[2].clone()));
+        assert!(!state.check_hash_value(bad_address));
+
+        let a = parent(&hashes[0], &hashes[1]);
+        let b = parent(&hashes[2], &hashes[2]); // MT will duplicated 4th element from 3rd
+        let c = parent(&a, &b);
+
+        let test_merkle_proof = vec![hashes[0].clone(), a, c];
+
+        let test_address = leaf(&account_0_string);
+        let merkle_proof = state.get_hash_proof(test_address).unwrap();
+        assert_eq!(merkle_proof, test_merkle_proof);
+    }
+
+    #[concordium_test]
+    fn test_merkle_proof() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        let account_0_string = "00000000000000000000000000000000000000000000000000".to_string();
+        let account_1_string = "11111111111111111111111111111111111111111111111111".to_string();
+        let account_2_string = "22222222222222222222222222222222222222222222222222".to_string();
+        let account_3_string = "33333333333333333333333333333333333333333333333333".to_string();
+
+        let whitelist: Vec<String> = vec![
+            account_0_string.clone(),
+            account_1_string.clone(),
+            account_2_string,
+        ];
+
+        // This should allow anyone to purchase 1 NFT
+        let params = InitParams {
+            nft_limit: 1,
+            whitelist: whitelist.clone(),
+            ..default_init_params()
+        };
+
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+
+        // convert the addresses to strings
+        let mut hashes: Vec<String> = vec![];
+        for address in whitelist {
+            hashes.push(leaf(&address));
+        }
+
+        assert!(state.check_hash_value(hashes[0].clone()));
+        assert!(state.check_hash_value(hashes[1].clone()));
+        assert!(state.check_hash_value(hashes[2].clone()));
+        assert!(!state.check_hash_value(account_3_string));
+
+        let a = parent(&hashes[0], &hashes[1]);
+        let b = parent(&hashes[2], &hashes[2]); // MT will duplicated 4th element from 3rd
+        let c = parent(&a, &b);
+
+        let test_merkle_proof = vec![hashes[0].clone(), a, c];
+
+        let test_address = leaf(&account_0_string);
+        let merkle_proof = state.get_hash_proof(test_address).unwrap();
+        assert_eq!(merkle_proof, test_merkle_proof);
+
+        const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
+        const ACCOUNT_1: AccountAddress = AccountAddress([1u8; 32]);
+
+        let proof_params = ClaimNFTParams {
+            proof: test_merkle_proof.clone(),
+            node: Address::Account(ACCOUNT_0),
+            node_string: account_0_string,
+            selected_token: index_token_id(0),
+            amount_of_tokens: 1,
+            tier: None,
+            round: None,
+            voucher: None,
+            max_claims: None,
+        };
+        assert!(state.check_proof(&proof_params, &crypto_primitives()));
+
+        let proof_params = ClaimNFTParams {
+            proof: test_merkle_proof.clone(),
+            node: Address::Account(ACCOUNT_1),
+            node_string: account_1_string,
+            selected_token: index_token_id(0),
+            amount_of_tokens: 1,
+            tier: None,
+            round: None,
+            voucher: None,
+            max_claims: None,
+        };
+        assert!(!state.check_proof(&proof_params, &crypto_primitives()));
+    }
+
+    #[concordium_test]
+    fn test_claim_with_whitelist_full_reserve() {
+        let mut ctx = TestInitContext::empty();
+        let mut state_builder = TestStateBuilder::new();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+
+        let account_0_string = "00000000000000000000000000000000000000000000000000".to_string();
+        let account_1_string = "10101010101010101010101010101010101010101010101010".to_string();
+        const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
+        const ACCOUNT_1: AccountAddress = AccountAddress([1u8; 32]);
+
+        let whitelist: Vec<String> = vec![account_0_string.clone(), account_1_string.clone()];
+
+        // This should allow anyone to purchase 1 NFT
+        let params = InitParams {
+            nft_limit: 4,
+            whitelist: whitelist.clone(),
+            reserve: 4,
+            ..default_init_params()
+        };
+
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+
+        let mut test_proof: Vec<String> = vec![];
+        let acc1 = leaf(&account_0_string);
+        let acc2 = leaf(&account_1_string);
+
+        test_proof.push(acc1.clone());
+        test_proof.push(parent(&acc1, &acc2));
+
+        let mut ctx_claim = TestReceiveContext::empty();
+        ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+
+        let mint_params = ClaimNFTParams {
+            node: Address::Account(ACCOUNT_0),
+            node_string: account_0_string,
+            proof: test_proof.clone(),
+            selected_token: index_token_id(0),
+            amount_of_tokens: 1,
+            tier: None,
+            round: None,
+            voucher: None,
+            max_claims: None,
+        };
+
+        let mut host = TestHost::new(state, state_builder);
+
+        let claim_parameter_bytes = to_bytes(&mint_params);
+        ctx_claim.set_parameter(&claim_parameter_bytes);
+        ctx_claim.set_sender(Address::Account(ACCOUNT_0));
+        let mut logger = TestLogger::init();
+
+        claim_nft(
+            &ctx_claim,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        )
+        .unwrap();
+
+        let mut ctx_bad_claim = TestReceiveContext::empty();
+        ctx_bad_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mint_bad_params = ClaimNFTParams {
+            node: Address::Account(ACCOUNT_1),
+            node_string: account_1_string,
+            proof: test_proof.clone(),
+            selected_token: index_token_id(0),
+            amount_of_tokens: 1,
+            tier: None,
+            round: None,
+            voucher: None,
+            max_claims: None,
+        };
+        let bad_claim_parameter_bytes = to_bytes(&mint_bad_params);
+        ctx_bad_claim.set_parameter(&bad_claim_parameter_bytes);
+        ctx_bad_claim.set_sender(Address::Account(ACCOUNT_1));
+
+        let claim_result_bad = claim_nft(
+            &ctx_bad_claim,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        );
+        claim_eq!(
+            claim_result_bad,
+            Err(Error::AddressNotOnWhitelist),
+            "Function should fail with NFT error"
+        );
+    }
+
+    #[concordium_test]
+    fn test_claim_with_whitelist_no_reserve() {
+        let mut ctx = TestInitContext::empty();
+        let mut state_builder = TestStateBuilder::new();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+
+        let account_0_string = "00000000000000000000000000000000000000000000000000".to_string();
+        let account_1_string = "11111111111111111111111111111111111111111111111111".to_string();
+        const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
+        const ACCOUNT_1: AccountAddress = AccountAddress([1u8; 32]);
+
+        let whitelist: Vec<String> = vec![account_0_string.clone(), account_1_string.clone()];
+
+        // This should allow anyone to purchase 1 NFT
+        let params = InitParams {
+            nft_limit: 10,
+            whitelist: whitelist.clone(),
+            nft_limit_per_address: 1,
+            ..default_init_params()
+        };
+
+        let mut test_proof: Vec<String> = vec![];
+        let acc1 = leaf(&account_0_string);
+        let acc2 = leaf(&account_1_string);
+
+        test_proof.push(acc1.clone());
+        test_proof.push(parent(&acc1, &acc2));
+
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+
+        let mut ctx_claim = TestReceiveContext::empty();
+        let mint_params = ClaimNFTParams {
+            node: Address::Account(ACCOUNT_0),
+            node_string: account_0_string,
+            proof: test_proof.clone(),
+            selected_token: index_token_id(0),
+            amount_of_tokens: 1,
+            tier: None,
+            round: None,
+            voucher: None,
+            max_claims: None,
+        };
+
+        let mut host = TestHost::new(state, state_builder);
+
+        let claim_parameter_bytes = to_bytes(&mint_params);
+        ctx_claim.set_parameter(&claim_parameter_bytes);
+        ctx_claim.set_sender(Address::Account(ACCOUNT_0));
+        ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut logger = TestLogger::init();
+
+        claim_nft(
+            &ctx_claim,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        )
+        .unwrap();
+
+        let mut ctx_bad_claim = TestReceiveContext::empty();
+        ctx_bad_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mint_bad_params = ClaimNFTParams {
+            node: Address::Account(ACCOUNT_1),
+            node_string: account_1_string,
+            proof: test_proof.clone(),
+            selected_token: index_token_id(0),
+            amount_of_tokens: 1,
+            tier: None,
+            round: None,
+            voucher: None,
+            max_claims: None,
+        };
+        let bad_claim_parameter_bytes = to_bytes(&mint_bad_params);
+        ctx_bad_claim.set_parameter(&bad_claim_parameter_bytes);
+        ctx_bad_claim.set_sender(Address::Account(ACCOUNT_1));
+
+        let claim_result_bad = claim_nft(
+            &ctx_bad_claim,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        );
+        claim_eq!(
+            claim_result_bad,
+            Err(Error::AddressNotOnWhitelist),
+            "Function should fail with NFT error"
+        );
+
+        // the rejected proof can be checked without sending a claim
+        assert_eq!(
+            verify_proof(&ctx_bad_claim, &host, &crypto_primitives()),
+            Ok(VerifyProofReply {
+                valid: false,
+                reason: Some(Error::AddressNotOnWhitelist),
+            })
+        );
+        assert_eq!(
+            verify_proof(&ctx_claim, &host, &crypto_primitives()),
+            Ok(VerifyProofReply {
+                valid: true,
+                reason: None,
+            })
+        );
+
+        // proofs longer than the tree is deep, or holding anything but
+        // lowercase hex hashes, are rejected before they are hashed
+        for proof in [
+            vec![acc1.clone(), parent(&acc1, &acc2), acc2.clone()],
+            vec![acc1.to_uppercase(), parent(&acc1, &acc2)],
+            vec![acc1.clone(), "zz".to_string()],
+        ] {
+            let params = ClaimNFTParams {
+                node: Address::Account(ACCOUNT_0),
+                node_string: mint_params.node_string.clone(),
+                proof,
+                selected_token: index_token_id(0),
+                amount_of_tokens: 1,
+                tier: None,
+                round: None,
+                voucher: None,
+                max_claims: None,
+            };
+            assert_eq!(
+                host.state().verify_proof(&params, &crypto_primitives()),
+                Err(Error::InvalidProof)
+            );
+        }
+    }
+
+    #[concordium_test]
+    fn test_proof_validation() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        let account_0_string = "00000000000000000000000000000000000000000000000000".to_string();
+        let account_1_string = "11111111111111111111111111111111111111111111111111".to_string();
+        const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
+
+        let params = InitParams {
+            nft_limit: 10,
+            whitelist: vec![account_0_string.clone(), account_1_string.clone()],
+            ..default_init_params()
+        };
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+        let mut logger = TestLogger::init();
+
+        let acc1 = leaf(&account_0_string);
+        let acc2 = leaf(&account_1_string);
+        let mut mint_params = ClaimNFTParams {
+            node: Address::Account(ACCOUNT_0),
+            node_string: account_0_string,
+            proof: vec![],
+            selected_token: index_token_id(0),
+            amount_of_tokens: 1,
+            tier: None,
+            round: None,
+            voucher: None,
+            max_claims: None,
+        };
+
+        // oversized and malformed proofs fail the claim before anything is
+        // hashed, so the crypto primitives are never called
+        let root = parent(&acc1, &acc2);
+        for proof in [
+            vec![acc1.clone(); 64],
+            vec![acc1.clone(), root.clone(), acc2.clone()],
+            vec![acc1.clone(), format!("{}0", root)],
+            vec![acc1.clone(), root[..62].to_string()],
+            vec![acc1.clone(), root.to_uppercase()],
+        ] {
+            mint_params.proof = proof;
+            let claim_parameter_bytes = to_bytes(&mint_params);
+            let mut ctx_claim = TestReceiveContext::empty();
+            ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+            ctx_claim.set_sender(Address::Account(ACCOUNT_0));
+            ctx_claim.set_parameter(&claim_parameter_bytes);
+            assert_eq!(
+                claim_nft(
+                    &ctx_claim,
+                    &mut host,
+                    Amount::zero(),
+                    &mut logger,
+                    &TestCryptoPrimitives::new(),
+                ),
+                Err(Error::InvalidProof)
+            );
+        }
+        assert_eq!(host.state().claimed_count(), 0);
+
+        mint_params.proof = vec![acc1, root];
+        let claim_parameter_bytes = to_bytes(&mint_params);
+        let mut ctx_claim = TestReceiveContext::empty();
+        ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        ctx_claim.set_sender(Address::Account(ACCOUNT_0));
+        ctx_claim.set_parameter(&claim_parameter_bytes);
+        claim_nft(
+            &ctx_claim,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        )
+        .unwrap();
+        assert_eq!(host.state().claimed_count(), 1);
+    }
+
+    #[concordium_test]
+    fn test_claim_with_whitelist_partial_reserve() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        let account_0_string = "00000000000000000000000000000000000000000000000000".to_string();
+        let account_1_string = "11111111111111111111111111111111111111111111111111".to_string();
+
+        const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
+        const ACCOUNT_1: AccountAddress = AccountAddress([1u8; 32]);
+
+        let whitelist: Vec<String> = vec![account_0_string.clone()];
+
+        // This should allow anyone to purchase 1 NFT
+        let params = InitParams {
+            nft_limit: 3,
+            whitelist: whitelist.clone(),
+            reserve: 2,
+            ..default_init_params()
+        };
+
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+
+        let mut ctx_claim = TestReceiveContext::empty();
+        ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+
+        let mint_params = ClaimNFTParams {
+            node: Address::Account(ACCOUNT_1),
+            node_string: account_1_string,
+            proof: vec![],
+            selected_token: index_token_id(0),
+            amount_of_tokens: 1,
+            tier: None,
+            round: None,
+            voucher: None,
+            max_claims: None,
+        };
+
+        let mut host = TestHost::new(state, state_builder);
+
+        let claim_parameter_bytes = to_bytes(&mint_params);
+        ctx_claim.set_parameter(&claim_parameter_bytes);
+        ctx_claim.set_sender(Address::Account(ACCOUNT_1));
+
+        let mut logger = TestLogger::init();
+        // this should not check the whitelist
+        claim_nft(
+            &ctx_claim,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        )
+        .unwrap();
+
+        let mut ctx_wl_claim = TestReceiveContext::empty();
+        ctx_wl_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        assert_eq!(claimed_count(&ctx_wl_claim, &host), Ok(1));
+        assert_eq!(
+            remaining_supply(&ctx_wl_claim, &host),
+            Ok(RemainingSupplyReply {
+                remaining: 2,
+                reserved: 2,
+            })
+        );
+        let address_hashed = leaf(&account_0_string);
+
+        let test_proof = vec![
+            address_hashed.clone(),
+            parent(&address_hashed, &address_hashed),
+        ];
+
+        let mint_wl_params = ClaimNFTParams {
+            node: Address::Account(ACCOUNT_0),
+            node_string: account_0_string,
+            proof: test_proof.clone(),
+            selected_token: index_token_id(0),
+            amount_of_tokens: 1,
+            tier: None,
+            round: None,
+            voucher: None,
+            max_claims: None,
+        };
+
+        let wl_claim_parameter_bytes = to_bytes(&mint_wl_params);
+        ctx_wl_claim.set_parameter(&wl_claim_parameter_bytes);
+        ctx_wl_claim.set_sender(Address::Account(ACCOUNT_0));
+
+        let mut logger = TestLogger::init();
+        // this should check the whitelist and pass
+        claim_nft(
+            &ctx_wl_claim,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        )
+        .unwrap();
+        assert_eq!(
+            remaining_supply(&ctx_wl_claim, &host),
+            Ok(RemainingSupplyReply {
+                remaining: 1,
+                reserved: 1,
+            })
+        );
+
+        // this should not check the whitelist and fail
+        let fail_claim = claim_nft(
+            &ctx_claim,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        );
+
+        claim_eq!(
+            fail_claim,
+            Err(Error::AddressNotOnWhitelist),
+            "Function should fail with whitelist error"
+        );
+    }
+
+    #[concordium_test]
+    fn test_mint_too_late() {
+        let mut ctx = TestInitContext::empty();
+        let mut state_builder = TestStateBuilder::new();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
+        let account_0_string = "00000000000000000000000000000000000000000000000000".to_string();
+
+        // This should allow anyone to purchase 1 NFT
+        let params = InitParams {
+            nft_limit: 1,
+            nft_time_limit: 10,
+            ..default_init_params()
+        };
+
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state_result = init(&ctx, &mut state_builder, Amount::zero());
+        let new_state = state_result.unwrap();
+        assert_eq!(new_state.nft_limit, 1);
+
+        let mut ctx_claim = TestReceiveContext::empty();
+        ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mint_params = ClaimNFTParams {
+            node: Address::Account(ACCOUNT_0),
+            node_string: account_0_string,
+            proof: vec![],
+            selected_token: index_token_id(0),
+            amount_of_tokens: 1,
+            tier: None,
+            round: None,
+            voucher: None,
+            max_claims: None,
+        };
+
+        let mut host = TestHost::new(new_state, state_builder);
+
+        let claim_parameter_bytes = to_bytes(&mint_params);
+        ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(11));
+        ctx_claim.set_parameter(&claim_parameter_bytes);
+        ctx_claim.set_sender(Address::Account(ACCOUNT_0));
+
+        let mut logger = TestLogger::init();
+        let claim_result = claim_nft(
+            &ctx_claim,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        );
+        claim_eq!(
+            claim_result,
+            Err(Error::AirdropNowClosed),
+            "Function should fail with Airdrop closed error"
+        );
+    }
+
+    #[concordium_test]
+    fn test_mint_no_reserve_no_whitelist_selected_index() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        let account_0_string = "00000000000000000000000000000000000000000000000000".to_string();
+        const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
+
+        // This should allow anyone to purchase 2 NFTs
+        let params = InitParams {
+            nft_limit: 2,
+            base_url: "https://some.example/token/".to_string(),
+            selected_index: true,
+            ..default_init_params()
+        };
+
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state_result = init(&ctx, &mut state_builder, Amount::zero());
+        let new_state = state_result.unwrap();
+        assert_eq!(new_state.nft_limit, 2);
+
+        let mut ctx_claim = TestReceiveContext::empty();
+        ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut mint_params = ClaimNFTParams {
+            node: Address::Account(ACCOUNT_0),
+            node_string: account_0_string.clone(),
+            proof: vec![],
+            selected_token: index_token_id(1),
+            amount_of_tokens: 1,
+            tier: None,
+            round: None,
+            voucher: None,
+            max_claims: None,
+        };
+
+        let mut host = TestHost::new(new_state, state_builder);
+        // claimers pick their own token, so there is no next token to show
+        assert_eq!(next_token_id(&ctx_claim, &host), Ok(None));
+
+        ctx_claim.set_sender(Address::Account(ACCOUNT_0));
+        let mut logger = TestLogger::init();
+
+        // token IDs run from 0 to nft_limit - 1
+        mint_params.selected_token = index_token_id(2);
+        let bad_parameter_bytes = to_bytes(&mint_params);
+        ctx_claim.set_parameter(&bad_parameter_bytes);
+        assert_eq!(
+            claim_nft(
+                &ctx_claim,
+                &mut host,
+                Amount::zero(),
+                &mut logger,
+                &crypto_primitives(),
+            ),
+            Err(Error::InvalidTokenIndex)
+        );
+
+        mint_params.selected_token = index_token_id(1);
+        let claim_parameter_bytes = to_bytes(&mint_params);
+        ctx_claim.set_parameter(&claim_parameter_bytes);
+
+        let claim_result = claim_nft(
+            &ctx_claim,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        );
+        assert!(claim_result.is_ok());
+
+        claim!(
+            logger.logs.contains(&to_bytes(&Cis2Event::Mint(MintEvent {
+                owner: concordium_std::Address::Account(ACCOUNT_0),
+                token_id: ContractTokenId::from(1),
+                amount: ContractTokenAmount::from(1),
+            }))),
+            "Expected an event for minting token 1"
+        );
+
+        claim!(
+            logger.logs.contains(&to_bytes(
+                &Cis2Event::TokenMetadata::<_, ContractTokenAmount>(TokenMetadataEvent {
+                    token_id: index_token_id(1),
+                    metadata_url: MetadataUrl {
+                        url: "https://some.example/token/1".to_string(),
+                        hash: None,
+                    },
+                })
+            )),
+            "Expected an event for token metadata for token 1"
+        );
+
+        // check that the token has the correct owner:
+        let mut owner_ctx = TestReceiveContext::empty();
+        let owner_params = index_token_id(1);
+        let owner_parameter_bytes = to_bytes(&owner_params);
+        owner_ctx.set_parameter(&owner_parameter_bytes);
+        let owner_result: Result<CheckOwnerReply, Reject> = check_owner(&owner_ctx, &host);
+        assert!(owner_result.is_ok());
+        let result_details = owner_result.unwrap();
+        assert!(result_details.address.is_some());
+        assert_eq!(result_details.address.unwrap(), account_0_string);
+
+        // check that the wrong token has the no owner:
+        let mut non_owner_ctx = TestReceiveContext::empty();
+        let non_owner_params = index_token_id(5);
+        let non_owner_parameter_bytes = to_bytes(&non_owner_params);
+        non_owner_ctx.set_parameter(&non_owner_parameter_bytes);
+        let non_owner_result: Result<CheckOwnerReply, Reject> = check_owner(&non_owner_ctx, &host);
+        assert!(non_owner_result.is_ok());
+        let non_result_details = non_owner_result.unwrap();
+        assert!(non_result_details.address.is_none());
+
+        // Check the right amount of tokens exist and have been claimed
+        assert_eq!(total_supply(&non_owner_ctx, &host).unwrap(), 2);
+        assert_eq!(current_supply(&non_owner_ctx, &host).unwrap(), 1);
+
+        let mut view_ctx = TestReceiveContext::empty();
+        let view_params = ViewParams {
+            from: None,
+            limit: 10,
+        };
+        let view_parameter_bytes = to_bytes(&view_params);
+        view_ctx.set_parameter(&view_parameter_bytes);
+        assert_eq!(
+            view(&view_ctx, &host).unwrap(),
+            ViewResult {
+                metadata: String::new(),
+                whitelist: String::new(),
+                number_of_nfts: 2,
+                claimed: vec![(index_token_id(1), account_0_string.clone())],
+                next: None,
+            }
+        );
+
+        // the limit bounds the token IDs looked up, not the claimed ones returned
+        let view_params = ViewParams {
+            from: None,
+            limit: 1,
+        };
+        let view_parameter_bytes = to_bytes(&view_params);
+        view_ctx.set_parameter(&view_parameter_bytes);
+        let view_result = view(&view_ctx, &host).unwrap();
+        assert_eq!(view_result.claimed, vec![]);
+        assert_eq!(view_result.next, Some(index_token_id(1)));
+
+        // the page is empty past the end of the collection
+        let view_params = ViewParams {
+            from: Some(index_token_id(2)),
+            limit: 10,
+        };
+        let view_parameter_bytes = to_bytes(&view_params);
+        view_ctx.set_parameter(&view_parameter_bytes);
+        let view_result = view(&view_ctx, &host).unwrap();
+        assert_eq!(view_result.claimed, vec![]);
+        assert_eq!(view_result.next, None);
+
+        let claim_result_bad: Result<(), Error> = claim_nft(
+            &ctx_claim,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        );
+        assert_eq!(claim_result_bad, Err(Error::IndexAlreadyClaimed));
+    }
+
+    #[concordium_test]
+    fn test_selected_token_range() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
+
+        let params = InitParams {
+            nft_limit: 2,
+            selected_index: true,
+            ..default_init_params()
+        };
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+        let mut logger = TestLogger::init();
+
+        let mut claim = |selected_token: ContractTokenId| {
+            let mint_params = ClaimNFTParams {
+                node: Address::Account(ACCOUNT_0),
+                node_string: address_to_string(Address::Account(ACCOUNT_0)),
+                proof: vec![],
+                selected_token,
+                amount_of_tokens: 1,
+                tier: None,
+                round: None,
+                voucher: None,
+                max_claims: None,
+            };
+            let claim_parameter_bytes = to_bytes(&mint_params);
+            let mut ctx_claim = TestReceiveContext::empty();
+            ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+            ctx_claim.set_parameter(&claim_parameter_bytes);
+            ctx_claim.set_sender(Address::Account(ACCOUNT_0));
+            claim_nft(
+                &ctx_claim,
+                &mut host,
+                Amount::zero(),
+                &mut logger,
+                &crypto_primitives(),
+            )
+        };
+
+        // token IDs at or above nft_limit are not part of the drop
+        assert_eq!(claim(index_token_id(2)), Err(Error::InvalidTokenIndex));
+        assert_eq!(
+            claim(index_token_id(4_000_000_000)),
+            Err(Error::InvalidTokenIndex)
+        );
+        assert!(claim(index_token_id(1)).is_ok());
+        assert_eq!(claim(index_token_id(1)), Err(Error::IndexAlreadyClaimed));
+
+        assert_eq!(host.state().claimed_count(), 1);
+        assert!(host.state().token_owners.get(&index_token_id(2)).is_none());
+    }
+
+    #[concordium_test]
+    fn test_mint_no_reserve_no_whitelist_address_limited() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        const ACCOUNT_0: AccountAddress = AccountAddress([1u8; 32]);
+        let account_0_string = "00000000000000000000000000000000000000000000000000".to_string();
+
+        let params = InitParams {
+            nft_limit: 3,
+            nft_limit_per_address: 1,
+            ..default_init_params()
+        };
+
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state_result = init(&ctx, &mut state_builder, Amount::zero());
+        let new_state = state_result.unwrap();
+        assert_eq!(new_state.nft_limit, 3);
+
+        let mut ctx_claim = TestReceiveContext::empty();
+        ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mint_params = ClaimNFTParams {
+            node: Address::Account(ACCOUNT_0),
+            node_string: account_0_string,
+            proof: vec![],
+            selected_token: index_token_id(0),
+            amount_of_tokens: 2,
+            tier: None,
+            round: None,
+            voucher: None,
+            max_claims: None,
+        };
+
+        let mut host = TestHost::new(new_state, state_builder);
+        let claim_parameter_bytes = to_bytes(&mint_params);
+        ctx_claim.set_parameter(&claim_parameter_bytes);
+        ctx_claim.set_sender(Address::Account(ACCOUNT_0));
+        let mut logger = TestLogger::init();
+
+        let claim_result = claim_nft(
+            &ctx_claim,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        );
+        assert_eq!(claim_result, Err(Error::WalletLimitReached));
+    }
+
+    #[concordium_test]
+    fn test_wallet_limit() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
+        const ACCOUNT_1: AccountAddress = AccountAddress([1u8; 32]);
+
+        let params = InitParams {
+            nft_limit: 10,
+            nft_limit_per_address: 2,
+            ..default_init_params()
+        };
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+        let mut logger = TestLogger::init();
+
+        let mut claim = |account: AccountAddress, amount_of_tokens: u32| {
+            let mint_params = ClaimNFTParams {
+                node: Address::Account(account),
+                node_string: address_to_string(Address::Account(account)),
+                proof: vec![],
+                selected_token: index_token_id(0),
+                amount_of_tokens,
+                tier: None,
+                round: None,
+                voucher: None,
+                max_claims: None,
+            };
+            let claim_parameter_bytes = to_bytes(&mint_params);
+            let mut ctx_claim = TestReceiveContext::empty();
+            ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+            ctx_claim.set_parameter(&claim_parameter_bytes);
+            ctx_claim.set_sender(Address::Account(account));
+            claim_nft(
+                &ctx_claim,
+                &mut host,
+                Amount::zero(),
+                &mut logger,
+                &crypto_primitives(),
+            )
+        };
+
+        // the limit counts the tokens of every claim an account has made
+        assert!(claim(ACCOUNT_0, 1).is_ok());
+        assert_eq!(claim(ACCOUNT_0, 2), Err(Error::WalletLimitReached));
+        assert!(claim(ACCOUNT_0, 1).is_ok());
+        assert_eq!(claim(ACCOUNT_0, 1), Err(Error::WalletLimitReached));
+
+        // other accounts keep their own allowance
+        assert!(claim(ACCOUNT_1, 2).is_ok());
+        assert_eq!(claim(ACCOUNT_1, 1), Err(Error::WalletLimitReached));
+
+        assert_eq!(
+            *host
+                .state()
+                .claimed_nfts
+                .get(&Address::Account(ACCOUNT_0))
+                .unwrap(),
+            2
+        );
+        assert_eq!(host.state().claimed_count(), 4);
+    }
+
+    #[concordium_test]
+    fn test_pause() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        const ADMIN: AccountAddress = AccountAddress([9u8; 32]);
+        const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
+        let account_0_string = "00000000000000000000000000000000000000000000000000".to_string();
+
+        let params = InitParams {
+            nft_limit: 3,
+            ..default_init_params()
+        };
+
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+
+        // only the owner can pause
+        let mut ctx_pause = TestReceiveContext::empty();
+        ctx_pause.set_owner(ADMIN);
+        ctx_pause.set_sender(Address::Account(ACCOUNT_0));
+        assert_eq!(pause(&ctx_pause, &mut host), Err(Error::Unauthorized));
+
+        ctx_pause.set_sender(Address::Account(ADMIN));
+        pause(&ctx_pause, &mut host).unwrap();
+
+        let mut ctx_claim = TestReceiveContext::empty();
+        ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mint_params = ClaimNFTParams {
+            node: Address::Account(ACCOUNT_0),
+            node_string: account_0_string,
+            proof: vec![],
+            selected_token: index_token_id(0),
+            amount_of_tokens: 1,
+            tier: None,
+            round: None,
+            voucher: None,
+            max_claims: None,
+        };
+        let claim_parameter_bytes = to_bytes(&mint_params);
+        ctx_claim.set_parameter(&claim_parameter_bytes);
+        ctx_claim.set_sender(Address::Account(ACCOUNT_0));
+        let mut logger = TestLogger::init();
+
+        let claim_result = claim_nft(
+            &ctx_claim,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        );
+        assert_eq!(claim_result, Err(Error::ContractPaused));
+
+        unpause(&ctx_pause, &mut host).unwrap();
+        claim_nft(
+            &ctx_claim,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        )
+        .unwrap();
+
+        // tokens can't move while paused either
+        let transfer_params = TransferParams(vec![Transfer {
+            token_id: index_token_id(0),
+            amount: ContractTokenAmount::from(1),
+            from: Address::Account(ACCOUNT_0),
+            to: Receiver::Account(ADMIN),
+            data: AdditionalData::empty(),
+        }]);
+        let transfer_parameter_bytes = to_bytes(&transfer_params);
+        let mut ctx_transfer = TestReceiveContext::empty();
+        ctx_transfer.set_sender(Address::Account(ACCOUNT_0));
+        ctx_transfer.set_parameter(&transfer_parameter_bytes);
+        pause(&ctx_pause, &mut host).unwrap();
+        assert_eq!(
+            transfer(&ctx_transfer, &mut host, &mut logger),
+            Err(Error::ContractPaused)
+        );
+        unpause(&ctx_pause, &mut host).unwrap();
+        transfer(&ctx_transfer, &mut host, &mut logger).unwrap();
+        assert_eq!(
+            *host.state().token_owners.get(&index_token_id(0)).unwrap(),
+            Address::Account(ADMIN)
+        );
+
+        // denied addresses can not claim until they are removed from the denylist
+        let denylist_parameter_bytes = to_bytes(&DenylistParams {
+            addresses: vec![Address::Account(ACCOUNT_0)],
+        });
+        ctx_pause.set_parameter(&denylist_parameter_bytes);
+        add_to_denylist(&ctx_pause, &mut host, &mut logger).unwrap();
+        assert_eq!(
+            logger.logs.last(),
+            Some(&to_bytes(&AirdropEvent::DenylistUpdated(
+                DenylistUpdatedEvent {
+                    address: Address::Account(ACCOUNT_0),
+                    denied: true,
+                }
+            )))
+        );
+        let claim_result = claim_nft(
+            &ctx_claim,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        );
+        assert_eq!(claim_result, Err(Error::AddressDenied));
+
+        remove_from_denylist(&ctx_pause, &mut host, &mut logger).unwrap();
+        let claim_result = claim_nft(
+            &ctx_claim,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        );
+        assert!(claim_result.is_ok());
+    }
+
+    #[concordium_test]
+    fn test_denylist() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        const ADMIN: AccountAddress = AccountAddress([9u8; 32]);
+        const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
+        const ACCOUNT_1: AccountAddress = AccountAddress([1u8; 32]);
+
+        let params = InitParams {
+            nft_limit: 10,
+            ..default_init_params()
+        };
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+        let mut logger = TestLogger::init();
+
+        let mint_params = ClaimNFTParams {
+            node: Address::Account(ACCOUNT_0),
+            node_string: address_to_string(Address::Account(ACCOUNT_0)),
+            proof: vec![],
+            selected_token: index_token_id(0),
+            amount_of_tokens: 1,
+            tier: None,
+            round: None,
+            voucher: None,
+            max_claims: None,
+        };
+        let claim_parameter_bytes = to_bytes(&mint_params);
+        let mut ctx_claim = TestReceiveContext::empty();
+        ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        ctx_claim.set_parameter(&claim_parameter_bytes);
+        ctx_claim.set_sender(Address::Account(ACCOUNT_0));
+        claim_nft(
+            &ctx_claim,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        )
+        .unwrap();
+
+        // only the owner can change the denylist
+        let denylist_parameter_bytes = to_bytes(&DenylistParams {
+            addresses: vec![Address::Account(ACCOUNT_1)],
+        });
+        let mut ctx_denylist = TestReceiveContext::empty();
+        ctx_denylist.set_owner(ADMIN);
+        ctx_denylist.set_sender(Address::Account(ACCOUNT_0));
+        ctx_denylist.set_parameter(&denylist_parameter_bytes);
+        assert_eq!(
+            add_to_denylist(&ctx_denylist, &mut host, &mut logger),
+            Err(Error::Unauthorized)
+        );
+        ctx_denylist.set_sender(Address::Account(ADMIN));
+        add_to_denylist(&ctx_denylist, &mut host, &mut logger).unwrap();
+        assert!(host.state().denylist.contains(&Address::Account(ACCOUNT_1)));
+
+        // tokens can't be sent to a denied address
+        let transfer_params = TransferParams(vec![Transfer {
+            token_id: index_token_id(0),
+            amount: ContractTokenAmount::from(1),
+            from: Address::Account(ACCOUNT_0),
+            to: Receiver::Account(ACCOUNT_1),
+            data: AdditionalData::empty(),
+        }]);
+        let transfer_parameter_bytes = to_bytes(&transfer_params);
+        let mut ctx_transfer = TestReceiveContext::empty();
+        ctx_transfer.set_sender(Address::Account(ACCOUNT_0));
+        ctx_transfer.set_parameter(&transfer_parameter_bytes);
+        assert_eq!(
+            transfer(&ctx_transfer, &mut host, &mut logger),
+            Err(Error::AddressDenied)
+        );
+
+        // nor moved by a denied holder
+        let denylist_parameter_bytes = to_bytes(&DenylistParams {
+            addresses: vec![Address::Account(ACCOUNT_0), Address::Account(ACCOUNT_1)],
+        });
+        ctx_denylist.set_parameter(&denylist_parameter_bytes);
+        remove_from_denylist(&ctx_denylist, &mut host, &mut logger).unwrap();
+        let denylist_parameter_bytes = to_bytes(&DenylistParams {
+            addresses: vec![Address::Account(ACCOUNT_0)],
+        });
+        ctx_denylist.set_parameter(&denylist_parameter_bytes);
+        add_to_denylist(&ctx_denylist, &mut host, &mut logger).unwrap();
+        assert_eq!(
+            transfer(&ctx_transfer, &mut host, &mut logger),
+            Err(Error::AddressDenied)
+        );
+        assert_eq!(
+            claim_nft(
+                &ctx_claim,
+                &mut host,
+                Amount::zero(),
+                &mut logger,
+                &crypto_primitives(),
+            ),
+            Err(Error::AddressDenied)
+        );
+        assert_eq!(
+            *host.state().token_owners.get(&index_token_id(0)).unwrap(),
+            Address::Account(ACCOUNT_0)
+        );
+
+        remove_from_denylist(&ctx_denylist, &mut host, &mut logger).unwrap();
+        assert_eq!(
+            logger.logs.last(),
+            Some(&to_bytes(&AirdropEvent::DenylistUpdated(
+                DenylistUpdatedEvent {
+                    address: Address::Account(ACCOUNT_0),
+                    denied: false,
+                }
+            )))
+        );
+        transfer(&ctx_transfer, &mut host, &mut logger).unwrap();
+        assert_eq!(
+            *host.state().token_owners.get(&index_token_id(0)).unwrap(),
+            Address::Account(ACCOUNT_1)
+        );
+    }
+
+    #[concordium_test]
+    fn test_set_whitelist() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        const ADMIN: AccountAddress = AccountAddress([9u8; 32]);
+        let account_0_string = "00000000000000000000000000000000000000000000000000".to_string();
+        let account_1_string = "11111111111111111111111111111111111111111111111111".to_string();
+
+        let params = InitParams {
+            nft_limit: 3,
+            whitelist: vec![account_0_string.clone()],
+            ..default_init_params()
+        };
+
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        assert!(!state.check_hash_value(leaf(&account_1_string)));
+        let mut host = TestHost::new(state, state_builder);
+
+        let whitelist_params = SetWhitelistParams {
+            whitelist: vec![account_0_string.clone(), account_1_string.clone()],
+            whitelist_file: "ipfs://new-whitelist".to_string(),
+        };
+        let whitelist_parameter_bytes = to_bytes(&whitelist_params);
+        let mut ctx_update = TestReceiveContext::empty();
+        ctx_update.set_owner(ADMIN);
+        ctx_update.set_sender(Address::Account(ADMIN));
+        ctx_update.set_parameter(&whitelist_parameter_bytes);
+        let mut logger = TestLogger::init();
+
+        set_whitelist(&ctx_update, &mut host, &mut logger).unwrap();
+
+        assert!(host.state().check_hash_value(leaf(&account_0_string)));
+        assert!(host.state().check_hash_value(leaf(&account_1_string)));
+        assert_eq!(host.state().whitelist, "ipfs://new-whitelist".to_string());
+
+        let acc0 = leaf(&account_0_string);
+        let acc1 = leaf(&account_1_string);
+        assert_eq!(
+            merkle_root(&ctx_update, &host).unwrap(),
+            Some(MerkleRootReply {
+                root: parent(&acc0, &acc1),
+                length: 2,
+                depth: 1,
+            })
+        );
+        claim!(
+            logger
+                .logs
+                .contains(&to_bytes(&AirdropEvent::WhitelistUpdated(
+                    WhitelistUpdatedEvent {
+                        root: Some(parent(&acc0, &acc1)),
+                        whitelist_file: "ipfs://new-whitelist".to_string(),
+                    }
+                ))),
+            "Expected an event for the whitelist update"
+        );
+    }
+
+    #[concordium_test]
+    fn test_large_whitelist() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        // more leaves than fit in a u8
+        let whitelist: Vec<String> = (0..300).map(|i| format!("{:050}", i)).collect();
+
+        let params = InitParams {
+            nft_limit: 300,
+            whitelist: whitelist.clone(),
+            ..default_init_params()
+        };
+
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let host = TestHost::new(state, state_builder);
+
+        let reply = merkle_root(&TestReceiveContext::empty(), &host)
+            .unwrap()
+            .unwrap();
+        assert_eq!(reply.length, 300);
+        assert_eq!(reply.depth, 9);
+
+        let last = whitelist[299].clone();
+        let merkle_proof = host.state().get_hash_proof(leaf(&last)).unwrap();
+        assert_eq!(merkle_proof.len(), 10);
+        let proof_params = ClaimNFTParams {
+            proof: merkle_proof,
+            node: Address::Account(AccountAddress([1u8; 32])),
+            node_string: last,
+            selected_token: index_token_id(0),
+            amount_of_tokens: 1,
+            tier: None,
+            round: None,
+            voucher: None,
+            max_claims: None,
+        };
+        assert!(host
+            .state()
+            .check_proof(&proof_params, &crypto_primitives()));
+    }
+
+    #[concordium_test]
+    fn test_chunked_whitelist() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        const ADMIN: AccountAddress = AccountAddress([9u8; 32]);
+        const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
+        let account_0_string = "00000000000000000000000000000000000000000000000000".to_string();
+        let account_1_string = "11111111111111111111111111111111111111111111111111".to_string();
+        let account_2_string = "22222222222222222222222222222222222222222222222222".to_string();
+
+        let params = InitParams {
+            nft_limit: 3,
+            ..default_init_params()
+        };
+
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+
+        let first_chunk = WhitelistChunkParams {
+            whitelist: vec![account_0_string.clone(), account_1_string.clone()],
+        };
+        let first_chunk_bytes = to_bytes(&first_chunk);
+        let mut ctx_chunk = TestReceiveContext::empty();
+        ctx_chunk.set_owner(ADMIN);
+        ctx_chunk.set_sender(Address::Account(ADMIN));
+        ctx_chunk.set_parameter(&first_chunk_bytes);
+        add_whitelist_chunk(&ctx_chunk, &mut host).unwrap();
+
+        let second_chunk = WhitelistChunkParams {
+            whitelist: vec![account_2_string.clone()],
+        };
+        let second_chunk_bytes = to_bytes(&second_chunk);
+        ctx_chunk.set_parameter(&second_chunk_bytes);
+        add_whitelist_chunk(&ctx_chunk, &mut host).unwrap();
+
+        // claims are closed while the upload is in progress
+        let mut ctx_claim = TestReceiveContext::empty();
+        ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mint_params = ClaimNFTParams {
+            node: Address::Account(ACCOUNT_0),
+            node_string: account_0_string.clone(),
+            proof: vec![],
+            selected_token: index_token_id(0),
+            amount_of_tokens: 1,
+            tier: None,
+            round: None,
+            voucher: None,
+            max_claims: None,
+        };
+        let claim_parameter_bytes = to_bytes(&mint_params);
+        ctx_claim.set_parameter(&claim_parameter_bytes);
+        ctx_claim.set_sender(Address::Account(ACCOUNT_0));
+        let mut logger = TestLogger::init();
+        assert_eq!(
+            claim_nft(
+                &ctx_claim,
+                &mut host,
+                Amount::zero(),
+                &mut logger,
+                &crypto_primitives()
+            ),
+            Err(Error::WhitelistNotFinalized)
+        );
+
+        let finalize_params = FinalizeWhitelistParams {
+            whitelist_file: "ipfs://whitelist".to_string(),
+        };
+        let finalize_parameter_bytes = to_bytes(&finalize_params);
+        ctx_chunk.set_parameter(&finalize_parameter_bytes);
+        finalize_whitelist(&ctx_chunk, &mut host, &mut logger).unwrap();
+
+        assert!(host.state().pending_whitelist.is_empty());
+        assert!(host.state().check_hash_value(leaf(&account_0_string)));
+        assert!(host.state().check_hash_value(leaf(&account_1_string)));
+        assert!(host.state().check_hash_value(leaf(&account_2_string)));
+
+        // a list longer than 256 entries, uploaded in two chunks
+        let whitelist: Vec<String> = (0..300).map(|i| format!("{:050}", i)).collect();
+        let chunk_bytes: Vec<Vec<u8>> = whitelist
+            .chunks(150)
+            .map(|chunk| {
+                to_bytes(&WhitelistChunkParams {
+                    whitelist: chunk.to_vec(),
+                })
+            })
+            .collect();
+        for bytes in &chunk_bytes {
+            ctx_chunk.set_parameter(bytes);
+            add_whitelist_chunk(&ctx_chunk, &mut host).unwrap();
+        }
+        ctx_chunk.set_parameter(&finalize_parameter_bytes);
+        finalize_whitelist(&ctx_chunk, &mut host, &mut logger).unwrap();
+
+        assert!(!host.state().check_hash_value(leaf(&account_2_string)));
+        for entry in [
+            &whitelist[0],
+            &whitelist[255],
+            &whitelist[256],
+            &whitelist[299],
+        ] {
+            let proof = host.state().get_hash_proof(leaf(entry)).unwrap();
+            assert_eq!(proof.len(), 10);
+        }
+        assert_eq!(merkle_root(&ctx_chunk, &host).unwrap().unwrap().length, 300);
+    }
+
+    #[concordium_test]
+    fn test_paid_mint() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
+        let account_0_string = "00000000000000000000000000000000000000000000000000".to_string();
+
+        let params = InitParams {
+            nft_limit: 3,
+            mint_price: Amount::from_ccd(10),
+            ..default_init_params()
+        };
+
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+
+        let mut ctx_claim = TestReceiveContext::empty();
+        ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mint_params = ClaimNFTParams {
+            node: Address::Account(ACCOUNT_0),
+            node_string: account_0_string,
+            proof: vec![],
+            selected_token: index_token_id(0),
+            amount_of_tokens: 2,
+            tier: None,
+            round: None,
+            voucher: None,
+            max_claims: None,
+        };
+        let claim_parameter_bytes = to_bytes(&mint_params);
+        ctx_claim.set_parameter(&claim_parameter_bytes);
+        ctx_claim.set_sender(Address::Account(ACCOUNT_0));
+        let mut logger = TestLogger::init();
+
+        let claim_result = claim_nft(
+            &ctx_claim,
+            &mut host,
+            Amount::from_ccd(15),
+            &mut logger,
+            &crypto_primitives(),
+        );
+        assert_eq!(claim_result, Err(Error::InsufficientFunds));
+
+        // the 5 CCD paid above the price is sent back
+        host.set_self_balance(Amount::from_ccd(25));
+        let claim_result = claim_nft(
+            &ctx_claim,
+            &mut host,
+            Amount::from_ccd(25),
+            &mut logger,
+            &crypto_primitives(),
+        );
+        assert!(claim_result.is_ok());
+        assert_eq!(host.state().collected_funds, Amount::from_ccd(20));
+        assert!(host.transfer_occurred(&ACCOUNT_0, Amount::from_ccd(5)));
+    }
+
+    #[concordium_test]
+    fn test_withdraw() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        const ADMIN: AccountAddress = AccountAddress([9u8; 32]);
+        const BENEFICIARY: AccountAddress = AccountAddress([8u8; 32]);
+
+        let params = InitParams {
+            nft_limit: 3,
+            mint_price: Amount::from_ccd(10),
+            ..default_init_params()
+        };
+
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+        host.set_self_balance(Amount::from_ccd(20));
+
+        let withdraw_params = WithdrawParams {
+            amount: Amount::from_ccd(5),
+            to: BENEFICIARY,
+        };
+        let withdraw_parameter_bytes = to_bytes(&withdraw_params);
+        let mut ctx_withdraw = TestReceiveContext::empty();
+        ctx_withdraw.set_owner(ADMIN);
+        ctx_withdraw.set_sender(Address::Account(BENEFICIARY));
+        ctx_withdraw.set_parameter(&withdraw_parameter_bytes);
+        let mut logger = TestLogger::init();
+
+        assert_eq!(
+            withdraw(&ctx_withdraw, &mut host, &mut logger),
+            Err(Error::Unauthorized)
+        );
+
+        ctx_withdraw.set_sender(Address::Account(ADMIN));
+        withdraw(&ctx_withdraw, &mut host, &mut logger).unwrap();
+
+        assert!(host.transfer_occurred(&BENEFICIARY, Amount::from_ccd(5)));
+        claim!(
+            logger
+                .logs
+                .contains(&to_bytes(&AirdropEvent::Withdraw(WithdrawEvent {
+                    amount: Amount::from_ccd(5),
+                    to: BENEFICIARY,
+                }))),
+            "Expected an event for the withdrawal"
+        );
+    }
+
+    #[concordium_test]
+    fn test_airdrop_to() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        const ADMIN: AccountAddress = AccountAddress([9u8; 32]);
+        const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
+        const ACCOUNT_1: AccountAddress = AccountAddress([1u8; 32]);
+
+        let params = InitParams {
+            nft_limit: 3,
+            reserve: 1,
+            ..default_init_params()
+        };
+
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+
+        let airdrop_params = AirdropParams {
+            recipients: vec![
+                (ACCOUNT_0, index_token_id(0)),
+                (ACCOUNT_1, index_token_id(0)),
+            ],
+        };
+        let airdrop_parameter_bytes = to_bytes(&airdrop_params);
+        let mut ctx_airdrop = TestReceiveContext::empty();
+        ctx_airdrop.set_owner(ADMIN);
+        ctx_airdrop.set_sender(Address::Account(ADMIN));
+        ctx_airdrop.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        ctx_airdrop.set_parameter(&airdrop_parameter_bytes);
+        let mut logger = TestLogger::init();
+
+        airdrop_to(&ctx_airdrop, &mut host, &mut logger).unwrap();
+
+        claim!(
+            logger.logs.contains(&to_bytes(&Cis2Event::Mint(MintEvent {
+                owner: concordium_std::Address::Account(ACCOUNT_1),
+                token_id: ContractTokenId::from(1),
+                amount: ContractTokenAmount::from(1),
+            }))),
+            "Expected an event for minting token 1"
+        );
+        assert_eq!(host.state().next_token_id, 2);
+        assert_eq!(
+            *host
+                .state()
+                .claimed_nfts
+                .get(&Address::Account(ACCOUNT_0))
+                .unwrap(),
+            1
+        );
+
+        // the last token is held in reserve
+        assert_eq!(
+            airdrop_to(&ctx_airdrop, &mut host, &mut logger),
+            Err(Error::NFTLimitReached)
+        );
+    }
+
+    #[concordium_test]
+    fn test_mint_reserve() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        const ADMIN: AccountAddress = AccountAddress([9u8; 32]);
+
+        let params = InitParams {
+            nft_limit: 4,
+            reserve: 2,
+            selected_index: true,
+            ..default_init_params()
+        };
+
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+
+        let reserve_params = MintReserveParams {
+            to: Receiver::Account(ADMIN),
+            count: 3,
+            data: AdditionalData::empty(),
+        };
+        let reserve_parameter_bytes = to_bytes(&reserve_params);
+        let mut ctx_reserve = TestReceiveContext::empty();
+        ctx_reserve.set_owner(ADMIN);
+        ctx_reserve.set_sender(Address::Account(ADMIN));
+        ctx_reserve.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        ctx_reserve.set_self_address(ContractAddress {
+            index: 1,
+            subindex: 0,
+        });
+        ctx_reserve.set_parameter(&reserve_parameter_bytes);
+        let mut logger = TestLogger::init();
+
+        // only 2 tokens are in reserve
+        assert_eq!(
+            mint_reserve(&ctx_reserve, &mut host, &mut logger),
+            Err(Error::NFTLimitReached)
+        );
+
+        let reserve_params = MintReserveParams {
+            to: Receiver::Account(ADMIN),
+            count: 2,
+            data: AdditionalData::empty(),
+        };
+        let reserve_parameter_bytes = to_bytes(&reserve_params);
+        ctx_reserve.set_parameter(&reserve_parameter_bytes);
+        mint_reserve(&ctx_reserve, &mut host, &mut logger).unwrap();
+
+        claim!(
+            logger.logs.contains(&to_bytes(&Cis2Event::Mint(MintEvent {
+                owner: concordium_std::Address::Account(ADMIN),
+                token_id: ContractTokenId::from(1),
+                amount: ContractTokenAmount::from(1),
+            }))),
+            "Expected an event for minting token 1"
+        );
+        assert_eq!(host.state().nft_reserve, None);
+        assert_eq!(host.state().claimed_count(), 2);
+    }
+
+    #[concordium_test]
+    fn test_mint_reserve_for() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        const ADMIN: AccountAddress = AccountAddress([9u8; 32]);
+
+        let params = InitParams {
+            nft_limit: 4,
+            reserve: 2,
+            selected_index: true,
+            ..default_init_params()
+        };
+
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+        let mut logger = TestLogger::init();
+
+        const PARTNER: ContractAddress = ContractAddress {
+            index: 7,
+            subindex: 0,
+        };
+        const PLAYER: AccountAddress = AccountAddress([4u8; 32]);
+
+        let quota_parameter_bytes = to_bytes(&PartnerQuotaParams {
+            partner: PARTNER,
+            quota: 1,
+        });
+        let mut ctx_quota = TestReceiveContext::empty();
+        ctx_quota.set_owner(ADMIN);
+        ctx_quota.set_sender(Address::Account(PLAYER));
+        ctx_quota.set_parameter(&quota_parameter_bytes);
+        assert_eq!(
+            set_partner_quota(&ctx_quota, &mut host),
+            Err(Error::Unauthorized)
+        );
+        ctx_quota.set_sender(Address::Account(ADMIN));
+        set_partner_quota(&ctx_quota, &mut host).unwrap();
+
+        let mint_parameter_bytes = to_bytes(&MintReserveParams {
+            to: Receiver::Account(PLAYER),
+            count: 1,
+            data: AdditionalData::empty(),
+        });
+        let mut ctx_mint = TestReceiveContext::empty();
+        ctx_mint.set_owner(ADMIN);
+        ctx_mint.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        ctx_mint.set_self_address(ContractAddress {
+            index: 1,
+            subindex: 0,
+        });
+        ctx_mint.set_parameter(&mint_parameter_bytes);
+
+        // only the partner contract can mint, and only within its quota
+        ctx_mint.set_sender(Address::Account(PLAYER));
+        assert_eq!(
+            mint_reserve_for(&ctx_mint, &mut host, &mut logger),
+            Err(Error::Unauthorized)
+        );
+        ctx_mint.set_sender(Address::Contract(PARTNER));
+        mint_reserve_for(&ctx_mint, &mut host, &mut logger).unwrap();
+        assert_eq!(host.state().claimed_count(), 1);
+        assert_eq!(host.state().nft_reserve, Some(1));
+
+        let mut ctx_view = TestReceiveContext::empty();
+        let view_parameter_bytes = to_bytes(&PARTNER);
+        ctx_view.set_parameter(&view_parameter_bytes);
+        assert_eq!(partner_quota(&ctx_view, &host), Ok(0));
+        assert_eq!(
+            mint_reserve_for(&ctx_mint, &mut host, &mut logger),
+            Err(Error::Unauthorized)
+        );
+
+        let quota_parameter_bytes = to_bytes(&PartnerQuotaParams {
+            partner: PARTNER,
+            quota: 1,
+        });
+        ctx_quota.set_parameter(&quota_parameter_bytes);
+        set_partner_quota(&ctx_quota, &mut host).unwrap();
+        let mint_parameter_bytes = to_bytes(&MintReserveParams {
+            to: Receiver::Account(PLAYER),
+            count: 2,
+            data: AdditionalData::empty(),
+        });
+        ctx_mint.set_parameter(&mint_parameter_bytes);
+        assert_eq!(
+            mint_reserve_for(&ctx_mint, &mut host, &mut logger),
+            Err(Error::PartnerQuotaExceeded)
+        );
+        assert_eq!(partner_quota(&ctx_view, &host), Ok(1));
+    }
+
+    #[concordium_test]
+    fn test_claim_bridged() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        const PLAYER: AccountAddress = AccountAddress([4u8; 32]);
+
+        let params = InitParams {
+            nft_limit: 4,
+            reserve: 2,
+            selected_index: true,
+            bridge_key: Some(PublicKeyEd25519([5u8; 32])),
+            unlock_time: 10,
+            ..default_init_params()
+        };
+
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+        let mut logger = TestLogger::init();
+
+        let mut params = BridgeClaimParams {
+            source_chain: "ethereum".to_string(),
+            source_id: "0xabc".to_string(),
+            claimer: Address::Account(PLAYER),
+            amount: 2,
+            signature: SignatureEd25519([0u8; 64]),
+        };
+        let parameter_bytes = to_bytes(&params);
+        let mut ctx_bridge = TestReceiveContext::empty();
+        ctx_bridge.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        ctx_bridge.set_self_address(ContractAddress {
+            index: 1,
+            subindex: 0,
+        });
+        ctx_bridge.set_parameter(&parameter_bytes);
+
+        let crypto_primitives = crypto_primitives();
+        crypto_primitives.setup_verify_ed25519_signature_mock(|_, _, _| false);
+        assert_eq!(
+            claim_bridged(&ctx_bridge, &mut host, &mut logger, &crypto_primitives),
+            Err(Error::InvalidBridgeAttestation)
+        );
+
+        let message_bytes = to_bytes(&BridgeMessage {
+            contract_address: ContractAddress {
+                index: 1,
+                subindex: 0,
+            },
+            source_chain: "ethereum".to_string(),
+            source_id: "0xabc".to_string(),
+            claimer: Address::Account(PLAYER),
+            amount: 2,
+        });
+        crypto_primitives.setup_verify_ed25519_signature_mock(move |key, _, message| {
+            key == PublicKeyEd25519([5u8; 32]) && message == message_bytes.as_slice()
+        });
+        claim_bridged(&ctx_bridge, &mut host, &mut logger, &crypto_primitives).unwrap();
+        assert_eq!(host.state().claimed_count(), 2);
+        // bridged claims are locked like direct ones
+        for index in 0..2 {
+            assert_eq!(
+                host.state()
+                    .token_unlock_times
+                    .get(&index_token_id(index))
+                    .map(|unlock_time| *unlock_time),
+                Some(Timestamp::from_timestamp_millis(10))
+            );
+        }
+        assert_eq!(
+            logger.logs.last(),
+            Some(&to_bytes(&AirdropEvent::Claim(ClaimEvent {
+                token_id: ContractTokenId::from(0),
+                claimer: Address::Account(PLAYER),
+                amount: 2,
+                slot_time: Timestamp::from_timestamp_millis(1),
+                claim_number: 1,
+            })))
+        );
+
+        // an attestation can only be used once
+        assert_eq!(
+            claim_bridged(&ctx_bridge, &mut host, &mut logger, &crypto_primitives),
+            Err(Error::AlreadyClaimed)
+        );
+
+        // the reserve is left alone
+        params.source_id = "0xdef".to_string();
+        params.amount = 1;
+        let parameter_bytes = to_bytes(&params);
+        ctx_bridge.set_parameter(&parameter_bytes);
+        crypto_primitives.setup_verify_ed25519_signature_mock(|_, _, _| true);
+        assert_eq!(
+            claim_bridged(&ctx_bridge, &mut host, &mut logger, &crypto_primitives),
+            Err(Error::NFTLimitReached)
+        );
+    }
+
+    #[concordium_test]
+    fn test_mint_too_early() {
+        let mut ctx = TestInitContext::empty();
+        let mut state_builder = TestStateBuilder::new();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
+        let account_0_string = "00000000000000000000000000000000000000000000000000".to_string();
+
+        let params = InitParams {
+            nft_limit: 1,
+            nft_time_limit: 20,
+            start_time: 10,
+            ..default_init_params()
+        };
+
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+
+        let mut ctx_claim = TestReceiveContext::empty();
+        let mint_params = ClaimNFTParams {
+            node: Address::Account(ACCOUNT_0),
+            node_string: account_0_string,
+            proof: vec![],
+            selected_token: index_token_id(0),
+            amount_of_tokens: 1,
+            tier: None,
+            round: None,
+            voucher: None,
+            max_claims: None,
+        };
+        let claim_parameter_bytes = to_bytes(&mint_params);
+        ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(5));
+        ctx_claim.set_parameter(&claim_parameter_bytes);
+        ctx_claim.set_sender(Address::Account(ACCOUNT_0));
+
+        let mut logger = TestLogger::init();
+        let claim_result = claim_nft(
+            &ctx_claim,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        );
+        claim_eq!(
+            claim_result,
+            Err(Error::AirdropNotStarted),
+            "Function should fail with Airdrop not started error"
+        );
+
+        ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(15));
+        let claim_result = claim_nft(
+            &ctx_claim,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        );
+        assert!(claim_result.is_ok());
+    }
+
+    #[concordium_test]
+    fn test_phases() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
+        const ACCOUNT_1: AccountAddress = AccountAddress([1u8; 32]);
+        let account_0_string = "00000000000000000000000000000000000000000000000000".to_string();
+        let account_1_string = "11111111111111111111111111111111111111111111111111".to_string();
+
+        let whitelist_phase = Phase {
+            start: Timestamp::from_timestamp_millis(10),
+            end: Timestamp::from_timestamp_millis(20),
+            whitelist_required: true,
+            price: Amount::zero(),
+            max_per_wallet: 0,
+        };
+        let public_phase = Phase {
+            start: Timestamp::from_timestamp_millis(20),
+            end: Timestamp::from_timestamp_millis(30),
+            whitelist_required: false,
+            price: Amount::from_ccd(5),
+            max_per_wallet: 1,
+        };
+
+        let params = InitParams {
+            nft_limit: 10,
+            whitelist: vec![account_0_string.clone()],
+            phases: vec![whitelist_phase, public_phase],
+            ..default_init_params()
+        };
+
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+        let mut logger = TestLogger::init();
+
+        let address_hashed = leaf(&account_0_string);
+        let test_proof = vec![
+            address_hashed.clone(),
+            parent(&address_hashed, &address_hashed),
+        ];
+        let wl_params = ClaimNFTParams {
+            node: Address::Account(ACCOUNT_0),
+            node_string: account_0_string,
+            proof: test_proof,
+            selected_token: index_token_id(0),
+            amount_of_tokens: 1,
+            tier: None,
+            round: None,
+            voucher: None,
+            max_claims: None,
+        };
+        let wl_parameter_bytes = to_bytes(&wl_params);
+        let mut ctx_wl_claim = TestReceiveContext::empty();
+        ctx_wl_claim.set_parameter(&wl_parameter_bytes);
+        ctx_wl_claim.set_sender(Address::Account(ACCOUNT_0));
+
+        let public_params = ClaimNFTParams {
+            node: Address::Account(ACCOUNT_1),
+            node_string: account_1_string,
+            proof: vec![],
+            selected_token: index_token_id(0),
+            amount_of_tokens: 1,
+            tier: None,
+            round: None,
+            voucher: None,
+            max_claims: None,
+        };
+        let public_parameter_bytes = to_bytes(&public_params);
+        let mut ctx_public_claim = TestReceiveContext::empty();
+        ctx_public_claim.set_parameter(&public_parameter_bytes);
+        ctx_public_claim.set_sender(Address::Account(ACCOUNT_1));
+
+        // before the first phase
+        ctx_wl_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(5));
+        assert_eq!(
+            claim_nft(
+                &ctx_wl_claim,
+                &mut host,
+                Amount::zero(),
+                &mut logger,
+                &crypto_primitives()
+            ),
+            Err(Error::AirdropNotStarted)
+        );
+
+        // whitelist phase
+        ctx_wl_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(15));
+        ctx_public_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(15));
+        assert_eq!(
+            claim_nft(
+                &ctx_public_claim,
+                &mut host,
+                Amount::from_ccd(5),
+                &mut logger,
+                &crypto_primitives()
+            ),
+            Err(Error::AddressNotOnWhitelist)
+        );
+        claim_nft(
+            &ctx_wl_claim,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        )
+        .unwrap();
+
+        // public phase
+        ctx_public_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(25));
+        assert_eq!(
+            claim_nft(
+                &ctx_public_claim,
+                &mut host,
+                Amount::zero(),
+                &mut logger,
+                &crypto_primitives()
+            ),
+            Err(Error::InsufficientFunds)
+        );
+        claim_nft(
+            &ctx_public_claim,
+            &mut host,
+            Amount::from_ccd(5),
+            &mut logger,
+            &crypto_primitives(),
+        )
+        .unwrap();
+        assert_eq!(
+            claim_nft(
+                &ctx_public_claim,
+                &mut host,
+                Amount::from_ccd(5),
+                &mut logger,
+                &crypto_primitives()
+            ),
+            Err(Error::WalletLimitReached)
+        );
+
+        // after the last phase
+        ctx_public_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(35));
+        assert_eq!(
+            claim_nft(
+                &ctx_public_claim,
+                &mut host,
+                Amount::from_ccd(5),
+                &mut logger,
+                &crypto_primitives()
+            ),
+            Err(Error::AirdropNowClosed)
+        );
+    }
+
+    #[concordium_test]
+    fn test_tiers() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
+        const ACCOUNT_1: AccountAddress = AccountAddress([1u8; 32]);
+        let account_0_string = "00000000000000000000000000000000000000000000000000".to_string();
+        let account_1_string = "11111111111111111111111111111111111111111111111111".to_string();
+
+        let og_tier = TierParams {
+            whitelist: vec![account_0_string.clone()],
+            max_per_wallet: 1,
+            reserve: 1,
+            unlock_time: 0,
+        };
+
+        let params = InitParams {
+            nft_limit: 3,
+            tiers: vec![og_tier],
+            ..default_init_params()
+        };
+
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+        let mut logger = TestLogger::init();
+
+        let public_params = ClaimNFTParams {
+            node: Address::Account(ACCOUNT_1),
+            node_string: account_1_string.clone(),
+            proof: vec![],
+            selected_token: index_token_id(0),
+            amount_of_tokens: 1,
+            tier: None,
+            round: None,
+            voucher: None,
+            max_claims: None,
+        };
+        let public_parameter_bytes = to_bytes(&public_params);
+        let mut ctx_public_claim = TestReceiveContext::empty();
+        ctx_public_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        ctx_public_claim.set_parameter(&public_parameter_bytes);
+        ctx_public_claim.set_sender(Address::Account(ACCOUNT_1));
+
+        // the last token is held back for the tier
+        claim_nft(
+            &ctx_public_claim,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        )
+        .unwrap();
+        claim_nft(
+            &ctx_public_claim,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        )
+        .unwrap();
+        assert_eq!(
+            claim_nft(
+                &ctx_public_claim,
+                &mut host,
+                Amount::zero(),
+                &mut logger,
+                &crypto_primitives()
+            ),
+            Err(Error::NFTLimitReached)
+        );
+
+        let address_hashed = leaf(&account_0_string);
+        let tier_proof = vec![
+            address_hashed.clone(),
+            parent(&address_hashed, &address_hashed),
+        ];
+
+        // an address outside the tier cannot use it
+        let bad_tier_params = ClaimNFTParams {
+            node: Address::Account(ACCOUNT_1),
+            node_string: account_1_string,
+            proof: tier_proof.clone(),
+            selected_token: index_token_id(0),
+            amount_of_tokens: 1,
+            tier: Some(0),
+            round: None,
+            voucher: None,
+            max_claims: None,
+        };
+        let bad_tier_parameter_bytes = to_bytes(&bad_tier_params);
+        let mut ctx_bad_tier_claim = TestReceiveContext::empty();
+        ctx_bad_tier_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        ctx_bad_tier_claim.set_parameter(&bad_tier_parameter_bytes);
+        ctx_bad_tier_claim.set_sender(Address::Account(ACCOUNT_1));
+        assert_eq!(
+            claim_nft(
+                &ctx_bad_tier_claim,
+                &mut host,
+                Amount::zero(),
+                &mut logger,
+                &crypto_primitives()
+            ),
+            Err(Error::AddressNotOnWhitelist)
+        );
+
+        let tier_params = ClaimNFTParams {
+            node: Address::Account(ACCOUNT_0),
+            node_string: account_0_string,
+            proof: tier_proof,
+            selected_token: index_token_id(0),
+            amount_of_tokens: 1,
+            tier: Some(0),
+            round: None,
+            voucher: None,
+            max_claims: None,
+        };
+        let tier_parameter_bytes = to_bytes(&tier_params);
+        let mut ctx_tier_claim = TestReceiveContext::empty();
+        ctx_tier_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        ctx_tier_claim.set_parameter(&tier_parameter_bytes);
+        ctx_tier_claim.set_sender(Address::Account(ACCOUNT_0));
+        claim_nft(
+            &ctx_tier_claim,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        )
+        .unwrap();
+        assert_eq!(host.state().tiers[0].claimed, 1);
+    }
+
+    #[concordium_test]
+    fn test_burn() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
+        const ACCOUNT_1: AccountAddress = AccountAddress([1u8; 32]);
+        let account_0_string = "00000000000000000000000000000000000000000000000000".to_string();
+
+        let params = InitParams {
+            nft_limit: 3,
+            ..default_init_params()
+        };
+
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+        let mut logger = TestLogger::init();
+
+        let mut ctx_claim = TestReceiveContext::empty();
+        ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mint_params = ClaimNFTParams {
+            node: Address::Account(ACCOUNT_0),
+            node_string: account_0_string,
+            proof: vec![],
+            selected_token: index_token_id(0),
+            amount_of_tokens: 1,
+            tier: None,
+            round: None,
+            voucher: None,
+            max_claims: None,
+        };
+        let claim_parameter_bytes = to_bytes(&mint_params);
+        ctx_claim.set_parameter(&claim_parameter_bytes);
+        ctx_claim.set_sender(Address::Account(ACCOUNT_0));
+        claim_nft(
+            &ctx_claim,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        )
+        .unwrap();
+
+        let burn_params = TokenParam {
+            token: index_token_id(0),
+        };
+        let burn_parameter_bytes = to_bytes(&burn_params);
+        let mut ctx_burn = TestReceiveContext::empty();
+        ctx_burn.set_sender(Address::Account(ACCOUNT_1));
+        ctx_burn.set_parameter(&burn_parameter_bytes);
+
+        assert_eq!(
+            burn(&ctx_burn, &mut host, &mut logger),
+            Err(Error::Unauthorized)
+        );
+
+        // make account 1 an operator for account 0
+        let operator_params = UpdateOperatorParams(vec![UpdateOperator {
+            update: OperatorUpdate::Add,
+            operator: Address::Account(ACCOUNT_1),
+        }]);
+        let operator_parameter_bytes = to_bytes(&operator_params);
+        let mut ctx_operator = TestReceiveContext::empty();
+        ctx_operator.set_sender(Address::Account(ACCOUNT_0));
+        ctx_operator.set_parameter(&operator_parameter_bytes);
+        update_operator(&ctx_operator, &mut host, &mut logger).unwrap();
+
+        burn(&ctx_burn, &mut host, &mut logger).unwrap();
+
+        claim!(
+            logger.logs.contains(&to_bytes(&Cis2Event::Burn(BurnEvent {
+                owner: concordium_std::Address::Account(ACCOUNT_0),
+                token_id: ContractTokenId::from(0),
+                amount: ContractTokenAmount::from(1),
+            }))),
+            "Expected an event for burning token 0"
+        );
+        assert_eq!(
+            burn(&ctx_burn, &mut host, &mut logger),
+            Err(Error::UnknownToken)
+        );
+    }
+
+    #[concordium_test]
+    fn test_unlock_time() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
+        const ACCOUNT_1: AccountAddress = AccountAddress([1u8; 32]);
+        let account_0_string = "00000000000000000000000000000000000000000000000000".to_string();
+
+        let params = InitParams {
+            nft_limit: 3,
+            unlock_time: 10,
+            ..default_init_params()
+        };
+
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+        let mut logger = TestLogger::init();
+
+        let mut ctx_claim = TestReceiveContext::empty();
+        ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mint_params = ClaimNFTParams {
+            node: Address::Account(ACCOUNT_0),
+            node_string: account_0_string,
+            proof: vec![],
+            selected_token: index_token_id(0),
+            amount_of_tokens: 1,
+            tier: None,
+            round: None,
+            voucher: None,
+            max_claims: None,
+        };
+        let claim_parameter_bytes = to_bytes(&mint_params);
+        ctx_claim.set_parameter(&claim_parameter_bytes);
+        ctx_claim.set_sender(Address::Account(ACCOUNT_0));
+        claim_nft(
+            &ctx_claim,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        )
+        .unwrap();
+
+        let transfer_params = TransferParams(vec![Transfer {
+            token_id: index_token_id(0),
+            amount: ContractTokenAmount::from(1),
+            from: Address::Account(ACCOUNT_0),
+            to: Receiver::Account(ACCOUNT_1),
+            data: AdditionalData::empty(),
+        }]);
+        let transfer_parameter_bytes = to_bytes(&transfer_params);
+        let mut ctx_transfer = TestReceiveContext::empty();
+        ctx_transfer.set_sender(Address::Account(ACCOUNT_0));
+        ctx_transfer.set_parameter(&transfer_parameter_bytes);
+        ctx_transfer.set_metadata_slot_time(Timestamp::from_timestamp_millis(9));
+
+        assert_eq!(
+            transfer(&ctx_transfer, &mut host, &mut logger),
+            Err(Error::TokenLocked)
+        );
+
+        let token_parameter_bytes = to_bytes(&index_token_id(0));
+        let mut ctx_view = TestReceiveContext::empty();
+        ctx_view.set_parameter(&token_parameter_bytes);
+        assert_eq!(
+            unlock_time(&ctx_view, &host),
+            Ok(Some(Timestamp::from_timestamp_millis(10)))
+        );
+
+        // the token can move once the unlock time is reached
+        ctx_transfer.set_metadata_slot_time(Timestamp::from_timestamp_millis(10));
+        transfer(&ctx_transfer, &mut host, &mut logger).unwrap();
+        assert_eq!(
+            *host.state().token_owners.get(&index_token_id(0)).unwrap(),
+            Address::Account(ACCOUNT_1)
+        );
+    }
+
+    #[concordium_test]
+    fn test_soulbound_transfer() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
+        const ACCOUNT_1: AccountAddress = AccountAddress([1u8; 32]);
+        let account_0_string = "00000000000000000000000000000000000000000000000000".to_string();
+
+        let params = InitParams {
+            nft_limit: 3,
+            soulbound: true,
+            ..default_init_params()
+        };
+
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+        let mut logger = TestLogger::init();
+
+        let mut ctx_claim = TestReceiveContext::empty();
+        ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mint_params = ClaimNFTParams {
+            node: Address::Account(ACCOUNT_0),
+            node_string: account_0_string,
+            proof: vec![],
+            selected_token: index_token_id(0),
+            amount_of_tokens: 1,
+            tier: None,
+            round: None,
+            voucher: None,
+            max_claims: None,
+        };
+        let claim_parameter_bytes = to_bytes(&mint_params);
+        ctx_claim.set_parameter(&claim_parameter_bytes);
+        ctx_claim.set_sender(Address::Account(ACCOUNT_0));
+        claim_nft(
+            &ctx_claim,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        )
+        .unwrap();
+
+        let transfer_params = TransferParams(vec![Transfer {
+            token_id: index_token_id(0),
+            amount: ContractTokenAmount::from(1),
+            from: Address::Account(ACCOUNT_0),
+            to: Receiver::Account(ACCOUNT_1),
+            data: AdditionalData::empty(),
+        }]);
+        let transfer_parameter_bytes = to_bytes(&transfer_params);
+        let mut ctx_transfer = TestReceiveContext::empty();
+        ctx_transfer.set_sender(Address::Account(ACCOUNT_0));
+        ctx_transfer.set_parameter(&transfer_parameter_bytes);
+
+        assert_eq!(
+            transfer(&ctx_transfer, &mut host, &mut logger),
+            Err(Error::TokenIsSoulbound)
+        );
+
+        // burning is still allowed
+        let burn_params = TokenParam {
+            token: index_token_id(0),
+        };
+        let burn_parameter_bytes = to_bytes(&burn_params);
+        let mut ctx_burn = TestReceiveContext::empty();
+        ctx_burn.set_sender(Address::Account(ACCOUNT_0));
+        ctx_burn.set_parameter(&burn_parameter_bytes);
+        burn(&ctx_burn, &mut host, &mut logger).unwrap();
+
+        // transfers work once the collection is not soulbound
+        host.state_mut().soulbound = false;
+        claim_nft(
+            &ctx_claim,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        )
+        .unwrap();
+        let transfer_params = TransferParams(vec![Transfer {
+            token_id: index_token_id(1),
+            amount: ContractTokenAmount::from(1),
+            from: Address::Account(ACCOUNT_0),
+            to: Receiver::Account(ACCOUNT_1),
+            data: AdditionalData::empty(),
+        }]);
+        let transfer_parameter_bytes = to_bytes(&transfer_params);
+        ctx_transfer.set_parameter(&transfer_parameter_bytes);
+        transfer(&ctx_transfer, &mut host, &mut logger).unwrap();
+        assert_eq!(
+            *host.state().token_owners.get(&index_token_id(1)).unwrap(),
+            Address::Account(ACCOUNT_1)
+        );
+    }
+
+    #[concordium_test]
+    fn test_permit_claim() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        const ADMIN: AccountAddress = AccountAddress([9u8; 32]);
+        const SPONSOR: AccountAddress = AccountAddress([8u8; 32]);
+        const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
+        const SELF_ADDRESS: ContractAddress = ContractAddress {
+            index: 10,
+            subindex: 0,
+        };
+        let account_0_string = "00000000000000000000000000000000000000000000000000".to_string();
+
+        let params = InitParams {
+            nft_limit: 3,
+            ..default_init_params()
+        };
+
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+        let mut logger = TestLogger::init();
+
+        let keys_params = RegisterPublicKeysParams {
+            key: PublicKeyEd25519([1u8; 32]),
+        };
+        let keys_parameter_bytes = to_bytes(&keys_params);
+        let mut ctx_keys = TestReceiveContext::empty();
+        ctx_keys.set_sender(Address::Account(ACCOUNT_0));
+        ctx_keys.set_parameter(&keys_parameter_bytes);
+        register_public_keys(&ctx_keys, &mut host).unwrap();
+
+        // each account registers its own key, even the owner can't set another's
+        let owner_keys_parameter_bytes = to_bytes(&RegisterPublicKeysParams {
+            key: PublicKeyEd25519([3u8; 32]),
+        });
+        let mut ctx_owner_keys = TestReceiveContext::empty();
+        ctx_owner_keys.set_owner(ADMIN);
+        ctx_owner_keys.set_sender(Address::Account(ADMIN));
+        ctx_owner_keys.set_parameter(&owner_keys_parameter_bytes);
+        register_public_keys(&ctx_owner_keys, &mut host).unwrap();
+        assert_eq!(
+            host.state()
+                .public_keys
+                .get(&ACCOUNT_0)
+                .map(|public_key| *public_key),
+            Some(PublicKeyEd25519([1u8; 32]))
+        );
+        ctx_owner_keys.set_sender(Address::Contract(SELF_ADDRESS));
+        assert_eq!(
+            register_public_keys(&ctx_owner_keys, &mut host),
+            Err(Error::Unauthorized)
+        );
+
+        let mint_params = ClaimNFTParams {
+            node: Address::Account(ACCOUNT_0),
+            node_string: account_0_string,
+            proof: vec![],
+            selected_token: index_token_id(0),
+            amount_of_tokens: 1,
+            tier: None,
+            round: None,
+            voucher: None,
+            max_claims: None,
+        };
+        let permit_params = PermitParam {
+            signature: SignatureEd25519([2u8; 64]),
+            signer: ACCOUNT_0,
+            message: PermitMessage {
+                contract_address: SELF_ADDRESS,
+                nonce: 0,
+                timestamp: Timestamp::from_timestamp_millis(100),
+                entry_point: OwnedEntrypointName::new_unchecked("claim_nft".to_string()),
+                payload: to_bytes(&mint_params),
+            },
+        };
+        let permit_parameter_bytes = to_bytes(&permit_params);
+        let mut ctx_permit = TestReceiveContext::empty();
+        ctx_permit.set_sender(Address::Account(SPONSOR));
+        ctx_permit.set_self_address(SELF_ADDRESS);
+        ctx_permit.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        ctx_permit.set_parameter(&permit_parameter_bytes);
+
+        let crypto_primitives = crypto_primitives();
+        crypto_primitives.setup_verify_ed25519_signature_mock(|_, _, _| false);
+        assert_eq!(
+            permit(&ctx_permit, &mut host, &mut logger, &crypto_primitives),
+            Err(Error::WrongSignature)
+        );
+
+        crypto_primitives.setup_verify_ed25519_signature_mock(|_, _, _| true);
+        permit(&ctx_permit, &mut host, &mut logger, &crypto_primitives).unwrap();
+
+        claim!(
+            logger.logs.contains(&to_bytes(&Cis2Event::Mint(MintEvent {
+                owner: concordium_std::Address::Account(ACCOUNT_0),
+                token_id: ContractTokenId::from(0),
+                amount: ContractTokenAmount::from(1),
+            }))),
+            "Expected an event for minting token 0"
+        );
+        assert_eq!(*host.state().nonces.get(&ACCOUNT_0).unwrap(), 1);
+        // CIS-3 lays the Nonce event out as tag 250, the nonce and the sponsoree
+        let mut nonce_event = vec![250u8];
+        nonce_event.extend_from_slice(&0u64.to_le_bytes());
+        nonce_event.extend_from_slice(&ACCOUNT_0.0);
+        claim!(
+            logger.logs.contains(&nonce_event),
+            "Expected a CIS-3 Nonce event"
+        );
+
+        // the same message cannot be used twice
+        assert_eq!(
+            permit(&ctx_permit, &mut host, &mut logger, &crypto_primitives),
+            Err(Error::NonceMismatch)
+        );
+    }
+
+    #[concordium_test]
+    fn test_mint_reserve_to_contract() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        const ADMIN: AccountAddress = AccountAddress([9u8; 32]);
+        const SELF_ADDRESS: ContractAddress = ContractAddress {
+            index: 1,
+            subindex: 0,
+        };
+        const RECEIVER: ContractAddress = ContractAddress {
+            index: 2,
+            subindex: 0,
+        };
+
+        let params = InitParams {
+            nft_limit: 2,
+            reserve: 1,
+            ..default_init_params()
+        };
+
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+        let hook = OwnedEntrypointName::new_unchecked("onReceivingCIS2".to_string());
+
+        let reserve_params = MintReserveParams {
+            to: Receiver::Contract(RECEIVER, hook.clone()),
+            count: 1,
+            data: AdditionalData::empty(),
+        };
+        let reserve_parameter_bytes = to_bytes(&reserve_params);
+        let mut ctx_reserve = TestReceiveContext::empty();
+        ctx_reserve.set_owner(ADMIN);
+        ctx_reserve.set_sender(Address::Account(ADMIN));
+        ctx_reserve.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        ctx_reserve.set_self_address(SELF_ADDRESS);
+        ctx_reserve.set_parameter(&reserve_parameter_bytes);
+        let mut logger = TestLogger::init();
+
+        // the receiving contract rejects the tokens
+        host.setup_mock_entrypoint(
+            RECEIVER,
+            hook.clone(),
+            MockFn::returning_err::<()>(CallContractError::Trap),
+        );
+        assert_eq!(
+            mint_reserve(&ctx_reserve, &mut host, &mut logger),
+            Err(Error::ReceiveHookFailed)
+        );
+
+        // a failed call leaves partial state in the test host, so start over
+        let mut state_builder = TestStateBuilder::new();
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+        host.setup_mock_entrypoint(RECEIVER, hook, MockFn::returning_ok(()));
+        let mut logger = TestLogger::init();
+        mint_reserve(&ctx_reserve, &mut host, &mut logger).unwrap();
+
+        assert_eq!(
+            *host.state().token_owners.get(&index_token_id(0)).unwrap(),
+            Address::Contract(RECEIVER)
+        );
+        assert_eq!(host.state().nft_reserve, None);
+    }
+
+    #[concordium_test]
+    fn test_reveal() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        const ADMIN: AccountAddress = AccountAddress([9u8; 32]);
+        const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
+        let account_0_string = "00000000000000000000000000000000000000000000000000".to_string();
+
+        let params = InitParams {
+            nft_limit: 3,
+            placeholder_url: "https://some.example/hidden.json".to_string(),
+            ..default_init_params()
+        };
+
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+        let mut logger = TestLogger::init();
+
+        let mut ctx_claim = TestReceiveContext::empty();
+        ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mint_params = ClaimNFTParams {
+            node: Address::Account(ACCOUNT_0),
+            node_string: account_0_string,
+            proof: vec![],
+            selected_token: index_token_id(0),
+            amount_of_tokens: 1,
+            tier: None,
+            round: None,
+            voucher: None,
+            max_claims: None,
+        };
+        let claim_parameter_bytes = to_bytes(&mint_params);
+        ctx_claim.set_parameter(&claim_parameter_bytes);
+        ctx_claim.set_sender(Address::Account(ACCOUNT_0));
+        claim_nft(
+            &ctx_claim,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        )
+        .unwrap();
+
+        claim!(
+            logger.logs.contains(&to_bytes(
+                &Cis2Event::TokenMetadata::<_, ContractTokenAmount>(TokenMetadataEvent {
+                    token_id: index_token_id(0),
+                    metadata_url: MetadataUrl {
+                        url: "https://some.example/hidden.json".to_string(),
+                        hash: None,
+                    },
+                })
+            )),
+            "Expected the placeholder URL before the reveal"
+        );
+
+        let reveal_params = RevealParams {
+            base_url: "https://some.example/token/".to_string(),
+        };
+        let reveal_parameter_bytes = to_bytes(&reveal_params);
+        let mut ctx_reveal = TestReceiveContext::empty();
+        ctx_reveal.set_owner(ADMIN);
+        ctx_reveal.set_sender(Address::Account(ACCOUNT_0));
+        ctx_reveal.set_parameter(&reveal_parameter_bytes);
+
+        assert_eq!(
+            reveal(&ctx_reveal, &mut host, &mut logger),
+            Err(Error::Unauthorized)
+        );
+
+        ctx_reveal.set_sender(Address::Account(ADMIN));
+        assert_eq!(
+            freeze_metadata(&ctx_reveal, &mut host),
+            Err(Error::MetadataNotRevealed)
+        );
+        reveal(&ctx_reveal, &mut host, &mut logger).unwrap();
+
+        // once frozen the URLs can not be changed again
+        freeze_metadata(&ctx_reveal, &mut host).unwrap();
+        assert_eq!(
+            reveal(&ctx_reveal, &mut host, &mut logger),
+            Err(Error::MetadataFrozen)
+        );
+
+        let revealed_url = MetadataUrl {
+            url: "https://some.example/token/0".to_string(),
+            hash: None,
+        };
+        claim!(
+            logger.logs.contains(&to_bytes(
+                &Cis2Event::TokenMetadata::<_, ContractTokenAmount>(TokenMetadataEvent {
+                    token_id: index_token_id(0),
+                    metadata_url: revealed_url.clone(),
+                })
+            )),
+            "Expected the real URL to be logged on reveal"
+        );
+
+        let query_params = ContractTokenMetadataQueryParams {
+            queries: vec![index_token_id(0)],
+        };
+        let query_parameter_bytes = to_bytes(&query_params);
+        let mut ctx_query = TestReceiveContext::empty();
+        ctx_query.set_parameter(&query_parameter_bytes);
+        let response = token_metadata(&ctx_query, &host).unwrap();
+        assert_eq!(response.0, vec![revealed_url]);
+    }
+
+    #[concordium_test]
+    fn test_metadata_hashes() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
+        let account_0_string = "00000000000000000000000000000000000000000000000000".to_string();
+
+        let mut params = InitParams {
+            nft_limit: 3,
+            base_url: "https://some.example/token/".to_string(),
+            metadata_hashes: vec![[1u8; 32]],
+            provenance_hash: digest("reshuffled".to_string()),
+            hex_token_ids: true,
+            ..default_init_params()
+        };
+
+        // the metadata hashes have to match the committed provenance
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+        assert!(init(&ctx, &mut state_builder, Amount::zero()).is_err());
+
+        let provenance_hash = digest(&[1u8; 32][..]);
+        params.provenance_hash = provenance_hash.clone();
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+        assert_eq!(
+            provenance(&TestReceiveContext::empty(), &host),
+            Ok(Some(provenance_hash))
+        );
+        let mut logger = TestLogger::init();
+
+        let mut ctx_claim = TestReceiveContext::empty();
+        ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mint_params = ClaimNFTParams {
+            node: Address::Account(ACCOUNT_0),
+            node_string: account_0_string,
+            proof: vec![],
+            selected_token: index_token_id(0),
+            amount_of_tokens: 2,
+            tier: None,
+            round: None,
+            voucher: None,
+            max_claims: None,
+        };
+        let claim_parameter_bytes = to_bytes(&mint_params);
+        ctx_claim.set_parameter(&claim_parameter_bytes);
+        ctx_claim.set_sender(Address::Account(ACCOUNT_0));
+        claim_nft(
+            &ctx_claim,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        )
+        .unwrap();
+
+        claim!(
+            logger.logs.contains(&to_bytes(
+                &Cis2Event::TokenMetadata::<_, ContractTokenAmount>(TokenMetadataEvent {
+                    token_id: index_token_id(0),
+                    metadata_url: MetadataUrl {
+                        url: format!("https://some.example/token/{}", index_token_id(0)),
+                        hash: Some([1u8; 32]),
+                    },
+                })
+            )),
+            "Expected the metadata hash to be logged"
+        );
+
+        // tokens past the end of the list have no hash
+        let query_params = ContractTokenMetadataQueryParams {
+            queries: vec![index_token_id(1)],
+        };
+        let query_parameter_bytes = to_bytes(&query_params);
+        let mut ctx_query = TestReceiveContext::empty();
+        ctx_query.set_parameter(&query_parameter_bytes);
+        let response = token_metadata(&ctx_query, &host).unwrap();
+        assert_eq!(response.0[0].hash, None);
+    }
+
+    #[concordium_test]
+    fn test_tokens_of() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
+        const ACCOUNT_1: AccountAddress = AccountAddress([1u8; 32]);
+        let account_0_string = "00000000000000000000000000000000000000000000000000".to_string();
+        let account_1_string = "11111111111111111111111111111111111111111111111111".to_string();
+
+        let params = InitParams {
+            nft_limit: 4,
+            selected_index: true,
+            ..default_init_params()
+        };
+
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+        let mut logger = TestLogger::init();
+
+        for (node, node_string, token) in [
+            (Address::Account(ACCOUNT_0), account_0_string.clone(), 3),
+            (Address::Account(ACCOUNT_1), account_1_string, 1),
+            (Address::Account(ACCOUNT_0), account_0_string, 0),
+        ] {
+            let mint_params = ClaimNFTParams {
+                node,
+                node_string,
+                proof: vec![],
+                selected_token: index_token_id(token),
+                amount_of_tokens: 1,
+                tier: None,
+                round: None,
+                voucher: None,
+                max_claims: None,
+            };
+            let claim_parameter_bytes = to_bytes(&mint_params);
+            let mut ctx_claim = TestReceiveContext::empty();
+            ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+            ctx_claim.set_parameter(&claim_parameter_bytes);
+            ctx_claim.set_sender(node);
+            claim_nft(
+                &ctx_claim,
+                &mut host,
+                Amount::zero(),
+                &mut logger,
+                &crypto_primitives(),
+            )
+            .unwrap();
+        }
+
+        let query_params = BalanceParam {
+            _dummy: 0,
+            node: Address::Account(ACCOUNT_0),
+        };
+        let query_parameter_bytes = to_bytes(&query_params);
+        let mut ctx_query = TestReceiveContext::empty();
+        ctx_query.set_parameter(&query_parameter_bytes);
+
+        assert_eq!(
+            tokens_of(&ctx_query, &host),
+            Ok(vec![index_token_id(0), index_token_id(3)])
+        );
+    }
+
+    #[concordium_test]
+    fn test_config() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        let params = InitParams {
+            nft_limit: 10,
+            nft_time_limit: 500,
+            reserve: 2,
+            base_url: "https://some.example/token/".to_string(),
+            selected_index: true,
+            nft_limit_per_address: 3,
+            mint_price: Amount::from_micro_ccd(10),
+            soulbound: true,
+            ..default_init_params()
+        };
+
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let host = TestHost::new(state, state_builder);
+        let mut ctx_view = TestReceiveContext::empty();
+        ctx_view.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+
+        assert_eq!(
+            config(&ctx_view, &host).unwrap(),
+            ConfigReply {
+                nft_limit: 10,
+                nft_limit_per_address: Some(3),
+                nft_reserve: Some(2),
+                nft_time_limit: Some(Timestamp::from_timestamp_millis(500)),
+                nft_start_time: None,
+                base_url: "https://some.example/token/".to_string(),
+                metadata: String::new(),
+                whitelist: String::new(),
+                selected_index: true,
+                random_index: false,
+                paused: false,
+                mint_price: Amount::from_micro_ccd(10),
+                phases: vec![],
+                tiers: 0,
+                soulbound: true,
+                placeholder_url: String::new(),
+                revealed: true,
+                sponsored_claims: false,
+                voucher_key: None,
+                sorted_pairs: false,
+                merkle_hash: MerkleHash::Sha256,
+                closed: false,
+                reserve_release_time: None,
+                beneficiaries: vec![],
+                marketplace: None,
+                max_per_tx: None,
+                one_claim_per_account: false,
+                metadata_frozen: false,
+                hex_token_ids: false,
+                payment_token: None,
+                raffle: None,
+                rate_limit: None,
+                leaf_encoding: LeafEncoding::NodeString,
+                supply_locked: false,
+                token_gate: None,
+                identity_policy: None,
+                admins: vec![],
+                admin_threshold: 0,
+                price_curve: None,
+                bridge_key: None,
+                airdrop_token: None,
+                ccd_unit: Amount::zero(),
+                ccd_pool: Amount::zero(),
+                unlock_time: None,
+            }
+        );
+
+        assert_eq!(
+            contract_version(&ctx_view, &host),
+            Ok(VersionReply {
+                version: env!("CARGO_PKG_VERSION").to_string(),
+                state_version: STATE_VERSION,
+                u64_token_ids: cfg!(feature = "u64_token_ids"),
+                paid: true,
+                soulbound: true,
+                selected_index: true,
+                random_index: false,
+                editions: false,
+            })
+        );
+    }
+
+    #[concordium_test]
+    fn test_sender_mismatch() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
+        const RELAYER: AccountAddress = AccountAddress([8u8; 32]);
+        let account_0_string = "00000000000000000000000000000000000000000000000000".to_string();
+
+        let mut params = InitParams {
+            nft_limit: 3,
+            ..default_init_params()
+        };
+
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+        let mut logger = TestLogger::init();
+
+        let mut ctx_claim = TestReceiveContext::empty();
+        ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mint_params = ClaimNFTParams {
+            node: Address::Account(ACCOUNT_0),
+            node_string: account_0_string,
+            proof: vec![],
+            selected_token: index_token_id(0),
+            amount_of_tokens: 1,
+            tier: None,
+            round: None,
+            voucher: None,
+            max_claims: None,
+        };
+        let claim_parameter_bytes = to_bytes(&mint_params);
+        ctx_claim.set_parameter(&claim_parameter_bytes);
+        ctx_claim.set_sender(Address::Account(RELAYER));
+
+        assert_eq!(
+            claim_nft(
+                &ctx_claim,
+                &mut host,
+                Amount::zero(),
+                &mut logger,
+                &crypto_primitives()
+            ),
+            Err(Error::SenderMismatch)
+        );
+
+        // an operator of the account can claim for it, minting to the account
+        let operator_parameter_bytes = to_bytes(&UpdateOperatorParams(vec![UpdateOperator {
+            update: OperatorUpdate::Add,
+            operator: Address::Account(RELAYER),
+        }]));
+        let mut ctx_operator = TestReceiveContext::empty();
+        ctx_operator.set_sender(Address::Account(ACCOUNT_0));
+        ctx_operator.set_parameter(&operator_parameter_bytes);
+        update_operator(&ctx_operator, &mut host, &mut logger).unwrap();
+        claim_nft(
+            &ctx_claim,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        )
+        .unwrap();
+        assert_eq!(
+            host.state()
+                .token_owners
+                .get(&index_token_id(0))
+                .map(|owner| *owner),
+            Some(Address::Account(ACCOUNT_0))
+        );
+
+        // a relayer can claim for the account once sponsored claims are allowed
+        params.allow_sponsored_claims = true;
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+        let mut state_builder = TestStateBuilder::new();
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+
+        claim_nft(
+            &ctx_claim,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        )
+        .unwrap();
+        assert_eq!(
+            *host
+                .state()
+                .claimed_nfts
+                .get(&Address::Account(ACCOUNT_0))
+                .unwrap(),
+            1
+        );
+
+        // malformed parameters are reported as such rather than as a claim error
+        ctx_claim.set_parameter(&[1, 2, 3]);
+        assert_eq!(
+            claim_nft(
+                &ctx_claim,
+                &mut host,
+                Amount::zero(),
+                &mut logger,
+                &crypto_primitives()
+            ),
+            Err(Error::ParseParams)
+        );
+    }
+
+    #[concordium_test]
+    fn test_rounds() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        const ADMIN: AccountAddress = AccountAddress([9u8; 32]);
+        const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
+        let account_0_string = "00000000000000000000000000000000000000000000000000".to_string();
+
+        let params = InitParams {
+            nft_limit: 1,
+            unlock_time: 10,
+            ..default_init_params()
+        };
+
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+        let mut logger = TestLogger::init();
+
+        let round_params = CreateRoundParams {
+            nft_limit: 2,
+            nft_limit_per_address: 0,
+            start_time: 0,
+            end_time: 0,
+            whitelist: vec![],
+            base_url: "https://some.example/round-1/".to_string(),
+            mint_price: Amount::zero(),
+        };
+        let round_parameter_bytes = to_bytes(&round_params);
+        let mut ctx_round = TestReceiveContext::empty();
+        ctx_round.set_owner(ADMIN);
+        ctx_round.set_sender(Address::Account(ADMIN));
+        ctx_round.set_parameter(&round_parameter_bytes);
+        create_round(&ctx_round, &mut host, &mut logger).unwrap();
+
+        let mut ctx_claim = TestReceiveContext::empty();
+        ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        ctx_claim.set_sender(Address::Account(ACCOUNT_0));
+        let mut mint_params = ClaimNFTParams {
+            node: Address::Account(ACCOUNT_0),
+            node_string: account_0_string,
+            proof: vec![],
+            selected_token: index_token_id(0),
+            amount_of_tokens: 2,
+            tier: None,
+            round: Some(1),
+            voucher: None,
+            max_claims: None,
+        };
+        let claim_parameter_bytes = to_bytes(&mint_params);
+        ctx_claim.set_parameter(&claim_parameter_bytes);
+        claim_nft(
+            &ctx_claim,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        )
+        .unwrap();
+
+        // the round's tokens are namespaced and leave the main collection untouched
+        assert_eq!(
+            *host
+                .state()
+                .token_owners
+                .get(&round_token_id(1, 0))
+                .unwrap(),
+            Address::Account(ACCOUNT_0)
+        );
+        assert_eq!(host.state().claimed_count(), 0);
+        // view pages move on from the end of the main collection to the round
+        let view_parameter_bytes = to_bytes(&ViewParams {
+            from: None,
+            limit: 2,
+        });
+        let mut ctx_view = TestReceiveContext::empty();
+        ctx_view.set_parameter(&view_parameter_bytes);
+        let view_result = view(&ctx_view, &host).unwrap();
+        assert_eq!(
+            view_result.claimed,
+            vec![(
+                round_token_id(1, 0),
+                address_to_string(Address::Account(ACCOUNT_0))
+            )]
+        );
+        assert_eq!(view_result.next, Some(round_token_id(1, 1)));
+        // round claims are locked like claims of the main collection
+        for index in 0..2 {
+            assert_eq!(
+                host.state()
+                    .token_unlock_times
+                    .get(&round_token_id(1, index))
+                    .map(|unlock_time| *unlock_time),
+                Some(Timestamp::from_timestamp_millis(10))
+            );
+        }
+        assert_eq!(
+            host.state().metadata_url(round_token_id(1, 1)).url,
+            format!("https://some.example/round-1/{}", round_token_id(1, 1).0)
+        );
+
+        mint_params.amount_of_tokens = 1;
+        let claim_parameter_bytes = to_bytes(&mint_params);
+        ctx_claim.set_parameter(&claim_parameter_bytes);
+        assert_eq!(
+            claim_nft(
+                &ctx_claim,
+                &mut host,
+                Amount::zero(),
+                &mut logger,
+                &crypto_primitives()
+            ),
+            Err(Error::NFTLimitReached)
+        );
+
+        mint_params.round = Some(2);
+        let claim_parameter_bytes = to_bytes(&mint_params);
+        ctx_claim.set_parameter(&claim_parameter_bytes);
+        assert_eq!(
+            claim_nft(
+                &ctx_claim,
+                &mut host,
+                Amount::zero(),
+                &mut logger,
+                &crypto_primitives()
+            ),
+            Err(Error::InvalidRound)
+        );
+    }
+
+    #[concordium_test]
+    fn test_random_index() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        const ADMIN: AccountAddress = AccountAddress([9u8; 32]);
+        const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
+        let account_0_string = "00000000000000000000000000000000000000000000000000".to_string();
+
+        let params = InitParams {
+            nft_limit: 5,
+            random_index: true,
+            random_salt_commitment: digest("secret salt".to_string()),
+            ..default_init_params()
+        };
+
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+        let mut logger = TestLogger::init();
+
+        let mut ctx_claim = TestReceiveContext::empty();
+        ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        ctx_claim.set_sender(Address::Account(ACCOUNT_0));
+        let mint_params = ClaimNFTParams {
+            node: Address::Account(ACCOUNT_0),
+            node_string: account_0_string,
+            proof: vec![],
+            selected_token: index_token_id(0),
+            amount_of_tokens: 5,
+            tier: None,
+            round: None,
+            voucher: None,
+            max_claims: None,
+        };
+        let claim_parameter_bytes = to_bytes(&mint_params);
+        ctx_claim.set_parameter(&claim_parameter_bytes);
+
+        assert_eq!(
+            claim_nft(
+                &ctx_claim,
+                &mut host,
+                Amount::zero(),
+                &mut logger,
+                &crypto_primitives()
+            ),
+            Err(Error::RandomSaltNotRevealed)
+        );
+
+        let mut ctx_salt = TestReceiveContext::empty();
+        ctx_salt.set_owner(ADMIN);
+        ctx_salt.set_sender(Address::Account(ADMIN));
+        let salt_params = RandomSaltParams {
+            salt: "another salt".to_string(),
+        };
+        let salt_parameter_bytes = to_bytes(&salt_params);
+        ctx_salt.set_parameter(&salt_parameter_bytes);
+        assert_eq!(
+            reveal_random_salt(&ctx_salt, &mut host),
+            Err(Error::WrongRandomSalt)
+        );
+
+        let salt_params = RandomSaltParams {
+            salt: "secret salt".to_string(),
+        };
+        let salt_parameter_bytes = to_bytes(&salt_params);
+        ctx_salt.set_parameter(&salt_parameter_bytes);
+        reveal_random_salt(&ctx_salt, &mut host).unwrap();
+
+        claim_nft(
+            &ctx_claim,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        )
+        .unwrap();
+
+        // every token in the pool is drawn exactly once
+        for token in 0..5 {
+            assert_eq!(
+                *host
+                    .state()
+                    .token_owners
+                    .get(&index_token_id(token))
+                    .unwrap(),
+                Address::Account(ACCOUNT_0)
+            );
+        }
+        assert_eq!(host.state().claimed_count(), 5);
+        assert_eq!(
+            claim_nft(
+                &ctx_claim,
+                &mut host,
+                Amount::zero(),
+                &mut logger,
+                &crypto_primitives()
+            ),
+            Err(Error::NFTLimitReached)
+        );
+    }
+
+    #[concordium_test]
+    fn test_random_airdrop_to() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        const ADMIN: AccountAddress = AccountAddress([9u8; 32]);
+        const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
+        const ACCOUNT_1: AccountAddress = AccountAddress([1u8; 32]);
+
+        let params = InitParams {
+            nft_limit: 5,
+            random_index: true,
+            random_salt_commitment: digest("secret salt".to_string()),
+            ..default_init_params()
+        };
+
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+        let mut logger = TestLogger::init();
+
+        let airdrop_params = AirdropParams {
+            recipients: vec![
+                (ACCOUNT_0, index_token_id(0)),
+                (ACCOUNT_1, index_token_id(0)),
+            ],
+        };
+        let airdrop_parameter_bytes = to_bytes(&airdrop_params);
+        let mut ctx_airdrop = TestReceiveContext::empty();
+        ctx_airdrop.set_owner(ADMIN);
+        ctx_airdrop.set_sender(Address::Account(ADMIN));
+        ctx_airdrop.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        ctx_airdrop.set_parameter(&airdrop_parameter_bytes);
+
+        // the IDs are drawn from the pool, which needs the salt
+        assert_eq!(
+            airdrop_to(&ctx_airdrop, &mut host, &mut logger),
+            Err(Error::RandomSaltNotRevealed)
+        );
+
+        let mut ctx_salt = TestReceiveContext::empty();
+        ctx_salt.set_owner(ADMIN);
+        ctx_salt.set_sender(Address::Account(ADMIN));
+        let salt_params = RandomSaltParams {
+            salt: "secret salt".to_string(),
+        };
+        let salt_parameter_bytes = to_bytes(&salt_params);
+        ctx_salt.set_parameter(&salt_parameter_bytes);
+        reveal_random_salt(&ctx_salt, &mut host).unwrap();
+
+        airdrop_to(&ctx_airdrop, &mut host, &mut logger).unwrap();
+
+        // each recipient gets a different token, and keeps it
+        let owned: Vec<Address> = (0..5)
+            .filter_map(|token| {
+                host.state()
+                    .token_owners
+                    .get(&index_token_id(token))
+                    .map(|owner| *owner)
+            })
+            .collect();
+        assert_eq!(owned.len(), 2);
+        assert!(owned.contains(&Address::Account(ACCOUNT_0)));
+        assert!(owned.contains(&Address::Account(ACCOUNT_1)));
+        assert_eq!(host.state().claimed_count(), 2);
+    }
+
+    #[concordium_test]
+    fn test_contract_claimer() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        const DAO: ContractAddress = ContractAddress {
+            index: 5,
+            subindex: 0,
+        };
+        let dao_string = "<5,0>".to_string();
+        let account_1_string = "11111111111111111111111111111111111111111111111111".to_string();
+
+        let params = InitParams {
+            nft_limit: 3,
+            whitelist: vec![dao_string.clone(), account_1_string.clone()],
+            ..default_init_params()
+        };
+
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+        let mut logger = TestLogger::init();
+
+        let dao_leaf = leaf(&dao_string);
+        let mut ctx_claim = TestReceiveContext::empty();
+        ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        ctx_claim.set_sender(Address::Contract(DAO));
+        let mint_params = ClaimNFTParams {
+            node: Address::Contract(DAO),
+            node_string: dao_string,
+            proof: vec![
+                dao_leaf.clone(),
+                parent(&dao_leaf, &leaf(&account_1_string)),
+            ],
+            selected_token: index_token_id(0),
+            amount_of_tokens: 1,
+            tier: None,
+            round: None,
+            voucher: None,
+            max_claims: None,
+        };
+        let claim_parameter_bytes = to_bytes(&mint_params);
+        ctx_claim.set_parameter(&claim_parameter_bytes);
+        claim_nft(
+            &ctx_claim,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        )
+        .unwrap();
+
+        let query_params = BalanceParam {
+            _dummy: 0,
+            node: Address::Contract(DAO),
+        };
+        let query_parameter_bytes = to_bytes(&query_params);
+        let mut ctx_query = TestReceiveContext::empty();
+        ctx_query.set_parameter(&query_parameter_bytes);
+        assert_eq!(balance_of(&ctx_query, &host), Ok(1));
+        assert_eq!(tokens_of(&ctx_query, &host), Ok(vec![index_token_id(0)]));
+    }
+
+    #[concordium_test]
+    fn test_voucher_claim() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
+        const SELF_ADDRESS: ContractAddress = ContractAddress {
+            index: 1,
+            subindex: 0,
+        };
+        let account_0_string = "00000000000000000000000000000000000000000000000000".to_string();
+        let account_1_string = "11111111111111111111111111111111111111111111111111".to_string();
+
+        // only account 1 is on the whitelist, account 0 claims with a voucher
+        let params = InitParams {
+            nft_limit: 5,
+            whitelist: vec![account_1_string],
+            voucher_key: Some(PublicKeyEd25519([3u8; 32])),
+            ..default_init_params()
+        };
+
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+        let mut logger = TestLogger::init();
+
+        let voucher_message = VoucherMessage {
+            contract_address: SELF_ADDRESS,
+            claimer: Address::Account(ACCOUNT_0),
+            max_amount: 2,
+            expiry: Timestamp::from_timestamp_millis(10),
+        };
+        let voucher_message_bytes = to_bytes(&voucher_message);
+        let crypto_primitives = crypto_primitives();
+        crypto_primitives.setup_verify_ed25519_signature_mock(move |key, _, message| {
+            key == PublicKeyEd25519([3u8; 32]) && message == voucher_message_bytes.as_slice()
+        });
+
+        let mut ctx_claim = TestReceiveContext::empty();
+        ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        ctx_claim.set_self_address(SELF_ADDRESS);
+        ctx_claim.set_sender(Address::Account(ACCOUNT_0));
+        let mut mint_params = ClaimNFTParams {
+            node: Address::Account(ACCOUNT_0),
+            node_string: account_0_string,
+            proof: vec![],
+            selected_token: index_token_id(0),
+            amount_of_tokens: 1,
+            tier: None,
+            round: None,
+            voucher: None,
+            max_claims: None,
+        };
+        let claim_parameter_bytes = to_bytes(&mint_params);
+        ctx_claim.set_parameter(&claim_parameter_bytes);
+        assert_eq!(
+            claim_nft(
+                &ctx_claim,
+                &mut host,
+                Amount::zero(),
+                &mut logger,
+                &crypto_primitives
+            ),
+            Err(Error::AddressNotOnWhitelist)
+        );
+
+        // a voucher for a different amount than the one signed is rejected
+        mint_params.voucher = Some(Voucher {
+            max_amount: 3,
+            expiry: Timestamp::from_timestamp_millis(10),
+            signature: SignatureEd25519([4u8; 64]),
+        });
+        let claim_parameter_bytes = to_bytes(&mint_params);
+        ctx_claim.set_parameter(&claim_parameter_bytes);
+        assert_eq!(
+            claim_nft(
+                &ctx_claim,
+                &mut host,
+                Amount::zero(),
+                &mut logger,
+                &crypto_primitives
+            ),
+            Err(Error::InvalidVoucher)
+        );
+
+        mint_params.voucher = Some(Voucher {
+            max_amount: 2,
+            expiry: Timestamp::from_timestamp_millis(10),
+            signature: SignatureEd25519([4u8; 64]),
+        });
+        let claim_parameter_bytes = to_bytes(&mint_params);
+        ctx_claim.set_parameter(&claim_parameter_bytes);
+        claim_nft(
+            &ctx_claim,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives,
+        )
+        .unwrap();
+
+        mint_params.amount_of_tokens = 2;
+        let claim_parameter_bytes = to_bytes(&mint_params);
+        ctx_claim.set_parameter(&claim_parameter_bytes);
+        assert_eq!(
+            claim_nft(
+                &ctx_claim,
+                &mut host,
+                Amount::zero(),
+                &mut logger,
+                &crypto_primitives
+            ),
+            Err(Error::WalletLimitReached)
+        );
+
+        ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(11));
+        mint_params.amount_of_tokens = 1;
+        let claim_parameter_bytes = to_bytes(&mint_params);
+        ctx_claim.set_parameter(&claim_parameter_bytes);
+        assert_eq!(
+            claim_nft(
+                &ctx_claim,
+                &mut host,
+                Amount::zero(),
+                &mut logger,
+                &crypto_primitives
+            ),
+            Err(Error::VoucherExpired)
+        );
+    }
+
+    #[concordium_test]
+    fn test_editions() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
+        const ACCOUNT_1: AccountAddress = AccountAddress([1u8; 32]);
+        let account_0_string = "00000000000000000000000000000000000000000000000000".to_string();
+
+        let params = InitParams {
+            nft_limit: 10,
+            selected_index: true,
+            edition_sizes: vec![3, 1],
+            ..default_init_params()
+        };
+
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+        let mut logger = TestLogger::init();
+
+        let mut ctx_claim = TestReceiveContext::empty();
+        ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        ctx_claim.set_sender(Address::Account(ACCOUNT_0));
+        let mut mint_params = ClaimNFTParams {
+            node: Address::Account(ACCOUNT_0),
+            node_string: account_0_string,
+            proof: vec![],
+            selected_token: index_token_id(0),
+            amount_of_tokens: 2,
+            tier: None,
+            round: None,
+            voucher: None,
+            max_claims: None,
+        };
+        let claim_parameter_bytes = to_bytes(&mint_params);
+        ctx_claim.set_parameter(&claim_parameter_bytes);
+        claim_nft(
+            &ctx_claim,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        )
+        .unwrap();
+
+        claim!(
+            logger.logs.contains(&to_bytes(&Cis2Event::Mint(MintEvent {
+                owner: Address::Account(ACCOUNT_0),
+                token_id: index_token_id(0),
+                amount: ContractTokenAmount::from(2),
+            }))),
+            "Expected two copies of token 0 to be minted"
+        );
+
+        let mut ctx_supply = TestReceiveContext::empty();
+        let supply_parameter_bytes = to_bytes(&TokenParam {
+            token: index_token_id(0),
+        });
+        ctx_supply.set_parameter(&supply_parameter_bytes);
+        assert_eq!(edition_supply(&ctx_supply, &host), Ok(1));
+
+        // only one copy is left
+        let claim_parameter_bytes = to_bytes(&mint_params);
+        ctx_claim.set_parameter(&claim_parameter_bytes);
+        assert_eq!(
+            claim_nft(
+                &ctx_claim,
+                &mut host,
+                Amount::zero(),
+                &mut logger,
+                &crypto_primitives()
+            ),
+            Err(Error::EditionSoldOut)
+        );
+
+        mint_params.amount_of_tokens = 1;
+        let claim_parameter_bytes = to_bytes(&mint_params);
+        ctx_claim.set_parameter(&claim_parameter_bytes);
+        claim_nft(
+            &ctx_claim,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        )
+        .unwrap();
+        assert_eq!(edition_supply(&ctx_supply, &host), Ok(0));
+
+        // copies of an edition can be transferred in any amount the holder has
+        let transfer_params = TransferParams(vec![Transfer {
+            token_id: index_token_id(0),
+            amount: ContractTokenAmount::from(2),
+            from: Address::Account(ACCOUNT_0),
+            to: Receiver::Account(ACCOUNT_1),
+            data: AdditionalData::empty(),
+        }]);
+        let transfer_parameter_bytes = to_bytes(&transfer_params);
+        let mut ctx_transfer = TestReceiveContext::empty();
+        ctx_transfer.set_sender(Address::Account(ACCOUNT_0));
+        ctx_transfer.set_parameter(&transfer_parameter_bytes);
+        transfer(&ctx_transfer, &mut host, &mut logger).unwrap();
+
+        assert_eq!(
+            *host
+                .state()
+                .edition_balances
+                .get(&(index_token_id(0), Address::Account(ACCOUNT_0)))
+                .unwrap(),
+            1
+        );
+        assert_eq!(
+            *host
+                .state()
+                .edition_balances
+                .get(&(index_token_id(0), Address::Account(ACCOUNT_1)))
+                .unwrap(),
+            2
+        );
+
+        // the snapshot lists each holder of an edition once, new holders last
+        let snapshot_parameter_bytes = to_bytes(&SnapshotParams {
+            from_token: None,
+            offset: 0,
+            limit: 10,
+        });
+        let mut ctx_snapshot = TestReceiveContext::empty();
+        ctx_snapshot.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        ctx_snapshot.set_parameter(&snapshot_parameter_bytes);
+        let page = snapshot(&ctx_snapshot, &host).unwrap();
+        assert!(page.token_owners.is_empty());
+        assert_eq!(page.next_token, None);
+        assert!(page.edition_balances.contains(&(
+            index_token_id(0),
+            Address::Account(ACCOUNT_0),
+            1
+        )));
+        assert_eq!(
+            page.edition_balances.last(),
+            Some(&(index_token_id(0), Address::Account(ACCOUNT_1), 2))
+        );
+        assert!(!page.has_more);
+    }
+
+    #[concordium_test]
+    fn test_sorted_pairs() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        let account_0_string = "00000000000000000000000000000000000000000000000000".to_string();
+        let account_1_string = "11111111111111111111111111111111111111111111111111".to_string();
+        let account_2_string = "22222222222222222222222222222222222222222222222222".to_string();
+
+        let whitelist: Vec<String> = vec![
+            account_0_string.clone(),
+            account_1_string.clone(),
+            account_2_string,
+        ];
+
+        let params = InitParams {
+            nft_limit: 1,
+            whitelist: whitelist.clone(),
+            sorted_pairs: true,
+            ..default_init_params()
+        };
+
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+
+        let hashes: Vec<String> = whitelist.iter().map(|address| leaf(address)).collect();
+        let sorted = |x: &String, y: &String| {
+            if x < y {
+                parent(x, y)
+            } else {
+                parent(y, x)
+            }
+        };
+        let a = sorted(&hashes[0], &hashes[1]);
+        let b = sorted(&hashes[2], &hashes[2]);
+        let c = sorted(&a, &b);
+
+        let merkle_proof = state.get_hash_proof(hashes[1].clone()).unwrap();
+        assert_eq!(merkle_proof, vec![hashes[1].clone(), a, c]);
+
+        const ACCOUNT_1: AccountAddress = AccountAddress([1u8; 32]);
+        let proof_params = ClaimNFTParams {
+            proof: merkle_proof,
+            node: Address::Account(ACCOUNT_1),
+            node_string: account_1_string,
+            selected_token: index_token_id(0),
+            amount_of_tokens: 1,
+            tier: None,
+            round: None,
+            voucher: None,
+            max_claims: None,
+        };
+        assert!(state.check_proof(&proof_params, &crypto_primitives()));
+        assert!(state.check_hash_value(hashes[2].clone()));
+    }
+
+    #[concordium_test]
+    fn test_keccak_whitelist() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        assert_eq!(
+            merkle_core::MerkleHash::from(MerkleHash::Keccak256).digest(b""),
+            "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+        );
+
+        let account_0_string = "00000000000000000000000000000000000000000000000000".to_string();
+        let account_1_string = "11111111111111111111111111111111111111111111111111".to_string();
+
+        let params = InitParams {
+            nft_limit: 1,
+            whitelist: vec![account_0_string.clone(), account_1_string.clone()],
+            merkle_hash: MerkleHash::Keccak256,
+            ..default_init_params()
+        };
+
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+
+        let acc0 = MerkleHash::Keccak256.hash_leaf(&account_0_string);
+        let acc1 = MerkleHash::Keccak256.hash_leaf(&account_1_string);
+        let root = merkle_core::hash_pair(&acc0, &acc1, false, MerkleHash::Keccak256.into());
+        assert_ne!(acc0, leaf(&account_0_string));
+
+        const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
+        let proof_params = ClaimNFTParams {
+            proof: vec![acc0, root],
+            node: Address::Account(ACCOUNT_0),
+            node_string: account_0_string,
+            selected_token: index_token_id(0),
+            amount_of_tokens: 1,
+            tier: None,
+            round: None,
+            voucher: None,
+            max_claims: None,
+        };
+        assert!(state.check_proof(&proof_params, &crypto_primitives()));
+        assert!(state.check_hash_value(acc1));
+    }
+
+    #[concordium_test]
+    fn test_sha3_whitelist() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        assert_eq!(
+            merkle_core::MerkleHash::from(MerkleHash::Sha3_256).digest(b""),
+            "a7ffc6f8bf1ed76651c14756a061d662f580ff4de43b49fa82d80a4b80f8434a"
+        );
+
+        let account_0_string = "00000000000000000000000000000000000000000000000000".to_string();
+        let account_1_string = "11111111111111111111111111111111111111111111111111".to_string();
+
+        let params = InitParams {
+            nft_limit: 1,
+            whitelist: vec![account_0_string.clone(), account_1_string.clone()],
+            merkle_hash: MerkleHash::Sha3_256,
+            ..default_init_params()
+        };
+
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+
+        let acc0 = MerkleHash::Sha3_256.hash_leaf(&account_0_string);
+        let acc1 = MerkleHash::Sha3_256.hash_leaf(&account_1_string);
+        let root = merkle_core::hash_pair(&acc0, &acc1, false, MerkleHash::Sha3_256.into());
+        assert_ne!(acc0, leaf(&account_0_string));
+
+        const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
+        let proof_params = ClaimNFTParams {
+            proof: vec![acc0, root],
+            node: Address::Account(ACCOUNT_0),
+            node_string: account_0_string,
+            selected_token: index_token_id(0),
+            amount_of_tokens: 1,
+            tier: None,
+            round: None,
+            voucher: None,
+            max_claims: None,
+        };
+        assert!(state.check_proof(&proof_params, &crypto_primitives()));
+        assert!(state.check_hash_value(acc1));
+
+        // a proof from a sha256 tree of the same whitelist is rejected
+        let mut proof_params = proof_params;
+        let sha256_leaf = leaf(&proof_params.node_string);
+        proof_params.proof = vec![
+            sha256_leaf.clone(),
+            parent(&sha256_leaf, &leaf(&account_1_string)),
+        ];
+        assert!(!state.check_proof(&proof_params, &crypto_primitives()));
+    }
+
+    #[concordium_test]
+    fn test_domain_separated_proofs() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        let account_0_string = "00000000000000000000000000000000000000000000000000".to_string();
+        let account_1_string = "11111111111111111111111111111111111111111111111111".to_string();
+
+        let params = InitParams {
+            nft_limit: 1,
+            whitelist: vec![account_0_string.clone(), account_1_string.clone()],
+            ..default_init_params()
+        };
+
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+
+        const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
+        let mut proof_params = ClaimNFTParams {
+            proof: vec![],
+            node: Address::Account(ACCOUNT_0),
+            node_string: account_0_string.clone(),
+            selected_token: index_token_id(0),
+            amount_of_tokens: 1,
+            tier: None,
+            round: None,
+            voucher: None,
+            max_claims: None,
+        };
+
+        // Proofs built without the leaf and node prefixes are rejected
+        let old_leaf_0 = digest(account_0_string.clone());
+        let old_leaf_1 = digest(account_1_string.clone());
+        proof_params.proof = vec![old_leaf_0.clone(), digest(old_leaf_0 + &old_leaf_1)];
+        assert!(!state.check_proof(&proof_params, &crypto_primitives()));
+
+        let leaf_0 = leaf(&account_0_string);
+        let leaf_1 = leaf(&account_1_string);
+        proof_params.proof = vec![leaf_0.clone(), parent(&leaf_0, &leaf_1)];
+        assert!(state.check_proof(&proof_params, &crypto_primitives()));
+
+        // An internal node is not a leaf, even though its hash is in the tree
+        assert_ne!(leaf(&(leaf_0.clone() + &leaf_1)), parent(&leaf_0, &leaf_1));
+    }
+
+    #[concordium_test]
+    fn test_close_airdrop() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        const ADMIN: AccountAddress = AccountAddress([9u8; 32]);
+        const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
+        let account_0_string = "00000000000000000000000000000000000000000000000000".to_string();
+
+        let params = InitParams {
+            nft_limit: 3,
+            ..default_init_params()
+        };
+
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+
+        // only the owner can close the airdrop
+        let mut ctx_close = TestReceiveContext::empty();
+        ctx_close.set_owner(ADMIN);
+        ctx_close.set_sender(Address::Account(ACCOUNT_0));
+        assert_eq!(
+            close_airdrop(&ctx_close, &mut host),
+            Err(Error::Unauthorized)
+        );
+
+        ctx_close.set_sender(Address::Account(ADMIN));
+        close_airdrop(&ctx_close, &mut host).unwrap();
+
+        let mut ctx_claim = TestReceiveContext::empty();
+        ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mint_params = ClaimNFTParams {
+            node: Address::Account(ACCOUNT_0),
+            node_string: account_0_string,
+            proof: vec![],
+            selected_token: index_token_id(0),
+            amount_of_tokens: 1,
+            tier: None,
+            round: None,
+            voucher: None,
+            max_claims: None,
+        };
+        let claim_parameter_bytes = to_bytes(&mint_params);
+        ctx_claim.set_parameter(&claim_parameter_bytes);
+        ctx_claim.set_sender(Address::Account(ACCOUNT_0));
+        let mut logger = TestLogger::init();
+
+        let claim_result = claim_nft(
+            &ctx_claim,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        );
+        assert_eq!(claim_result, Err(Error::AirdropNowClosed));
+
+        // unpausing does not reopen a closed airdrop
+        unpause(&ctx_close, &mut host).unwrap();
+        let claim_result = claim_nft(
+            &ctx_claim,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        );
+        assert_eq!(claim_result, Err(Error::AirdropNowClosed));
+
+        let mut ctx_view = TestReceiveContext::empty();
+        ctx_view.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        assert!(config(&ctx_view, &host).unwrap().closed);
+    }
+
+    #[concordium_test]
+    fn test_reserve_release_time() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        let account_0_string = "00000000000000000000000000000000000000000000000000".to_string();
+        let account_1_string = "11111111111111111111111111111111111111111111111111".to_string();
+        const ACCOUNT_1: AccountAddress = AccountAddress([1u8; 32]);
+
+        let params = InitParams {
+            nft_limit: 2,
+            whitelist: vec![account_0_string],
+            reserve: 1,
+            reserve_release_time: 10,
+            ..default_init_params()
+        };
+
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+        let mut logger = TestLogger::init();
+
+        let mint_params = ClaimNFTParams {
+            node: Address::Account(ACCOUNT_1),
+            node_string: account_1_string,
+            proof: vec![],
+            selected_token: index_token_id(0),
+            amount_of_tokens: 1,
+            tier: None,
+            round: None,
+            voucher: None,
+            max_claims: None,
+        };
+        let claim_parameter_bytes = to_bytes(&mint_params);
+        let mut ctx_claim = TestReceiveContext::empty();
+        ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        ctx_claim.set_parameter(&claim_parameter_bytes);
+        ctx_claim.set_sender(Address::Account(ACCOUNT_1));
+
+        // the public part can be claimed without a proof
+        claim_nft(
+            &ctx_claim,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        )
+        .unwrap();
+
+        // the reserve is held back for the whitelist until the release time
+        let claim_result = claim_nft(
+            &ctx_claim,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        );
+        assert_eq!(claim_result, Err(Error::AddressNotOnWhitelist));
+
+        ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(10));
+        claim_nft(
+            &ctx_claim,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        )
+        .unwrap();
+        assert_eq!(host.state().claimed_count(), 2);
+    }
+
+    #[concordium_test]
+    fn test_payment_splitter() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        const ADMIN: AccountAddress = AccountAddress([9u8; 32]);
+        const ARTIST: AccountAddress = AccountAddress([8u8; 32]);
+        const STUDIO: AccountAddress = AccountAddress([7u8; 32]);
+        const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
+        let account_0_string = "00000000000000000000000000000000000000000000000000".to_string();
+
+        let mut params = InitParams {
+            nft_limit: 3,
+            mint_price: Amount::from_ccd(10),
+            beneficiaries: vec![
+                Beneficiary {
+                    account: ARTIST,
+                    share: 70,
+                },
+                Beneficiary {
+                    account: STUDIO,
+                    share: 20,
+                },
+            ],
+            ..default_init_params()
+        };
+
+        // the shares have to add up to 100
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+        assert!(init(&ctx, &mut state_builder, Amount::zero()).is_err());
+
+        params.beneficiaries[1].share = 30;
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+        host.set_self_balance(Amount::from_ccd(20));
+
+        let mut ctx_claim = TestReceiveContext::empty();
+        ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mint_params = ClaimNFTParams {
+            node: Address::Account(ACCOUNT_0),
+            node_string: account_0_string,
+            proof: vec![],
+            selected_token: index_token_id(0),
+            amount_of_tokens: 1,
+            tier: None,
+            round: None,
+            voucher: None,
+            max_claims: None,
+        };
+        let claim_parameter_bytes = to_bytes(&mint_params);
+        ctx_claim.set_parameter(&claim_parameter_bytes);
+        ctx_claim.set_sender(Address::Account(ACCOUNT_0));
+        let mut logger = TestLogger::init();
+
+        claim_nft(
+            &ctx_claim,
+            &mut host,
+            Amount::from_ccd(10),
+            &mut logger,
+            &crypto_primitives(),
+        )
+        .unwrap();
+
+        // the admin can not withdraw proceeds which are split
+        let withdraw_params = WithdrawParams {
+            amount: Amount::from_ccd(10),
+            to: ADMIN,
+        };
+        let withdraw_parameter_bytes = to_bytes(&withdraw_params);
+        let mut ctx_admin = TestReceiveContext::empty();
+        ctx_admin.set_owner(ADMIN);
+        ctx_admin.set_sender(Address::Account(ADMIN));
+        ctx_admin.set_parameter(&withdraw_parameter_bytes);
+        assert_eq!(
+            withdraw(&ctx_admin, &mut host, &mut logger),
+            Err(Error::PaymentSplitterActive)
+        );
+
+        let ctx_release = TestReceiveContext::empty();
+        release(&ctx_release, &mut host, &mut logger).unwrap();
+        assert_eq!(host.get_transfers_to(ARTIST), vec![Amount::from_ccd(7)]);
+        assert_eq!(host.get_transfers_to(STUDIO), vec![Amount::from_ccd(3)]);
+
+        // only what was collected since the last release is paid out
+        release(&ctx_release, &mut host, &mut logger).unwrap();
+        claim_nft(
+            &ctx_claim,
+            &mut host,
+            Amount::from_ccd(10),
+            &mut logger,
+            &crypto_primitives(),
+        )
+        .unwrap();
+        release(&ctx_release, &mut host, &mut logger).unwrap();
+        assert_eq!(
+            host.get_transfers_to(ARTIST),
+            vec![Amount::from_ccd(7), Amount::from_ccd(7)]
+        );
+        assert_eq!(
+            host.state().released.get(&STUDIO).map(|released| *released),
+            Some(Amount::from_ccd(6))
+        );
+    }
+
+    #[concordium_test]
+    fn test_allocation_leaves() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
+        let account_0_string = "00000000000000000000000000000000000000000000000000".to_string();
+        let account_1_string = "11111111111111111111111111111111111111111111111111".to_string();
+        let allocation_0 = format!("{}:2", account_0_string);
+        let allocation_1 = format!("{}:1", account_1_string);
+
+        let params = InitParams {
+            nft_limit: 10,
+            whitelist: vec![allocation_0.clone(), allocation_1.clone()],
+            ..default_init_params()
+        };
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+        let mut logger = TestLogger::init();
+
+        let leaf_0 = leaf(&allocation_0);
+        let proof = vec![leaf_0.clone(), parent(&leaf_0, &leaf(&allocation_1))];
+
+        let mut claim = |max_claims: Option<u32>, amount_of_tokens: u32| {
+            let mint_params = ClaimNFTParams {
+                node: Address::Account(ACCOUNT_0),
+                node_string: account_0_string.clone(),
+                proof: proof.clone(),
+                selected_token: index_token_id(0),
+                amount_of_tokens,
+                tier: None,
+                round: None,
+                voucher: None,
+                max_claims,
+            };
+            let claim_parameter_bytes = to_bytes(&mint_params);
+            let mut ctx_claim = TestReceiveContext::empty();
+            ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+            ctx_claim.set_parameter(&claim_parameter_bytes);
+            ctx_claim.set_sender(Address::Account(ACCOUNT_0));
+            claim_nft(
+                &ctx_claim,
+                &mut host,
+                Amount::zero(),
+                &mut logger,
+                &crypto_primitives(),
+            )
+        };
+
+        // the allocation has to match the one in the leaf
+        assert_eq!(claim(None, 1), Err(Error::AddressNotOnWhitelist));
+        assert_eq!(claim(Some(5), 1), Err(Error::AddressNotOnWhitelist));
+
+        assert_eq!(claim(Some(2), 3), Err(Error::WalletLimitReached));
+        assert!(claim(Some(2), 1).is_ok());
+        assert!(claim(Some(2), 1).is_ok());
+        assert_eq!(claim(Some(2), 1), Err(Error::WalletLimitReached));
+    }
+
+    #[concordium_test]
+    fn test_airdrop_token() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
+        const PROJECT_TOKEN: ContractAddress = ContractAddress {
+            index: 7,
+            subindex: 0,
+        };
+        let account_0_string = "00000000000000000000000000000000000000000000000000".to_string();
+        let account_1_string = "11111111111111111111111111111111111111111111111111".to_string();
+        let allocation_0 = format!("{}:2", account_0_string);
+        let allocation_1 = format!("{}:1", account_1_string);
+
+        let params = InitParams {
+            nft_limit: 10,
+            whitelist: vec![allocation_0.clone(), allocation_1.clone()],
+            airdrop_token: Some(AirdropToken {
+                contract: PROJECT_TOKEN,
+                token_id: TokenIdVec(vec![1]),
+                unit: TokenAmountU64(1_000),
+            }),
+            ..default_init_params()
+        };
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+        let mut logger = TestLogger::init();
+
+        // only the airdropped token can be deposited
+        let deposit = |sender: Address, token_id: u8| {
+            let deposit_params = OnReceivingCis2Params {
+                token_id: TokenIdVec(vec![token_id]),
+                amount: TokenAmountU64(3_000),
+                from: Address::Account(AccountAddress([9u8; 32])),
+                data: AdditionalData::empty(),
+            };
+            let deposit_parameter_bytes = to_bytes(&deposit_params);
+            let mut ctx_deposit = TestReceiveContext::empty();
+            ctx_deposit.set_parameter(&deposit_parameter_bytes);
+            ctx_deposit.set_sender(sender);
+            on_receiving_cis2(&ctx_deposit, &host)
+        };
+        assert!(deposit(Address::Contract(PROJECT_TOKEN), 1).is_ok());
+        assert_eq!(
+            deposit(Address::Contract(PROJECT_TOKEN), 2),
+            Err(Error::Unauthorized)
+        );
+        assert_eq!(
+            deposit(Address::Account(ACCOUNT_0), 1),
+            Err(Error::Unauthorized)
+        );
+
+        let transfer = OwnedEntrypointName::new_unchecked("transfer".to_string());
+        host.setup_mock_entrypoint(PROJECT_TOKEN, transfer.clone(), MockFn::returning_ok(()));
+
+        let leaf_0 = leaf(&allocation_0);
+        let proof = vec![leaf_0.clone(), parent(&leaf_0, &leaf(&allocation_1))];
+        let claim = |host: &mut TestHost<State<TestStateApi>>,
+                     logger: &mut TestLogger,
+                     max_claims: Option<u32>,
+                     amount_of_tokens: u32| {
+            let mint_params = ClaimNFTParams {
+                node: Address::Account(ACCOUNT_0),
+                node_string: account_0_string.clone(),
+                proof: proof.clone(),
+                selected_token: index_token_id(0),
+                amount_of_tokens,
+                tier: None,
+                round: None,
+                voucher: None,
+                max_claims,
+            };
+            let claim_parameter_bytes = to_bytes(&mint_params);
+            let mut ctx_claim = TestReceiveContext::empty();
+            ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+            ctx_claim.set_parameter(&claim_parameter_bytes);
+            ctx_claim.set_sender(Address::Account(ACCOUNT_0));
+            ctx_claim.set_self_address(ContractAddress {
+                index: 1,
+                subindex: 0,
+            });
+            claim_nft(
+                &ctx_claim,
+                host,
+                Amount::zero(),
+                logger,
+                &crypto_primitives(),
+            )
+        };
+
+        // the allocation comes from the leaf, so the claim needs a proof of it
+        assert_eq!(
+            claim(&mut host, &mut logger, None, 1),
+            Err(Error::AddressNotOnWhitelist)
+        );
+        assert_eq!(
+            claim(&mut host, &mut logger, Some(2), 3),
+            Err(Error::WalletLimitReached)
+        );
+
+        // no NFT is minted, the units claimed are sent in the airdropped token
+        assert_eq!(claim(&mut host, &mut logger, Some(2), 1), Ok(()));
+        assert_eq!(host.state().claimed_count(), 0);
+        assert_eq!(
+            *host
+                .state()
+                .claimed_nfts
+                .get(&Address::Account(ACCOUNT_0))
+                .unwrap(),
+            1
+        );
+        assert_eq!(
+            logger.logs.last(),
+            Some(&to_bytes(&AirdropEvent::TokensClaimed(
+                TokensClaimedEvent {
+                    claimer: Address::Account(ACCOUNT_0),
+                    units: 1,
+                    amount: TokenAmountU64(1_000),
+                    slot_time: Timestamp::from_timestamp_millis(1),
+                    claim_number: 1,
+                }
+            )))
+        );
+
+        // the claim fails when the token contract rejects the transfer, such as
+        // when too little of the token was deposited
+        host.setup_mock_entrypoint(
+            PROJECT_TOKEN,
+            transfer,
+            MockFn::returning_err::<()>(CallContractError::Trap),
+        );
+        assert_eq!(
+            claim(&mut host, &mut logger, Some(2), 1),
+            Err(Error::AirdropTransferFailed)
+        );
+    }
+
+    #[concordium_test]
+    fn test_ccd_airdrop() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        const ADMIN: AccountAddress = AccountAddress([9u8; 32]);
+        const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
+        let account_0_string = "00000000000000000000000000000000000000000000000000".to_string();
+        let account_1_string = "11111111111111111111111111111111111111111111111111".to_string();
+        let allocation_0 = format!("{}:2", account_0_string);
+        let allocation_1 = format!("{}:1", account_1_string);
+
+        let mut params = InitParams {
+            nft_limit: 10,
+            nft_time_limit: 10,
+            whitelist: vec![allocation_0.clone(), allocation_1.clone()],
+            ..default_init_params()
+        };
+
+        // CCD can only be sent along in CCD airdrop mode
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+        assert!(init(&ctx, &mut state_builder, Amount::from_ccd(3)).is_err());
+
+        params.ccd_unit = Amount::from_ccd(1);
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state = init(&ctx, &mut state_builder, Amount::from_ccd(2)).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+        let mut logger = TestLogger::init();
+        host.set_self_balance(Amount::from_ccd(3));
+
+        let mut ctx_fund = TestReceiveContext::empty();
+        ctx_fund.set_sender(Address::Account(ADMIN));
+        fund_ccd_airdrop(&ctx_fund, &mut host, Amount::from_ccd(1)).unwrap();
+        assert_eq!(host.state().ccd_pool, Amount::from_ccd(3));
+
+        let leaf_0 = leaf(&allocation_0);
+        let proof = vec![leaf_0.clone(), parent(&leaf_0, &leaf(&allocation_1))];
+        let mint_params = ClaimNFTParams {
+            node: Address::Account(ACCOUNT_0),
+            node_string: account_0_string,
+            proof,
+            selected_token: index_token_id(0),
+            amount_of_tokens: 2,
+            tier: None,
+            round: None,
+            voucher: None,
+            max_claims: Some(2),
+        };
+        let claim_parameter_bytes = to_bytes(&mint_params);
+        let mut ctx_claim = TestReceiveContext::empty();
+        ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        ctx_claim.set_parameter(&claim_parameter_bytes);
+        ctx_claim.set_sender(Address::Account(ACCOUNT_0));
+        claim_nft(
+            &ctx_claim,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        )
+        .unwrap();
+
+        // the allocation of 2 units is sent as 2 CCD, and no NFT is minted
+        assert!(host.transfer_occurred(&ACCOUNT_0, Amount::from_ccd(2)));
+        assert_eq!(host.state().ccd_pool, Amount::from_ccd(1));
+        assert_eq!(host.state().claimed_count(), 0);
+        assert_eq!(
+            logger.logs.last(),
+            Some(&to_bytes(&AirdropEvent::CcdClaimed(CcdClaimedEvent {
+                claimer: ACCOUNT_0,
+                units: 2,
+                amount: Amount::from_ccd(2),
+                slot_time: Timestamp::from_timestamp_millis(1),
+                claim_number: 1,
+            })))
+        );
+
+        // the funds left for claims can't be withdrawn as proceeds
+        host.set_self_balance(Amount::from_ccd(1));
+        let withdraw_params = WithdrawParams {
+            to: ADMIN,
+            amount: Amount::from_ccd(1),
+        };
+        let withdraw_parameter_bytes = to_bytes(&withdraw_params);
+        let mut ctx_withdraw = TestReceiveContext::empty();
+        ctx_withdraw.set_owner(ADMIN);
+        ctx_withdraw.set_sender(Address::Account(ADMIN));
+        ctx_withdraw.set_parameter(&withdraw_parameter_bytes);
+        assert_eq!(
+            withdraw(&ctx_withdraw, &mut host, &mut logger),
+            Err(Error::InsufficientFunds)
+        );
+
+        // what is left unclaimed can be swept once the deadline has passed
+        let mut ctx_sweep = TestReceiveContext::empty();
+        ctx_sweep.set_owner(ADMIN);
+        ctx_sweep.set_sender(Address::Account(ADMIN));
+        ctx_sweep.set_metadata_slot_time(Timestamp::from_timestamp_millis(10));
+        assert_eq!(
+            sweep_ccd_airdrop(&ctx_sweep, &mut host, &mut logger),
+            Err(Error::AirdropStillOpen)
+        );
+        ctx_sweep.set_metadata_slot_time(Timestamp::from_timestamp_millis(11));
+        sweep_ccd_airdrop(&ctx_sweep, &mut host, &mut logger).unwrap();
+        assert!(host.transfer_occurred(&ADMIN, Amount::from_ccd(1)));
+        assert_eq!(host.state().ccd_pool, Amount::zero());
+    }
+
+    #[concordium_test]
+    fn test_multisig_sweep() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        const OWNER: AccountAddress = AccountAddress([9u8; 32]);
+        const ADMIN_0: AccountAddress = AccountAddress([0u8; 32]);
+        const ADMIN_1: AccountAddress = AccountAddress([1u8; 32]);
+        const BENEFICIARY: AccountAddress = AccountAddress([8u8; 32]);
+        let account_0_string = "00000000000000000000000000000000000000000000000000".to_string();
+
+        let params = InitParams {
+            nft_limit: 10,
+            nft_time_limit: 10,
+            whitelist: vec![format!("{}:2", account_0_string)],
+            ccd_unit: Amount::from_ccd(1),
+            admins: vec![ADMIN_0, ADMIN_1],
+            admin_threshold: 2,
+            ..default_init_params()
+        };
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state = init(&ctx, &mut state_builder, Amount::from_ccd(2)).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+        host.set_self_balance(Amount::from_ccd(2));
+        let mut logger = TestLogger::init();
+
+        // The owner can no longer sweep the pool alone
+        let mut ctx_sweep = TestReceiveContext::empty();
+        ctx_sweep.set_owner(OWNER);
+        ctx_sweep.set_sender(Address::Account(OWNER));
+        ctx_sweep.set_metadata_slot_time(Timestamp::from_timestamp_millis(11));
+        assert_eq!(
+            sweep_ccd_airdrop(&ctx_sweep, &mut host, &mut logger),
+            Err(Error::MultisigRequired)
+        );
+
+        let propose_bytes = to_bytes(&AdminAction::SweepCcdAirdrop(BENEFICIARY));
+        let mut ctx_propose = TestReceiveContext::empty();
+        ctx_propose.set_sender(Address::Account(ADMIN_0));
+        ctx_propose.set_parameter(&propose_bytes);
+        propose_action(&ctx_propose, &mut host, &mut logger).unwrap();
+        assert_eq!(host.state().ccd_pool, Amount::from_ccd(2));
+
+        // The pool is swept to the proposed account once enough admins approve
+        let confirm_bytes = to_bytes(&0u64);
+        let mut ctx_confirm = TestReceiveContext::empty();
+        ctx_confirm.set_sender(Address::Account(ADMIN_1));
+        ctx_confirm.set_parameter(&confirm_bytes);
+        ctx_confirm.set_metadata_slot_time(Timestamp::from_timestamp_millis(11));
+        confirm_action(&ctx_confirm, &mut host, &mut logger).unwrap();
+        assert!(host.transfer_occurred(&BENEFICIARY, Amount::from_ccd(2)));
+        assert_eq!(host.state().ccd_pool, Amount::zero());
+        assert_eq!(
+            logger.logs.last(),
+            Some(&to_bytes(&AirdropEvent::Withdraw(WithdrawEvent {
+                amount: Amount::from_ccd(2),
+                to: BENEFICIARY,
+            })))
+        );
+    }
+
+    #[concordium_test]
+    fn test_snapshot() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
+        const ACCOUNT_1: AccountAddress = AccountAddress([1u8; 32]);
+        let account_0_string = "00000000000000000000000000000000000000000000000000".to_string();
+        let account_1_string = "11111111111111111111111111111111111111111111111111".to_string();
+
+        let params = InitParams {
+            nft_limit: 10,
+            ..default_init_params()
+        };
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+        let mut logger = TestLogger::init();
+
+        for (node, node_string, amount_of_tokens) in [
+            (ACCOUNT_0, account_0_string, 2),
+            (ACCOUNT_1, account_1_string, 1),
+        ] {
+            let mint_params = ClaimNFTParams {
+                node: Address::Account(node),
+                node_string,
+                proof: vec![],
+                selected_token: index_token_id(0),
+                amount_of_tokens,
+                tier: None,
+                round: None,
+                voucher: None,
+                max_claims: None,
+            };
+            let claim_parameter_bytes = to_bytes(&mint_params);
+            let mut ctx_claim = TestReceiveContext::empty();
+            ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+            ctx_claim.set_parameter(&claim_parameter_bytes);
+            ctx_claim.set_sender(Address::Account(node));
+            claim_nft(
+                &ctx_claim,
+                &mut host,
+                Amount::zero(),
+                &mut logger,
+                &crypto_primitives(),
+            )
+            .unwrap();
+        }
+
+        let mut ctx_view = TestReceiveContext::empty();
+        ctx_view.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let view_parameter_bytes = to_bytes(&SnapshotParams {
+            from_token: None,
+            offset: 0,
+            limit: 1,
+        });
+        ctx_view.set_parameter(&view_parameter_bytes);
+        let first_page = snapshot(&ctx_view, &host).unwrap();
+
+        assert_eq!(first_page.version, STATE_VERSION);
+        assert_eq!(first_page.config.nft_limit, 10);
+        assert_eq!(first_page.next_token_id, 3);
+        assert_eq!(first_page.claim_count, 2);
+        assert_eq!(
+            first_page.token_owners,
+            vec![(index_token_id(0), Address::Account(ACCOUNT_0))]
+        );
+        assert_eq!(first_page.next_token, Some(index_token_id(1)));
+        assert_eq!(first_page.claims, vec![(Address::Account(ACCOUNT_0), 2)]);
+        assert!(first_page.has_more);
+
+        let view_parameter_bytes = to_bytes(&SnapshotParams {
+            from_token: first_page.next_token,
+            offset: 1,
+            limit: 2,
+        });
+        ctx_view.set_parameter(&view_parameter_bytes);
+        let second_page = snapshot(&ctx_view, &host).unwrap();
+
+        assert_eq!(
+            second_page.token_owners,
+            vec![
+                (index_token_id(1), Address::Account(ACCOUNT_0)),
+                (index_token_id(2), Address::Account(ACCOUNT_1)),
+            ]
+        );
+        // the token IDs up to nft_limit are still to be looked up
+        assert_eq!(second_page.next_token, Some(index_token_id(3)));
+        assert_eq!(second_page.claims, vec![(Address::Account(ACCOUNT_1), 1)]);
+        assert!(second_page.edition_balances.is_empty());
+        assert!(!second_page.has_more);
+
+        let view_parameter_bytes = to_bytes(&SnapshotParams {
+            from_token: second_page.next_token,
+            offset: 2,
+            limit: 10,
+        });
+        ctx_view.set_parameter(&view_parameter_bytes);
+        let last_page = snapshot(&ctx_view, &host).unwrap();
+        assert!(last_page.token_owners.is_empty());
+        assert_eq!(last_page.next_token, None);
+        assert!(last_page.claims.is_empty());
+    }
+
+    #[concordium_test]
+    fn test_marketplace_approval() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
+        let account_0_string = "00000000000000000000000000000000000000000000000000".to_string();
+        let marketplace = ContractAddress {
+            index: 5,
+            subindex: 0,
+        };
+
+        let params = InitParams {
+            nft_limit: 10,
+            marketplace: Some(marketplace),
+            ..default_init_params()
+        };
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+
+        let mint_params = ClaimNFTParams {
+            node: Address::Account(ACCOUNT_0),
+            node_string: account_0_string,
+            proof: vec![],
+            selected_token: index_token_id(0),
+            amount_of_tokens: 1,
+            tier: None,
+            round: None,
+            voucher: None,
+            max_claims: None,
+        };
+        let claim_parameter_bytes = to_bytes(&mint_params);
+        let mut ctx_claim = TestReceiveContext::empty();
+        ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        ctx_claim.set_parameter(&claim_parameter_bytes);
+        ctx_claim.set_sender(Address::Account(ACCOUNT_0));
+
+        let approval = to_bytes(
+            &Cis2Event::<ContractTokenId, ContractTokenAmount>::UpdateOperator(
+                UpdateOperatorEvent {
+                    owner: Address::Account(ACCOUNT_0),
+                    operator: Address::Contract(marketplace),
+                    update: OperatorUpdate::Add,
+                },
+            ),
+        );
+
+        let mut approvals = Vec::new();
+        for _ in 0..2 {
+            let mut logger = TestLogger::init();
+            claim_nft(
+                &ctx_claim,
+                &mut host,
+                Amount::zero(),
+                &mut logger,
+                &crypto_primitives(),
+            )
+            .unwrap();
+            approvals.push(logger.logs.iter().filter(|log| **log == approval).count());
+        }
+        // the approval is only logged the first time
+        assert_eq!(approvals, vec![1, 0]);
+
+        assert!(host
+            .state()
+            .operators
+            .contains(&(Address::Account(ACCOUNT_0), Address::Contract(marketplace))));
+    }
+
+    #[concordium_test]
+    fn test_is_whitelisted() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
+        const ACCOUNT_1: AccountAddress = AccountAddress([1u8; 32]);
+        const ACCOUNT_2: AccountAddress = AccountAddress([2u8; 32]);
+
+        let params = InitParams {
+            nft_limit: 10,
+            whitelist: vec![
+                account_address_to_string(ACCOUNT_0),
+                account_address_to_string(ACCOUNT_1),
+            ],
+            ..default_init_params()
+        };
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let host = TestHost::new(state, state_builder);
+
+        for (account, expected) in [(ACCOUNT_0, true), (ACCOUNT_1, true), (ACCOUNT_2, false)] {
+            let view_parameter_bytes = to_bytes(&account);
+            let mut ctx_view = TestReceiveContext::empty();
+            ctx_view.set_parameter(&view_parameter_bytes);
+            assert_eq!(is_whitelisted(&ctx_view, &host), Ok(expected));
+        }
+    }
+
+    #[concordium_test]
+    fn test_max_per_tx() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
+        let account_0_string = "00000000000000000000000000000000000000000000000000".to_string();
+
+        let params = InitParams {
+            nft_limit: 10,
+            max_per_tx: 2,
+            ..default_init_params()
+        };
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+        let mut logger = TestLogger::init();
+
+        let mut claim = |amount_of_tokens: u32| {
+            let mint_params = ClaimNFTParams {
+                node: Address::Account(ACCOUNT_0),
+                node_string: account_0_string.clone(),
+                proof: vec![],
+                selected_token: index_token_id(0),
+                amount_of_tokens,
+                tier: None,
+                round: None,
+                voucher: None,
+                max_claims: None,
+            };
+            let claim_parameter_bytes = to_bytes(&mint_params);
+            let mut ctx_claim = TestReceiveContext::empty();
+            ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+            ctx_claim.set_parameter(&claim_parameter_bytes);
+            ctx_claim.set_sender(Address::Account(ACCOUNT_0));
+            claim_nft(
+                &ctx_claim,
+                &mut host,
+                Amount::zero(),
+                &mut logger,
+                &crypto_primitives(),
+            )
+        };
+
+        assert_eq!(claim(3), Err(Error::TransactionLimitReached));
+        assert!(claim(2).is_ok());
+        // the cap is per transaction, not per address
+        assert!(claim(2).is_ok());
+    }
+
+    #[concordium_test]
+    fn test_sold_out_once() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        const ADMIN: AccountAddress = AccountAddress([9u8; 32]);
+        const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
+        let account_0_string = "00000000000000000000000000000000000000000000000000".to_string();
+
+        let params = InitParams {
+            nft_limit: 1,
+            selected_index: true,
+            ..default_init_params()
+        };
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+        let mut logger = TestLogger::init();
+
+        let mut mint_params = ClaimNFTParams {
+            node: Address::Account(ACCOUNT_0),
+            node_string: account_0_string,
+            proof: vec![],
+            selected_token: index_token_id(0),
+            amount_of_tokens: 1,
+            tier: None,
+            round: None,
+            voucher: None,
+            max_claims: None,
+        };
+        let claim_parameter_bytes = to_bytes(&mint_params);
+        let mut ctx_claim = TestReceiveContext::empty();
+        ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        ctx_claim.set_parameter(&claim_parameter_bytes);
+        ctx_claim.set_sender(Address::Account(ACCOUNT_0));
+        claim_nft(
+            &ctx_claim,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        )
+        .unwrap();
+
+        let sold_out_events = |logger: &TestLogger| {
+            logger
+                .logs
+                .iter()
+                .filter(|log| {
+                    log.first()
+                        == to_bytes(&AirdropEvent::SoldOut(SoldOutEvent {
+                            claim_count: 0,
+                            slot_time: Timestamp::from_timestamp_millis(0),
+                        }))
+                        .first()
+                })
+                .count()
+        };
+        assert_eq!(sold_out_events(&logger), 1);
+
+        // claiming the burned token again does not log a second SoldOut
+        let burn_parameter_bytes = to_bytes(&TokenParam {
+            token: index_token_id(0),
+        });
+        let mut ctx_burn = TestReceiveContext::empty();
+        ctx_burn.set_parameter(&burn_parameter_bytes);
+        ctx_burn.set_sender(Address::Account(ACCOUNT_0));
+        burn(&ctx_burn, &mut host, &mut logger).unwrap();
+        claim_nft(
+            &ctx_claim,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        )
+        .unwrap();
+        assert_eq!(host.state().claimed_count(), 1);
+        assert_eq!(sold_out_events(&logger), 1);
+
+        // a raised cap sells out again
+        let limit_parameter_bytes = to_bytes(&SetNftLimitParams { nft_limit: 2 });
+        let mut ctx_limit = TestReceiveContext::empty();
+        ctx_limit.set_owner(ADMIN);
+        ctx_limit.set_sender(Address::Account(ADMIN));
+        ctx_limit.set_parameter(&limit_parameter_bytes);
+        set_nft_limit(&ctx_limit, &mut host, &mut logger).unwrap();
+        mint_params.selected_token = index_token_id(1);
+        let claim_parameter_bytes = to_bytes(&mint_params);
+        ctx_claim.set_parameter(&claim_parameter_bytes);
+        claim_nft(
+            &ctx_claim,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        )
+        .unwrap();
+        assert_eq!(sold_out_events(&logger), 2);
+    }
+
+    #[concordium_test]
+    fn test_waitlist() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
+        const ACCOUNT_1: AccountAddress = AccountAddress([1u8; 32]);
+        const ACCOUNT_2: AccountAddress = AccountAddress([2u8; 32]);
+        let account_0_string = "00000000000000000000000000000000000000000000000000".to_string();
+
+        let params = InitParams {
+            nft_limit: 1,
+            selected_index: true,
+            ..default_init_params()
+        };
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+        let mut logger = TestLogger::init();
+
+        let mut ctx_join = TestReceiveContext::empty();
+        ctx_join.set_sender(Address::Account(ACCOUNT_1));
+        assert_eq!(
+            join_waitlist(&ctx_join, &mut host),
+            Err(Error::WaitlistNotOpen)
+        );
+
+        let mint_params = ClaimNFTParams {
+            node: Address::Account(ACCOUNT_0),
+            node_string: account_0_string,
+            proof: vec![],
+            selected_token: index_token_id(0),
+            amount_of_tokens: 1,
+            tier: None,
+            round: None,
+            voucher: None,
+            max_claims: None,
+        };
+        let claim_parameter_bytes = to_bytes(&mint_params);
+        let mut ctx_claim = TestReceiveContext::empty();
+        ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        ctx_claim.set_parameter(&claim_parameter_bytes);
+        ctx_claim.set_sender(Address::Account(ACCOUNT_0));
+        claim_nft(
+            &ctx_claim,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        )
+        .unwrap();
+
+        // the drop has sold out, so the waitlist is open
+        join_waitlist(&ctx_join, &mut host).unwrap();
+        assert_eq!(
+            join_waitlist(&ctx_join, &mut host),
+            Err(Error::AlreadyOnWaitlist)
+        );
+        ctx_join.set_sender(Address::Account(ACCOUNT_2));
+        join_waitlist(&ctx_join, &mut host).unwrap();
+
+        let burn_parameter_bytes = to_bytes(&TokenParam {
+            token: index_token_id(0),
+        });
+        let mut ctx_burn = TestReceiveContext::empty();
+        ctx_burn.set_parameter(&burn_parameter_bytes);
+        ctx_burn.set_sender(Address::Account(ACCOUNT_0));
+        burn(&ctx_burn, &mut host, &mut logger).unwrap();
+
+        // the freed token goes to the waitlist in the order it was joined
+        assert_eq!(
+            claim_nft(
+                &ctx_claim,
+                &mut host,
+                Amount::zero(),
+                &mut logger,
+                &crypto_primitives(),
+            ),
+            Err(Error::WaitlistActive)
+        );
+        let token_parameter_bytes = to_bytes(&WaitlistClaimParams {
+            proof: vec![],
+            node_string: String::new(),
+            selected_token: index_token_id(0),
+            max_claims: None,
+        });
+        let mut ctx_waitlist = TestReceiveContext::empty();
+        ctx_waitlist.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        ctx_waitlist.set_parameter(&token_parameter_bytes);
+        ctx_waitlist.set_sender(Address::Account(ACCOUNT_2));
+        assert_eq!(
+            claim_from_waitlist(
+                &ctx_waitlist,
+                &mut host,
+                Amount::zero(),
+                &mut logger,
+                &crypto_primitives(),
+            ),
+            Err(Error::NotFirstOnWaitlist)
+        );
+        ctx_waitlist.set_sender(Address::Account(ACCOUNT_1));
+        claim_from_waitlist(
+            &ctx_waitlist,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            host.state()
+                .token_owners
+                .get(&index_token_id(0))
+                .map(|owner| *owner),
+            Some(Address::Account(ACCOUNT_1))
+        );
+        // the drop sold out once, and the waitlist claim does not log it again
+        let sold_out_tag = to_bytes(&AirdropEvent::SoldOut(SoldOutEvent {
+            claim_count: 0,
+            slot_time: Timestamp::from_timestamp_millis(0),
+        }))[0];
+        assert_eq!(
+            logger
+                .logs
+                .iter()
+                .filter(|log| log[0] == sold_out_tag)
+                .count(),
+            1
+        );
+        assert_eq!(
+            claim_from_waitlist(
+                &ctx_waitlist,
+                &mut host,
+                Amount::zero(),
+                &mut logger,
+                &crypto_primitives(),
+            ),
+            Err(Error::NotFirstOnWaitlist)
+        );
+    }
+
+    #[concordium_test]
+    fn test_waitlist_sequential() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        const ADMIN: AccountAddress = AccountAddress([9u8; 32]);
+        const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
+        const ACCOUNT_1: AccountAddress = AccountAddress([1u8; 32]);
+        let account_0_string = "00000000000000000000000000000000000000000000000000".to_string();
+
+        let params = InitParams {
+            nft_limit: 2,
+            one_claim_per_account: true,
+            unlock_time: 10,
+            ..default_init_params()
+        };
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+        let mut logger = TestLogger::init();
+
+        let mint_params = ClaimNFTParams {
+            node: Address::Account(ACCOUNT_0),
+            node_string: account_0_string,
+            proof: vec![],
+            selected_token: index_token_id(0),
+            amount_of_tokens: 2,
+            tier: None,
+            round: None,
+            voucher: None,
+            max_claims: None,
+        };
+        let claim_parameter_bytes = to_bytes(&mint_params);
+        let mut ctx_claim = TestReceiveContext::empty();
+        ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        ctx_claim.set_parameter(&claim_parameter_bytes);
+        ctx_claim.set_sender(Address::Account(ACCOUNT_0));
+        claim_nft(
+            &ctx_claim,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        )
+        .unwrap();
+
+        let mut ctx_join = TestReceiveContext::empty();
+        for account in [ACCOUNT_0, ACCOUNT_1] {
+            ctx_join.set_sender(Address::Account(account));
+            join_waitlist(&ctx_join, &mut host).unwrap();
+        }
+
+        // burning a sequential token frees its ID, though next_token_id stays put
+        let burn_parameter_bytes = to_bytes(&TokenParam {
+            token: index_token_id(1),
+        });
+        let mut ctx_burn = TestReceiveContext::empty();
+        ctx_burn.set_parameter(&burn_parameter_bytes);
+        ctx_burn.set_sender(Address::Account(ACCOUNT_0));
+        burn(&ctx_burn, &mut host, &mut logger).unwrap();
+        assert_eq!(host.state().next_token_id, 2);
+        assert_eq!(host.state().freed_count, 1);
+        assert!(host
+            .state()
+            .token_unlock_times
+            .get(&index_token_id(1))
+            .is_none());
+
+        // the waitlist is held to the same limits as claim_nft
+        let token_parameter_bytes = to_bytes(&WaitlistClaimParams {
+            proof: vec![],
+            node_string: String::new(),
+            selected_token: index_token_id(0),
+            max_claims: None,
+        });
+        let mut ctx_waitlist = TestReceiveContext::empty();
+        ctx_waitlist.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        ctx_waitlist.set_parameter(&token_parameter_bytes);
+        ctx_waitlist.set_sender(Address::Account(ACCOUNT_0));
+        assert_eq!(
+            claim_from_waitlist(
+                &ctx_waitlist,
+                &mut host,
+                Amount::zero(),
+                &mut logger,
+                &crypto_primitives(),
+            ),
+            Err(Error::AlreadyClaimed)
+        );
+
+        // the owner can skip a head of the queue which can't or won't claim
+        let mut ctx_skip = TestReceiveContext::empty();
+        ctx_skip.set_owner(ADMIN);
+        ctx_skip.set_sender(Address::Account(ACCOUNT_1));
+        assert_eq!(
+            skip_waitlist(&ctx_skip, &mut host),
+            Err(Error::Unauthorized)
+        );
+        ctx_skip.set_sender(Address::Account(ADMIN));
+        skip_waitlist(&ctx_skip, &mut host).unwrap();
+        assert!(!host
+            .state()
+            .waitlisted
+            .contains(&Address::Account(ACCOUNT_0)));
+
+        ctx_waitlist.set_sender(Address::Account(ACCOUNT_1));
+        claim_from_waitlist(
+            &ctx_waitlist,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        )
+        .unwrap();
+        assert_eq!(
+            host.state()
+                .token_owners
+                .get(&index_token_id(1))
+                .map(|owner| *owner),
+            Some(Address::Account(ACCOUNT_1))
+        );
+        assert_eq!(host.state().next_token_id, 2);
+        assert_eq!(host.state().freed_count, 0);
+        // tokens claimed from the waitlist are locked like any other claim
+        assert_eq!(
+            host.state()
+                .token_unlock_times
+                .get(&index_token_id(1))
+                .map(|unlock_time| *unlock_time),
+            Some(Timestamp::from_timestamp_millis(10))
+        );
+
+        assert_eq!(
+            skip_waitlist(&ctx_skip, &mut host),
+            Err(Error::WaitlistEmpty)
+        );
+    }
+
+    #[concordium_test]
+    fn test_waitlist_whitelist() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        const ADMIN: AccountAddress = AccountAddress([9u8; 32]);
+        const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
+        const ACCOUNT_1: AccountAddress = AccountAddress([1u8; 32]);
+        const ACCOUNT_2: AccountAddress = AccountAddress([2u8; 32]);
+        let account_0_string = "00000000000000000000000000000000000000000000000000".to_string();
+        let account_1_string = "11111111111111111111111111111111111111111111111111".to_string();
+        let account_2_string = "22222222222222222222222222222222222222222222222222".to_string();
+
+        let params = InitParams {
+            nft_limit: 1,
+            whitelist: vec![account_0_string.clone(), account_1_string.clone()],
+            ..default_init_params()
+        };
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+        let mut logger = TestLogger::init();
+
+        let mint_params = ClaimNFTParams {
+            node: Address::Account(ACCOUNT_0),
+            node_string: account_0_string.clone(),
+            proof: host
+                .state()
+                .get_hash_proof(leaf(&account_0_string))
+                .unwrap(),
+            selected_token: index_token_id(0),
+            amount_of_tokens: 1,
+            tier: None,
+            round: None,
+            voucher: None,
+            max_claims: None,
+        };
+        let claim_parameter_bytes = to_bytes(&mint_params);
+        let mut ctx_claim = TestReceiveContext::empty();
+        ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        ctx_claim.set_parameter(&claim_parameter_bytes);
+        ctx_claim.set_sender(Address::Account(ACCOUNT_0));
+        claim_nft(
+            &ctx_claim,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        )
+        .unwrap();
+
+        let mut ctx_join = TestReceiveContext::empty();
+        for account in [ACCOUNT_2, ACCOUNT_1] {
+            ctx_join.set_sender(Address::Account(account));
+            join_waitlist(&ctx_join, &mut host).unwrap();
+        }
 
-    match log_mint_result {
-        Ok(_) => (),
-        Err(error) => match error {
-            LogError::Full => {
-                return Err(Error::MintingLogFull);
-            }
-            LogError::Malformed => {
-                return Err(Error::MintingLogMalformed);
-            }
-        },
-    }
+        let burn_parameter_bytes = to_bytes(&TokenParam {
+            token: index_token_id(0),
+        });
+        let mut ctx_burn = TestReceiveContext::empty();
+        ctx_burn.set_parameter(&burn_parameter_bytes);
+        ctx_burn.set_sender(Address::Account(ACCOUNT_0));
+        burn(&ctx_burn, &mut host, &mut logger).unwrap();
 
-    let url: String = state.base_url.clone() + &token_id_to_use.to_string();
+        // the waitlist is no way around the whitelist
+        let mut waitlist_params = WaitlistClaimParams {
+            proof: vec![],
+            node_string: account_2_string,
+            selected_token: index_token_id(0),
+            max_claims: None,
+        };
+        let waitlist_parameter_bytes = to_bytes(&waitlist_params);
+        let mut ctx_waitlist = TestReceiveContext::empty();
+        ctx_waitlist.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        ctx_waitlist.set_parameter(&waitlist_parameter_bytes);
+        ctx_waitlist.set_sender(Address::Account(ACCOUNT_2));
+        assert_eq!(
+            claim_from_waitlist(
+                &ctx_waitlist,
+                &mut host,
+                Amount::zero(),
+                &mut logger,
+                &crypto_primitives(),
+            ),
+            Err(Error::AddressNotOnWhitelist)
+        );
 
-    // Metadata URL for the token.
-    let log_meta_result = logger.log(&Cis2Event::TokenMetadata::<_, ContractTokenAmount>(
-        TokenMetadataEvent {
-            token_id: token_id_to_use,
-            metadata_url: MetadataUrl { url, hash: None },
-        },
-    ));
+        let mut ctx_skip = TestReceiveContext::empty();
+        ctx_skip.set_owner(ADMIN);
+        ctx_skip.set_sender(Address::Account(ADMIN));
+        skip_waitlist(&ctx_skip, &mut host).unwrap();
 
-    match log_meta_result {
-        Ok(_) => (),
-        Err(error) => match error {
-            LogError::Full => {
-                return Err(Error::MetaDataLogFull);
-            }
-            LogError::Malformed => {
-                return Err(Error::MetaDataLogMalformed);
-            }
-        },
-    }
+        waitlist_params.node_string = account_1_string.clone();
+        let waitlist_parameter_bytes = to_bytes(&waitlist_params);
+        ctx_waitlist.set_parameter(&waitlist_parameter_bytes);
+        ctx_waitlist.set_sender(Address::Account(ACCOUNT_1));
+        assert_eq!(
+            claim_from_waitlist(
+                &ctx_waitlist,
+                &mut host,
+                Amount::zero(),
+                &mut logger,
+                &crypto_primitives(),
+            ),
+            Err(Error::AddressNotOnWhitelist)
+        );
 
-    if state.taken_indexes.is_some() {
-        state
-            .taken_indexes
-            .as_mut()
-            .unwrap()
-            .insert(token_id_to_use, params.node_string);
-    } else {
-        state.next_token_id += params.amount_of_tokens;
+        waitlist_params.proof = host
+            .state()
+            .get_hash_proof(leaf(&account_1_string))
+            .unwrap();
+        let waitlist_parameter_bytes = to_bytes(&waitlist_params);
+        ctx_waitlist.set_parameter(&waitlist_parameter_bytes);
+        claim_from_waitlist(
+            &ctx_waitlist,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        )
+        .unwrap();
+        assert_eq!(
+            host.state()
+                .token_owners
+                .get(&index_token_id(0))
+                .map(|owner| *owner),
+            Some(Address::Account(ACCOUNT_1))
+        );
     }
 
-    let mut tokens = state.claimed_nfts.entry(params.node).or_insert(0);
-    *tokens += amount_of_tokens;
+    #[concordium_test]
+    fn test_one_claim_per_account() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
 
-    Ok(())
-}
+        const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
+        const ACCOUNT_1: AccountAddress = AccountAddress([1u8; 32]);
 
-/// View function that returns the metadata, whitelist and number of NFTs
-#[receive(
-    contract = "airdrop_project",
-    name = "view",
-    return_value = "ViewResult"
-)]
-fn view<S: HasStateApi>(
-    _ctx: &impl HasReceiveContext,
-    host: &impl HasHost<State<S>, StateApiType = S>,
-) -> ReceiveResult<ViewResult> {
-    let state = host.state();
+        let params = InitParams {
+            nft_limit: 10,
+            one_claim_per_account: true,
+            ..default_init_params()
+        };
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
 
-    Ok(ViewResult {
-        metadata: state.metadata.clone(),
-        whitelist: state.whitelist.clone(),
-        number_of_nfts: state.nft_limit,
-    })
-}
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+        let mut logger = TestLogger::init();
 
-/// View function that returns the amount of tokens claimed by the address
-#[receive(
-    contract = "airdrop_project",
-    name = "balance_of",
-    parameter = "BalanceParam",
-    return_value = "u32"
-)]
-fn balance_of<S: HasStateApi>(
-    ctx: &impl HasReceiveContext,
-    host: &impl HasHost<State<S>, StateApiType = S>,
-) -> ReceiveResult<u32> {
-    let state: &State<S> = host.state();
-    let params: BalanceParam = ctx.parameter_cursor().get()?;
+        let mut claim = |account: AccountAddress| {
+            let mint_params = ClaimNFTParams {
+                node: Address::Account(account),
+                node_string: account_address_to_string(account),
+                proof: vec![],
+                selected_token: index_token_id(0),
+                amount_of_tokens: 2,
+                tier: None,
+                round: None,
+                voucher: None,
+                max_claims: None,
+            };
+            let claim_parameter_bytes = to_bytes(&mint_params);
+            let mut ctx_claim = TestReceiveContext::empty();
+            ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+            ctx_claim.set_parameter(&claim_parameter_bytes);
+            ctx_claim.set_sender(Address::Account(account));
+            claim_nft(
+                &ctx_claim,
+                &mut host,
+                Amount::zero(),
+                &mut logger,
+                &crypto_primitives(),
+            )
+        };
 
-    let res = state.claimed_nfts.get(&params.node);
-    if res.is_none() {
-        Ok(0)
-    } else {
-        Ok(*state.claimed_nfts.get(&params.node).unwrap())
+        assert!(claim(ACCOUNT_0).is_ok());
+        assert_eq!(claim(ACCOUNT_0), Err(Error::AlreadyClaimed));
+        assert!(claim(ACCOUNT_1).is_ok());
     }
-}
-
-/// View function that returns the total supply of available NFTs
-#[receive(
-    contract = "airdrop_project",
-    name = "total_supply",
-    return_value = "u32"
-)]
-fn total_supply<S: HasStateApi>(
-    _ctx: &impl HasReceiveContext,
-    host: &impl HasHost<State<S>, StateApiType = S>,
-) -> ReceiveResult<u32> {
-    Ok(host.state().nft_limit)
-}
 
-/// View function that returns the current supply of available NFTs
-#[receive(
-    contract = "airdrop_project",
-    name = "current_supply",
-    return_value = "u32"
-)]
-fn current_supply<S: HasStateApi>(
-    _ctx: &impl HasReceiveContext,
-    host: &impl HasHost<State<S>, StateApiType = S>,
-) -> ReceiveResult<u32> {
-    let current_claimed = if host.state().taken_indexes.is_some() {
-        host.state().taken_indexes.as_ref().unwrap().len() as u32
-    } else {
-        host.state().next_token_id
-    };
+    #[concordium_test]
+    fn test_reserved_ranges() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
 
-    Ok(host.state().nft_limit - current_claimed)
-}
+        const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
+        const ACCOUNT_1: AccountAddress = AccountAddress([1u8; 32]);
+        let account_0_string = account_address_to_string(ACCOUNT_0);
+        let account_1_string = account_address_to_string(ACCOUNT_1);
 
-/// View function that returns the owner of tokens or None if no one owns it
-#[receive(
-    contract = "airdrop_project",
-    name = "check_owner",
-    parameter = "TokenParam",
-    return_value = "CheckOwnerReply"
-)]
-fn check_owner<S: HasStateApi>(
-    ctx: &impl HasReceiveContext,
-    host: &impl HasHost<State<S>, StateApiType = S>,
-) -> ReceiveResult<CheckOwnerReply> {
-    let params: TokenParam = ctx.parameter_cursor().get()?;
+        let mut params = InitParams {
+            nft_limit: 4,
+            whitelist: vec![account_0_string.clone(), account_1_string.clone()],
+            reserve: 1,
+            reserved_ranges: vec![IndexRange { start: 0, end: 1 }],
+            ..default_init_params()
+        };
 
-    if host.state().taken_indexes.as_ref().is_none() {
-        return Ok(CheckOwnerReply { address: None });
-    }
+        // reserved ranges need selected_index
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+        assert!(init(&ctx, &mut state_builder, Amount::zero()).is_err());
 
-    if !host
-        .state()
-        .taken_indexes
-        .as_ref()
-        .unwrap()
-        .contains_key(&params.token)
-    {
-        return Ok(CheckOwnerReply { address: None });
-    }
+        params.selected_index = true;
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+        let mut logger = TestLogger::init();
 
-    return Ok(CheckOwnerReply {
-        address: Some(
-            host.state()
-                .taken_indexes
-                .as_ref()
-                .unwrap()
-                .get(&params.token)
-                .unwrap()
-                .clone(),
-        ),
-    });
-}
+        let leaf_0 = leaf(&account_0_string);
+        let proof_0 = vec![leaf_0.clone(), parent(&leaf_0, &leaf(&account_1_string))];
+
+        let mut claim = |account: AccountAddress, proof: Vec<String>, index: u32| {
+            let mint_params = ClaimNFTParams {
+                node: Address::Account(account),
+                node_string: account_address_to_string(account),
+                proof,
+                selected_token: index_token_id(index),
+                amount_of_tokens: 1,
+                tier: None,
+                round: None,
+                voucher: None,
+                max_claims: None,
+            };
+            let claim_parameter_bytes = to_bytes(&mint_params);
+            let mut ctx_claim = TestReceiveContext::empty();
+            ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+            ctx_claim.set_parameter(&claim_parameter_bytes);
+            ctx_claim.set_sender(Address::Account(account));
+            claim_nft(
+                &ctx_claim,
+                &mut host,
+                Amount::zero(),
+                &mut logger,
+                &crypto_primitives(),
+            )
+        };
 
-#[concordium_cfg_test]
-mod tests {
-    use super::*;
-    use test_infrastructure::*;
+        // indexes outside the ranges are public
+        assert_eq!(
+            claim(ACCOUNT_1, vec![], 0),
+            Err(Error::AddressNotOnWhitelist)
+        );
+        assert!(claim(ACCOUNT_1, vec![], 2).is_ok());
+        assert!(claim(ACCOUNT_0, proof_0, 1).is_ok());
+    }
 
     #[concordium_test]
-    /// Test that initializing the contract succeeds with some state.
-    fn test_init() {
+    fn test_url_template() {
         let mut ctx = TestInitContext::empty();
         ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
         let mut state_builder = TestStateBuilder::new();
 
         let params = InitParams {
-            nft_limit: 0,
-            nft_time_limit: 0,
-            whitelist: vec![],
-            reserve: 0,
-            base_url: String::new(),
-            whitelist_file: String::new(),
-            metadata: String::new(),
-            selected_index: false,
-            nft_limit_per_address: 0,
+            nft_limit: 10,
+            base_url: "ipfs://CID/{id}.json".to_string(),
+            ..default_init_params()
         };
-
         let parameter_bytes = to_bytes(&params);
         ctx.set_parameter(&parameter_bytes);
 
-        let state_result = init(&ctx, &mut state_builder);
-        state_result.unwrap();
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+        assert_eq!(
+            host.state().metadata_url(index_token_id(12)).url,
+            "ipfs://CID/12.json"
+        );
+
+        host.state_mut().base_url = "https://some.example/{id:4}/meta.json".to_string();
+        assert_eq!(
+            host.state().metadata_url(index_token_id(12)).url,
+            "https://some.example/0012/meta.json"
+        );
     }
 
     #[concordium_test]
-    fn test_mint_no_reserve_no_whitelist() {
+    fn test_token_payment() {
         let mut ctx = TestInitContext::empty();
         ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
         let mut state_builder = TestStateBuilder::new();
 
-        const ACCOUNT_0: AccountAddress = AccountAddress([1u8; 32]);
+        const ADMIN: AccountAddress = AccountAddress([9u8; 32]);
+        const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
+        const STABLECOIN: ContractAddress = ContractAddress {
+            index: 7,
+            subindex: 0,
+        };
         let account_0_string = "00000000000000000000000000000000000000000000000000".to_string();
 
-        // This should allow anyone to purchase 1 NFT
-        let params = InitParams {
-            nft_limit: 3,
-            nft_time_limit: 0,
-            whitelist: vec![],
-            reserve: 0,
-            base_url: String::new(),
-            whitelist_file: String::new(),
-            metadata: String::new(),
-            selected_index: false,
-            nft_limit_per_address: 0,
+        let mut params = InitParams {
+            nft_limit: 10,
+            mint_price: Amount::from_ccd(1),
+            payment_token: Some(PaymentToken {
+                contract: STABLECOIN,
+                token_id: TokenIdVec(vec![]),
+                price: TokenAmountU64(5_000_000),
+            }),
+            ..default_init_params()
         };
 
+        // a CCD price and a token price cannot both be set
         let parameter_bytes = to_bytes(&params);
         ctx.set_parameter(&parameter_bytes);
+        assert!(init(&ctx, &mut state_builder, Amount::zero()).is_err());
 
-        let state_result = init(&ctx, &mut state_builder);
-        let new_state = state_result.unwrap();
-        assert_eq!(new_state.nft_limit, 3);
+        params.mint_price = Amount::zero();
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+        let mut logger = TestLogger::init();
+        let transfer = OwnedEntrypointName::new_unchecked("transfer".to_string());
 
-        let mut ctx_claim = TestReceiveContext::empty();
-        ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
         let mint_params = ClaimNFTParams {
-            node: ACCOUNT_0,
+            node: Address::Account(ACCOUNT_0),
             node_string: account_0_string,
             proof: vec![],
-            selected_token: concordium_cis2::TokenIdU32(0),
+            selected_token: index_token_id(0),
             amount_of_tokens: 2,
+            tier: None,
+            round: None,
+            voucher: None,
+            max_claims: None,
         };
-
-        let mut host = TestHost::new(new_state, state_builder);
-
         let claim_parameter_bytes = to_bytes(&mint_params);
+        let mut ctx_claim = TestReceiveContext::empty();
+        ctx_claim.set_owner(ADMIN);
+        ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
         ctx_claim.set_parameter(&claim_parameter_bytes);
+        ctx_claim.set_sender(Address::Account(ACCOUNT_0));
+
+        host.setup_mock_entrypoint(STABLECOIN, transfer.clone(), MockFn::returning_ok(()));
+        claim_nft(
+            &ctx_claim,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        )
+        .unwrap();
+        assert_eq!(host.state().claimed_count(), 2);
+        assert_eq!(host.state().collected_funds, Amount::zero());
+
+        // the claim fails when the stablecoin contract rejects the transfer,
+        // such as when this contract is not an operator of the claimer
+        host.setup_mock_entrypoint(
+            STABLECOIN,
+            transfer,
+            MockFn::returning_err::<()>(CallContractError::Trap),
+        );
+        assert_eq!(
+            claim_nft(
+                &ctx_claim,
+                &mut host,
+                Amount::zero(),
+                &mut logger,
+                &crypto_primitives(),
+            ),
+            Err(Error::TokenPaymentFailed)
+        );
+
+        // claims relayed through permit are paid for by the signer, not the sponsor
+        const SPONSOR: AccountAddress = AccountAddress([8u8; 32]);
+        const SELF_ADDRESS: ContractAddress = ContractAddress {
+            index: 10,
+            subindex: 0,
+        };
+        host.setup_mock_entrypoint(
+            STABLECOIN,
+            OwnedEntrypointName::new_unchecked("transfer".to_string()),
+            MockFn::new_v1(|parameter, _, _, _| {
+                let params: TransferParams<TokenIdVec, TokenAmountU64> =
+                    from_bytes(parameter.as_ref()).map_err(|_| CallContractError::Trap)?;
+                if params.0[0].from != Address::Account(ACCOUNT_0) {
+                    return Err(CallContractError::Trap);
+                }
+                Ok((false, ()))
+            }),
+        );
+        let keys_params = RegisterPublicKeysParams {
+            key: PublicKeyEd25519([1u8; 32]),
+        };
+        let keys_parameter_bytes = to_bytes(&keys_params);
+        let mut ctx_keys = TestReceiveContext::empty();
+        ctx_keys.set_sender(Address::Account(ACCOUNT_0));
+        ctx_keys.set_parameter(&keys_parameter_bytes);
+        register_public_keys(&ctx_keys, &mut host).unwrap();
+
+        let permit_params = PermitParam {
+            signature: SignatureEd25519([2u8; 64]),
+            signer: ACCOUNT_0,
+            message: PermitMessage {
+                contract_address: SELF_ADDRESS,
+                nonce: 0,
+                timestamp: Timestamp::from_timestamp_millis(100),
+                entry_point: OwnedEntrypointName::new_unchecked("claim_nft".to_string()),
+                payload: claim_parameter_bytes.clone(),
+            },
+        };
+        let permit_parameter_bytes = to_bytes(&permit_params);
+        let mut ctx_permit = TestReceiveContext::empty();
+        ctx_permit.set_owner(ADMIN);
+        ctx_permit.set_sender(Address::Account(SPONSOR));
+        ctx_permit.set_self_address(SELF_ADDRESS);
+        ctx_permit.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        ctx_permit.set_parameter(&permit_parameter_bytes);
+        let crypto_primitives = crypto_primitives();
+        crypto_primitives.setup_verify_ed25519_signature_mock(|_, _, _| true);
+        let claimed = host.state().claimed_count();
+        permit(&ctx_permit, &mut host, &mut logger, &crypto_primitives).unwrap();
+        assert_eq!(host.state().claimed_count(), claimed + 2);
+    }
+
+    #[concordium_test]
+    fn test_raffle() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        const ADMIN: AccountAddress = AccountAddress([9u8; 32]);
+        const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
+        const ACCOUNT_1: AccountAddress = AccountAddress([1u8; 32]);
+
+        let params = InitParams {
+            nft_limit: 10,
+            raffle: Some(RaffleParams {
+                registration_end: Timestamp::from_timestamp_millis(10),
+                winners: 1,
+                deposit: Amount::from_ccd(1),
+                salt_commitment: digest("salt"),
+            }),
+            ..default_init_params()
+        };
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+        host.set_self_balance(Amount::from_ccd(2));
         let mut logger = TestLogger::init();
 
-        let claim_result = claim_nft(&ctx_claim, &mut host, &mut logger);
-        assert_eq!(claim_result.is_ok(), true);
+        let mut ctx_register = TestReceiveContext::empty();
+        ctx_register.set_metadata_slot_time(Timestamp::from_timestamp_millis(5));
+        for account in [ACCOUNT_0, ACCOUNT_1] {
+            ctx_register.set_sender(Address::Account(account));
+            assert_eq!(
+                register(&ctx_register, &mut host, Amount::zero()),
+                Err(Error::InsufficientFunds)
+            );
+            register(&ctx_register, &mut host, Amount::from_ccd(1)).unwrap();
+        }
+        assert_eq!(
+            register(&ctx_register, &mut host, Amount::from_ccd(1)),
+            Err(Error::AlreadyRegistered)
+        );
 
-        let claim_result_bad: Result<(), Error> = claim_nft(&ctx_claim, &mut host, &mut logger);
-        assert_eq!(claim_result_bad, Err(Error::NFTLimitReached));
+        let claim = |host: &mut TestHost<State<TestStateApi>>,
+                     logger: &mut TestLogger,
+                     account: AccountAddress| {
+            let mint_params = ClaimNFTParams {
+                node: Address::Account(account),
+                node_string: account_address_to_string(account),
+                proof: vec![],
+                selected_token: index_token_id(0),
+                amount_of_tokens: 1,
+                tier: None,
+                round: None,
+                voucher: None,
+                max_claims: None,
+            };
+            let claim_parameter_bytes = to_bytes(&mint_params);
+            let mut ctx_claim = TestReceiveContext::empty();
+            ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(11));
+            ctx_claim.set_parameter(&claim_parameter_bytes);
+            ctx_claim.set_sender(Address::Account(account));
+            claim_nft(
+                &ctx_claim,
+                host,
+                Amount::zero(),
+                logger,
+                &crypto_primitives(),
+            )
+        };
+        assert_eq!(
+            claim(&mut host, &mut logger, ACCOUNT_0),
+            Err(Error::RaffleNotDrawn)
+        );
+
+        let salt_params = RandomSaltParams {
+            salt: "salt".to_string(),
+        };
+        let salt_parameter_bytes = to_bytes(&salt_params);
+        let mut ctx_draw = TestReceiveContext::empty();
+        ctx_draw.set_owner(ADMIN);
+        ctx_draw.set_sender(Address::Account(ADMIN));
+        ctx_draw.set_parameter(&salt_parameter_bytes);
+        ctx_draw.set_metadata_slot_time(Timestamp::from_timestamp_millis(10));
+        assert_eq!(draw(&ctx_draw, &mut host), Err(Error::RaffleStillOpen));
+
+        ctx_draw.set_metadata_slot_time(Timestamp::from_timestamp_millis(11));
+        draw(&ctx_draw, &mut host).unwrap();
+        assert_eq!(draw(&ctx_draw, &mut host), Err(Error::RaffleNotOpen));
+        assert_eq!(host.state().collected_funds, Amount::from_ccd(1));
+
+        // the deposit of the account which was not drawn can not be withdrawn
+        let mut ctx_withdraw = TestReceiveContext::empty();
+        ctx_withdraw.set_owner(ADMIN);
+        ctx_withdraw.set_sender(Address::Account(ADMIN));
+        let withdraw_all_bytes = to_bytes(&WithdrawParams {
+            amount: Amount::from_ccd(2),
+            to: ADMIN,
+        });
+        ctx_withdraw.set_parameter(&withdraw_all_bytes);
+        assert_eq!(
+            withdraw(&ctx_withdraw, &mut host, &mut logger),
+            Err(Error::InsufficientFunds)
+        );
+        let withdraw_proceeds_bytes = to_bytes(&WithdrawParams {
+            amount: Amount::from_ccd(1),
+            to: ADMIN,
+        });
+        ctx_withdraw.set_parameter(&withdraw_proceeds_bytes);
+        withdraw(&ctx_withdraw, &mut host, &mut logger).unwrap();
+
+        let (winner, loser) = if host.state().raffle_winners.contains(&ACCOUNT_0) {
+            (ACCOUNT_0, ACCOUNT_1)
+        } else {
+            (ACCOUNT_1, ACCOUNT_0)
+        };
+        assert!(host.state().raffle_winners.contains(&winner));
+        assert!(!host.state().raffle_winners.contains(&loser));
+
+        assert!(claim(&mut host, &mut logger, winner).is_ok());
+        assert_eq!(
+            claim(&mut host, &mut logger, loser),
+            Err(Error::NotRaffleWinner)
+        );
+
+        // only the accounts which were not drawn get their deposit back, once
+        let mut ctx_refund = TestReceiveContext::empty();
+        ctx_refund.set_sender(Address::Account(winner));
+        assert_eq!(
+            refund_deposit(&ctx_refund, &mut host),
+            Err(Error::NotRegistered)
+        );
+        ctx_refund.set_sender(Address::Account(loser));
+        refund_deposit(&ctx_refund, &mut host).unwrap();
+        assert_eq!(
+            refund_deposit(&ctx_refund, &mut host),
+            Err(Error::NotRegistered)
+        );
+        assert_eq!(host.get_transfers_to(loser), vec![Amount::from_ccd(1)]);
     }
 
     #[concordium_test]
-    fn test_whitelist() {
+    fn test_rate_limit() {
         let mut ctx = TestInitContext::empty();
         ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
         let mut state_builder = TestStateBuilder::new();
 
+        const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
         let account_0_string = "00000000000000000000000000000000000000000000000000".to_string();
-        let account_1_string = "11111111111111111111111111111111111111111111111111".to_string();
-        let account_2_string = "22222222222222222222222222222222222222222222222222".to_string();
-
-        let whitelist: Vec<String> =
-            vec![account_0_string.clone(), account_1_string, account_2_string];
 
-        // This should allow anyone to purchase 1 NFT
         let params = InitParams {
-            nft_limit: 1,
-            nft_time_limit: 0,
-            whitelist: whitelist.clone(),
-            reserve: 0,
-            base_url: String::new(),
-            whitelist_file: String::new(),
-            metadata: String::new(),
-            selected_index: false,
-            nft_limit_per_address: 0,
+            nft_limit: 10,
+            rate_limit: Some(RateLimit {
+                max_claims: 2,
+                window: Duration::from_seconds(60),
+            }),
+            ..default_init_params()
         };
-
         let parameter_bytes = to_bytes(&params);
         ctx.set_parameter(&parameter_bytes);
 
-        let state = init(&ctx, &mut state_builder).unwrap();
-        // convert the addresses to strings
-        let mut hashes: Vec<String> = vec![];
-        for address in whitelist {
-            hashes.push(digest(address));
-        }
-
-        let bad_address: String = "This address should not work".to_string();
-        assert_eq!(state.check_hash_value(hashes[0].clone()), true);
-        assert_eq!(state.check_hash_value(hashes[1].clone()), true);
-        assert_eq!(state.check_hash_value(hashes[2].clone()), true);
-        assert_eq!(state.check_hash_value(bad_address), false);
-
-        let a = digest(hashes[0].clone() + &hashes[1]);
-        let b = digest(hashes[2].clone() + &hashes[2]); // MT will duplicated 4th element from 3rd
-        let c = digest(a.clone() + &b);
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+        let mut logger = TestLogger::init();
 
-        let test_merkle_proof = vec![hashes[0].clone(), a, c];
+        let mut claim = |slot_time: u64| {
+            let mint_params = ClaimNFTParams {
+                node: Address::Account(ACCOUNT_0),
+                node_string: account_0_string.clone(),
+                proof: vec![],
+                selected_token: index_token_id(0),
+                amount_of_tokens: 1,
+                tier: None,
+                round: None,
+                voucher: None,
+                max_claims: None,
+            };
+            let claim_parameter_bytes = to_bytes(&mint_params);
+            let mut ctx_claim = TestReceiveContext::empty();
+            ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(slot_time));
+            ctx_claim.set_parameter(&claim_parameter_bytes);
+            ctx_claim.set_sender(Address::Account(ACCOUNT_0));
+            claim_nft(
+                &ctx_claim,
+                &mut host,
+                Amount::zero(),
+                &mut logger,
+                &crypto_primitives(),
+            )
+        };
 
-        let test_address = digest(account_0_string);
-        let merkle_proof = state.get_hash_proof(test_address).unwrap();
-        assert_eq!(merkle_proof, test_merkle_proof);
+        assert!(claim(1_000).is_ok());
+        assert!(claim(59_999).is_ok());
+        assert_eq!(claim(59_999), Err(Error::RateLimited));
+        // the next minute starts a new window
+        assert!(claim(60_000).is_ok());
     }
 
     #[concordium_test]
-    fn test_merkle_proof() {
+    fn test_claim_next_and_selected() {
         let mut ctx = TestInitContext::empty();
         ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
         let mut state_builder = TestStateBuilder::new();
 
+        const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
         let account_0_string = "00000000000000000000000000000000000000000000000000".to_string();
-        let account_1_string = "11111111111111111111111111111111111111111111111111".to_string();
-        let account_2_string = "22222222222222222222222222222222222222222222222222".to_string();
-        let account_3_string = "33333333333333333333333333333333333333333333333333".to_string();
-
-        let whitelist: Vec<String> = vec![
-            account_0_string.clone(),
-            account_1_string.clone(),
-            account_2_string,
-        ];
 
-        // This should allow anyone to purchase 1 NFT
-        let params = InitParams {
-            nft_limit: 1,
-            nft_time_limit: 0,
-            whitelist: whitelist.clone(),
-            whitelist_file: String::new(),
-            metadata: String::new(),
-            reserve: 0,
-            base_url: String::new(),
-            selected_index: false,
-            nft_limit_per_address: 0,
+        let mut params = InitParams {
+            nft_limit: 10,
+            ..default_init_params()
         };
-
         let parameter_bytes = to_bytes(&params);
         ctx.set_parameter(&parameter_bytes);
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+        let mut logger = TestLogger::init();
 
-        let state = init(&ctx, &mut state_builder).unwrap();
-
-        // convert the addresses to strings
-        let mut hashes: Vec<String> = vec![];
-        for address in whitelist {
-            hashes.push(digest(address));
-        }
-
-        assert_eq!(state.check_hash_value(hashes[0].clone()), true);
-        assert_eq!(state.check_hash_value(hashes[1].clone()), true);
-        assert_eq!(state.check_hash_value(hashes[2].clone()), true);
-        assert_eq!(state.check_hash_value(account_3_string), false);
+        let next_params = ClaimNextParams {
+            proof: vec![],
+            node: Address::Account(ACCOUNT_0),
+            node_string: account_0_string.clone(),
+            amount_of_tokens: 2,
+            tier: None,
+            round: None,
+            voucher: None,
+            max_claims: None,
+        };
+        let next_parameter_bytes = to_bytes(&next_params);
+        let selected_params = ClaimSelectedParams {
+            proof: vec![],
+            node: Address::Account(ACCOUNT_0),
+            node_string: account_0_string,
+            selected_token: index_token_id(5),
+            amount_of_tokens: 1,
+            tier: None,
+            voucher: None,
+            max_claims: None,
+        };
+        let selected_parameter_bytes = to_bytes(&selected_params);
+
+        let mut ctx_next = TestReceiveContext::empty();
+        ctx_next.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        ctx_next.set_sender(Address::Account(ACCOUNT_0));
+        ctx_next.set_parameter(&next_parameter_bytes);
+        let mut ctx_selected = TestReceiveContext::empty();
+        ctx_selected.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        ctx_selected.set_sender(Address::Account(ACCOUNT_0));
+        ctx_selected.set_parameter(&selected_parameter_bytes);
+
+        // sequential claims take the next tokens and cannot select one
+        claim_next(
+            &ctx_next,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        )
+        .unwrap();
+        assert_eq!(host.state().next_token_id, 2);
+        assert_eq!(
+            claim_selected(
+                &ctx_selected,
+                &mut host,
+                Amount::zero(),
+                &mut logger,
+                &crypto_primitives(),
+            ),
+            Err(Error::ConflictingClaimModes)
+        );
 
-        let a = digest(hashes[0].clone() + &hashes[1]);
-        let b = digest(hashes[2].clone() + &hashes[2]); // MT will duplicated 4th element from 3rd
-        let c = digest(a.clone() + &b);
+        params.selected_index = true;
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+        let mut state_builder = TestStateBuilder::new();
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let mut host = TestHost::new(state, state_builder);
 
-        let test_merkle_proof = vec![hashes[0].clone(), a, c];
+        // claimers selecting their token have to say which one
+        assert_eq!(
+            claim_next(
+                &ctx_next,
+                &mut host,
+                Amount::zero(),
+                &mut logger,
+                &crypto_primitives(),
+            ),
+            Err(Error::ConflictingClaimModes)
+        );
+        claim_selected(
+            &ctx_selected,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        )
+        .unwrap();
+        assert_eq!(
+            *host.state().taken_indexes.get(&index_token_id(5)).unwrap(),
+            "00000000000000000000000000000000000000000000000000"
+        );
+    }
 
-        let test_address = digest(account_0_string.clone());
-        let merkle_proof = state.get_hash_proof(test_address).unwrap();
-        assert_eq!(merkle_proof, test_merkle_proof);
+    #[concordium_test]
+    fn test_leaf_encoding() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
 
         const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
         const ACCOUNT_1: AccountAddress = AccountAddress([1u8; 32]);
+        let account_0_base58 = "2wkBET2rRgE8pahuaczxKbmv7ciehqsne57F9gtzf1PVdr2VP3".to_string();
+        let account_1_base58 = "2xBpaHottqhwFZURMZW4uZduQvpxNDSy46iXMYs9kceNGaPpZX".to_string();
+        assert_eq!(account_address_to_base58(ACCOUNT_0), account_0_base58);
+        assert_eq!(account_address_to_base58(ACCOUNT_1), account_1_base58);
 
-        let proof_params = ClaimNFTParams {
-            proof: test_merkle_proof.clone(),
-            node: ACCOUNT_0,
-            node_string: account_0_string,
-            selected_token: concordium_cis2::TokenIdU32(0),
-            amount_of_tokens: 1,
+        let mut params = InitParams {
+            nft_limit: 10,
+            whitelist: vec![account_0_base58.clone(), account_1_base58],
+            leaf_encoding: LeafEncoding::Base58Check,
+            ..default_init_params()
         };
-        assert_eq!(state.check_proof(&proof_params), true);
-
-        let proof_params = ClaimNFTParams {
-            proof: test_merkle_proof.clone(),
-            node: ACCOUNT_1,
-            node_string: account_1_string,
-            selected_token: concordium_cis2::TokenIdU32(0),
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+
+        // the leaf is encoded from the claiming address, whatever node_string says
+        let proof = state
+            .get_hash_proof(MerkleHash::Sha256.hash_leaf(&account_0_base58))
+            .unwrap();
+        let mut proof_params = ClaimNFTParams {
+            node: Address::Account(ACCOUNT_0),
+            node_string: String::new(),
+            proof,
+            selected_token: index_token_id(0),
             amount_of_tokens: 1,
+            tier: None,
+            round: None,
+            voucher: None,
+            max_claims: None,
         };
-        assert_eq!(state.check_proof(&proof_params), false);
+        assert!(state.check_proof(&proof_params, &crypto_primitives()));
+        proof_params.node = Address::Account(ACCOUNT_1);
+        assert!(!state.check_proof(&proof_params, &crypto_primitives()));
+
+        // raw leaves are the address bytes, whitelisted as hex
+        params.leaf_encoding = LeafEncoding::Raw;
+        params.whitelist = vec![
+            account_address_to_string(ACCOUNT_0),
+            account_address_to_string(ACCOUNT_1),
+        ];
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+        let mut state_builder = TestStateBuilder::new();
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        proof_params.proof = state
+            .get_hash_proof(MerkleHash::Sha256.hash_leaf(ACCOUNT_1.0))
+            .unwrap();
+        assert!(state.check_proof(&proof_params, &crypto_primitives()));
+
+        // an account listed twice is rejected, even in another case or with
+        // another allocation
+        params.whitelist = vec![
+            account_address_to_string(ACCOUNT_0),
+            format!("{}:2", account_address_to_string(ACCOUNT_0).to_uppercase()),
+        ];
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+        let mut state_builder = TestStateBuilder::new();
+        assert_eq!(
+            init(&ctx, &mut state_builder, Amount::zero()).err(),
+            Some(Error::DuplicateWhitelistEntry.into())
+        );
     }
 
     #[concordium_test]
-    fn test_claim_with_whitelist_full_reserve() {
+    fn test_reserve_allocations() {
         let mut ctx = TestInitContext::empty();
-        let mut state_builder = TestStateBuilder::new();
         ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
 
-        let account_0_string = "00000000000000000000000000000000000000000000000000".to_string();
-        let account_1_string = "10101010101010101010101010101010101010101010101010".to_string();
         const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
         const ACCOUNT_1: AccountAddress = AccountAddress([1u8; 32]);
 
-        let whitelist: Vec<String> = vec![account_0_string.clone(), account_1_string.clone()];
-
-        // This should allow anyone to purchase 1 NFT
-        let params = InitParams {
+        let mut params = InitParams {
             nft_limit: 4,
-            nft_time_limit: 0,
-            whitelist: whitelist.clone(),
-            reserve: 4,
-            base_url: String::new(),
-            whitelist_file: String::new(),
-            metadata: String::new(),
-            selected_index: false,
-            nft_limit_per_address: 0,
+            reserve: 2,
+            reserve_allocations: vec![(Address::Account(ACCOUNT_1), 3)],
+            ..default_init_params()
         };
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+        assert!(init(&ctx, &mut state_builder, Amount::zero()).is_err());
 
+        params.reserve_allocations = vec![(Address::Account(ACCOUNT_1), 1)];
         let parameter_bytes = to_bytes(&params);
         ctx.set_parameter(&parameter_bytes);
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+        let mut logger = TestLogger::init();
 
-        let state = init(&ctx, &mut state_builder).unwrap();
+        let mut claim = |account: AccountAddress, amount_of_tokens: u32| {
+            let mint_params = ClaimNFTParams {
+                node: Address::Account(account),
+                node_string: account_address_to_string(account),
+                proof: vec![],
+                selected_token: index_token_id(0),
+                amount_of_tokens,
+                tier: None,
+                round: None,
+                voucher: None,
+                max_claims: None,
+            };
+            let claim_parameter_bytes = to_bytes(&mint_params);
+            let mut ctx_claim = TestReceiveContext::empty();
+            ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+            ctx_claim.set_parameter(&claim_parameter_bytes);
+            ctx_claim.set_sender(Address::Account(account));
+            claim_nft(
+                &ctx_claim,
+                &mut host,
+                Amount::zero(),
+                &mut logger,
+                &crypto_primitives(),
+            )
+        };
 
-        let mut test_proof: Vec<String> = vec![];
-        let acc1 = digest(account_0_string.clone());
-        let acc2 = digest(account_1_string.clone());
+        assert!(claim(ACCOUNT_0, 3).is_ok());
+        // the last token is ACCOUNT_1's allocation
+        assert_eq!(claim(ACCOUNT_0, 1), Err(Error::ReserveAllocationExceeded));
+        assert_eq!(claim(ACCOUNT_1, 2), Err(Error::NFTLimitReached));
+        assert!(claim(ACCOUNT_1, 1).is_ok());
+
+        let allocation_parameter_bytes = to_bytes(&Address::Account(ACCOUNT_1));
+        let mut ctx_view = TestReceiveContext::empty();
+        ctx_view.set_parameter(&allocation_parameter_bytes);
+        assert_eq!(reserve_allocation(&ctx_view, &host), Ok(0));
+        assert_eq!(host.state().reserve_allocated, 0);
+    }
 
-        test_proof.push(acc1.clone());
-        test_proof.push(digest(acc1.clone() + &acc2));
+    #[concordium_test]
+    fn test_set_nft_limit() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
 
-        let mut ctx_claim = TestReceiveContext::empty();
-        ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        const ADMIN: AccountAddress = AccountAddress([9u8; 32]);
+        const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
 
-        let mint_params = ClaimNFTParams {
-            node: ACCOUNT_0,
-            node_string: account_0_string,
-            proof: test_proof.clone(),
-            selected_token: concordium_cis2::TokenIdU32(0),
-            amount_of_tokens: 1,
+        let params = InitParams {
+            nft_limit: 2,
+            ..default_init_params()
         };
-
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
         let mut host = TestHost::new(state, state_builder);
+        let mut logger = TestLogger::init();
 
+        let mint_params = ClaimNFTParams {
+            node: Address::Account(ACCOUNT_0),
+            node_string: account_address_to_string(ACCOUNT_0),
+            proof: vec![],
+            selected_token: index_token_id(0),
+            amount_of_tokens: 2,
+            tier: None,
+            round: None,
+            voucher: None,
+            max_claims: None,
+        };
         let claim_parameter_bytes = to_bytes(&mint_params);
+        let mut ctx_claim = TestReceiveContext::empty();
+        ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
         ctx_claim.set_parameter(&claim_parameter_bytes);
-        let mut logger = TestLogger::init();
+        ctx_claim.set_sender(Address::Account(ACCOUNT_0));
+        assert!(claim_nft(
+            &ctx_claim,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        )
+        .is_ok());
+
+        let mut ctx_limit = TestReceiveContext::empty();
+        ctx_limit.set_owner(ADMIN);
+        ctx_limit.set_sender(Address::Account(ACCOUNT_0));
+        let limit_parameter_bytes = to_bytes(&SetNftLimitParams { nft_limit: 5 });
+        ctx_limit.set_parameter(&limit_parameter_bytes);
+        assert_eq!(
+            set_nft_limit(&ctx_limit, &mut host, &mut logger),
+            Err(Error::Unauthorized)
+        );
 
-        claim_nft(&ctx_claim, &mut host, &mut logger).unwrap();
+        ctx_limit.set_sender(Address::Account(ADMIN));
+        assert!(set_nft_limit(&ctx_limit, &mut host, &mut logger).is_ok());
+        assert_eq!(host.state().nft_limit, 5);
+        assert_eq!(
+            logger.logs.last(),
+            Some(&to_bytes(&AirdropEvent::NftLimitUpdated(
+                NftLimitUpdatedEvent {
+                    previous: 2,
+                    nft_limit: 5,
+                }
+            )))
+        );
 
-        let mut ctx_bad_claim = TestReceiveContext::empty();
-        ctx_bad_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
-        let mint_bad_params = ClaimNFTParams {
-            node: ACCOUNT_1,
-            node_string: account_1_string,
-            proof: test_proof.clone(),
-            selected_token: concordium_cis2::TokenIdU32(0),
-            amount_of_tokens: 1,
-        };
-        let bad_claim_parameter_bytes = to_bytes(&mint_bad_params);
-        ctx_bad_claim.set_parameter(&bad_claim_parameter_bytes);
+        // it can't go below the two tokens claimed
+        let limit_parameter_bytes = to_bytes(&SetNftLimitParams { nft_limit: 1 });
+        ctx_limit.set_parameter(&limit_parameter_bytes);
+        assert_eq!(
+            set_nft_limit(&ctx_limit, &mut host, &mut logger),
+            Err(Error::InvalidNftLimit)
+        );
+        let limit_parameter_bytes = to_bytes(&SetNftLimitParams { nft_limit: 2 });
+        ctx_limit.set_parameter(&limit_parameter_bytes);
+        assert!(set_nft_limit(&ctx_limit, &mut host, &mut logger).is_ok());
+        assert_eq!(host.state().nft_limit, 2);
 
-        let claim_result_bad = claim_nft(&ctx_bad_claim, &mut host, &mut logger);
-        claim_eq!(
-            claim_result_bad,
-            Err(Error::AddressNotOnWhitelist),
-            "Function should fail with NFT error"
+        assert!(lock_supply(&ctx_limit, &mut host).is_ok());
+        assert!(
+            host.state()
+                .config(Timestamp::from_timestamp_millis(1))
+                .supply_locked
+        );
+        assert_eq!(
+            set_nft_limit(&ctx_limit, &mut host, &mut logger),
+            Err(Error::SupplyLocked)
+        );
+        let reserve_parameter_bytes = to_bytes(&MintReserveParams {
+            to: Receiver::Account(ADMIN),
+            count: 1,
+            data: AdditionalData::empty(),
+        });
+        ctx_limit.set_parameter(&reserve_parameter_bytes);
+        assert_eq!(
+            mint_reserve(&ctx_limit, &mut host, &mut logger),
+            Err(Error::SupplyLocked)
         );
     }
 
     #[concordium_test]
-    fn test_claim_with_whitelist_no_reserve() {
+    fn test_shuffle_reveal() {
         let mut ctx = TestInitContext::empty();
-        let mut state_builder = TestStateBuilder::new();
         ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
 
-        let account_0_string = "00000000000000000000000000000000000000000000000000".to_string();
-        let account_1_string = "11111111111111111111111111111111111111111111111111".to_string();
+        const ADMIN: AccountAddress = AccountAddress([9u8; 32]);
         const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
-        const ACCOUNT_1: AccountAddress = AccountAddress([1u8; 32]);
-
-        let whitelist: Vec<String> = vec![account_0_string.clone(), account_1_string.clone()];
 
-        // This should allow anyone to purchase 1 NFT
         let params = InitParams {
-            nft_limit: 10,
-            nft_time_limit: 0,
-            whitelist: whitelist.clone(),
-            reserve: 0,
-            base_url: String::new(),
-            whitelist_file: String::new(),
-            metadata: String::new(),
-            selected_index: false,
-            nft_limit_per_address: 1,
+            nft_limit: 6,
+            base_url: "ipfs://cid/".to_string(),
+            shuffle_seed_commitment: digest("seed"),
+            ..default_init_params()
         };
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+        let mut logger = TestLogger::init();
 
-        let mut test_proof: Vec<String> = vec![];
-        let acc1 = digest(account_0_string.clone());
-        let acc2 = digest(account_1_string.clone());
+        let mut claim = |host: &mut TestHost<State<TestStateApi>>, amount_of_tokens: u32| {
+            let mint_params = ClaimNFTParams {
+                node: Address::Account(ACCOUNT_0),
+                node_string: account_address_to_string(ACCOUNT_0),
+                proof: vec![],
+                selected_token: index_token_id(0),
+                amount_of_tokens,
+                tier: None,
+                round: None,
+                voucher: None,
+                max_claims: None,
+            };
+            let claim_parameter_bytes = to_bytes(&mint_params);
+            let mut ctx_claim = TestReceiveContext::empty();
+            ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+            ctx_claim.set_parameter(&claim_parameter_bytes);
+            ctx_claim.set_sender(Address::Account(ACCOUNT_0));
+            claim_nft(
+                &ctx_claim,
+                host,
+                Amount::zero(),
+                &mut logger,
+                &crypto_primitives(),
+            )
+        };
+        assert!(claim(&mut host, 5).is_ok());
+        // the metadata stays hidden until the seed is revealed
+        assert_eq!(host.state().metadata_url(index_token_id(0)).url, "");
 
-        test_proof.push(acc1.clone());
-        test_proof.push(digest(acc1.clone() + &acc2));
+        let mut logger = TestLogger::init();
+        let mut ctx_reveal = TestReceiveContext::empty();
+        ctx_reveal.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        ctx_reveal.set_owner(ADMIN);
+        ctx_reveal.set_sender(Address::Account(ADMIN));
+        let wrong_seed_bytes = to_bytes(&RandomSaltParams {
+            salt: "other".to_string(),
+        });
+        ctx_reveal.set_parameter(&wrong_seed_bytes);
+        assert_eq!(
+            reveal_shuffle_seed(&ctx_reveal, &mut host, &mut logger),
+            Err(Error::WrongRandomSalt)
+        );
+        let seed_bytes = to_bytes(&RandomSaltParams {
+            salt: "seed".to_string(),
+        });
+        ctx_reveal.set_parameter(&seed_bytes);
+        assert_eq!(
+            reveal_shuffle_seed(&ctx_reveal, &mut host, &mut logger),
+            Err(Error::NotSoldOut)
+        );
 
-        let parameter_bytes = to_bytes(&params);
-        ctx.set_parameter(&parameter_bytes);
+        assert!(claim(&mut host, 1).is_ok());
+        assert!(reveal_shuffle_seed(&ctx_reveal, &mut host, &mut logger).is_ok());
+        assert_eq!(logger.logs.len(), 6);
+        assert_eq!(
+            reveal_shuffle_seed(&ctx_reveal, &mut host, &mut logger),
+            Err(Error::ShuffleAlreadyRevealed)
+        );
 
-        let state = init(&ctx, &mut state_builder).unwrap();
+        // every token gets different metadata, and all of it is used
+        let mut urls: Vec<String> = (0..6)
+            .map(|index| host.state().metadata_url(index_token_id(index)).url)
+            .collect();
+        urls.sort();
+        let expected: Vec<String> = (0..6)
+            .map(|index| format!("ipfs://cid/{}", index))
+            .collect();
+        assert_eq!(urls, expected);
+    }
 
-        let mut ctx_claim = TestReceiveContext::empty();
-        let mint_params = ClaimNFTParams {
-            node: ACCOUNT_0,
-            node_string: account_0_string,
-            proof: test_proof.clone(),
-            selected_token: concordium_cis2::TokenIdU32(0),
-            amount_of_tokens: 1,
+    #[concordium_test]
+    fn test_token_gate() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
+        const PASS: ContractAddress = ContractAddress {
+            index: 8,
+            subindex: 0,
         };
 
+        let params = InitParams {
+            nft_limit: 10,
+            token_gate: Some(TokenGate {
+                contract: PASS,
+                token_id: TokenIdVec(vec![1]),
+                min_balance: TokenAmountU64(2),
+            }),
+            ..default_init_params()
+        };
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
         let mut host = TestHost::new(state, state_builder);
+        let mut logger = TestLogger::init();
+        let balance_of = OwnedEntrypointName::new_unchecked("balanceOf".to_string());
 
+        let mint_params = ClaimNFTParams {
+            node: Address::Account(ACCOUNT_0),
+            node_string: account_address_to_string(ACCOUNT_0),
+            proof: vec![],
+            selected_token: index_token_id(0),
+            amount_of_tokens: 1,
+            tier: None,
+            round: None,
+            voucher: None,
+            max_claims: None,
+        };
         let claim_parameter_bytes = to_bytes(&mint_params);
-        ctx_claim.set_parameter(&claim_parameter_bytes);
+        let mut ctx_claim = TestReceiveContext::empty();
         ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
-        let mut logger = TestLogger::init();
-
-        claim_nft(&ctx_claim, &mut host, &mut logger).unwrap();
+        ctx_claim.set_parameter(&claim_parameter_bytes);
+        ctx_claim.set_sender(Address::Account(ACCOUNT_0));
+
+        host.setup_mock_entrypoint(
+            PASS,
+            balance_of.clone(),
+            MockFn::new_v1(|_, _, _, _| {
+                Ok((false, BalanceOfQueryResponse(vec![TokenAmountU64(1)])))
+            }),
+        );
+        assert_eq!(
+            claim_nft(
+                &ctx_claim,
+                &mut host,
+                Amount::zero(),
+                &mut logger,
+                &crypto_primitives(),
+            ),
+            Err(Error::NotTokenHolder)
+        );
 
-        let mut ctx_bad_claim = TestReceiveContext::empty();
-        ctx_bad_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
-        let mint_bad_params = ClaimNFTParams {
-            node: ACCOUNT_1,
-            node_string: account_1_string,
-            proof: test_proof.clone(),
-            selected_token: concordium_cis2::TokenIdU32(0),
-            amount_of_tokens: 1,
+        // claims relayed through permit are gated on the signer's balance too
+        const SPONSOR: AccountAddress = AccountAddress([8u8; 32]);
+        const SELF_ADDRESS: ContractAddress = ContractAddress {
+            index: 10,
+            subindex: 0,
+        };
+        let keys_params = RegisterPublicKeysParams {
+            key: PublicKeyEd25519([1u8; 32]),
         };
-        let bad_claim_parameter_bytes = to_bytes(&mint_bad_params);
-        ctx_bad_claim.set_parameter(&bad_claim_parameter_bytes);
+        let keys_parameter_bytes = to_bytes(&keys_params);
+        let mut ctx_keys = TestReceiveContext::empty();
+        ctx_keys.set_sender(Address::Account(ACCOUNT_0));
+        ctx_keys.set_parameter(&keys_parameter_bytes);
+        register_public_keys(&ctx_keys, &mut host).unwrap();
+        let permit_params = PermitParam {
+            signature: SignatureEd25519([2u8; 64]),
+            signer: ACCOUNT_0,
+            message: PermitMessage {
+                contract_address: SELF_ADDRESS,
+                nonce: 0,
+                timestamp: Timestamp::from_timestamp_millis(100),
+                entry_point: OwnedEntrypointName::new_unchecked("claim_nft".to_string()),
+                payload: claim_parameter_bytes.clone(),
+            },
+        };
+        let permit_parameter_bytes = to_bytes(&permit_params);
+        let mut ctx_permit = TestReceiveContext::empty();
+        ctx_permit.set_sender(Address::Account(SPONSOR));
+        ctx_permit.set_self_address(SELF_ADDRESS);
+        ctx_permit.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        ctx_permit.set_parameter(&permit_parameter_bytes);
+        let permit_primitives = crypto_primitives();
+        permit_primitives.setup_verify_ed25519_signature_mock(|_, _, _| true);
+        assert_eq!(
+            permit(&ctx_permit, &mut host, &mut logger, &permit_primitives),
+            Err(Error::NotTokenHolder)
+        );
 
-        let claim_result_bad = claim_nft(&ctx_bad_claim, &mut host, &mut logger);
-        claim_eq!(
-            claim_result_bad,
-            Err(Error::AddressNotOnWhitelist),
-            "Function should fail with NFT error"
+        host.setup_mock_entrypoint(
+            PASS,
+            balance_of,
+            MockFn::new_v1(|_, _, _, _| {
+                Ok((false, BalanceOfQueryResponse(vec![TokenAmountU64(2)])))
+            }),
         );
+        assert!(claim_nft(
+            &ctx_claim,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        )
+        .is_ok());
+        assert_eq!(host.state().claimed_count(), 1);
     }
 
     #[concordium_test]
-    fn test_claim_with_whitelist_partial_reserve() {
+    fn test_identity_policy() {
         let mut ctx = TestInitContext::empty();
         ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
         let mut state_builder = TestStateBuilder::new();
 
-        let account_0_string = "00000000000000000000000000000000000000000000000000".to_string();
-        let account_1_string = "11111111111111111111111111111111111111111111111111".to_string();
-
         const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
-        const ACCOUNT_1: AccountAddress = AccountAddress([1u8; 32]);
-
-        let whitelist: Vec<String> = vec![account_0_string.clone()];
 
-        // This should allow anyone to purchase 1 NFT
         let params = InitParams {
-            nft_limit: 3,
-            nft_time_limit: 0,
-            whitelist: whitelist.clone(),
-            reserve: 2,
-            base_url: String::new(),
-            whitelist_file: String::new(),
-            metadata: String::new(),
-            selected_index: false,
-            nft_limit_per_address: 0,
+            nft_limit: 10,
+            identity_policy: Some(IdentityPolicy {
+                attribute: attributes::NATIONALITY.0,
+                blocked: vec!["KP".to_string()],
+            }),
+            ..default_init_params()
         };
-
         let parameter_bytes = to_bytes(&params);
         ctx.set_parameter(&parameter_bytes);
-
-        let state = init(&ctx, &mut state_builder).unwrap();
-
-        let mut ctx_claim = TestReceiveContext::empty();
-        ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+        let mut logger = TestLogger::init();
 
         let mint_params = ClaimNFTParams {
-            node: ACCOUNT_1,
-            node_string: account_1_string,
+            node: Address::Account(ACCOUNT_0),
+            node_string: account_address_to_string(ACCOUNT_0),
             proof: vec![],
-            selected_token: concordium_cis2::TokenIdU32(0),
+            selected_token: index_token_id(0),
             amount_of_tokens: 1,
+            tier: None,
+            round: None,
+            voucher: None,
+            max_claims: None,
         };
-
-        let mut host = TestHost::new(state, state_builder);
-
         let claim_parameter_bytes = to_bytes(&mint_params);
-        ctx_claim.set_parameter(&claim_parameter_bytes);
-
-        let mut logger = TestLogger::init();
-        // this should not check the whitelist
-        claim_nft(&ctx_claim, &mut host, &mut logger).unwrap();
+        let mut claim = |nationality: Option<&str>| {
+            let mut ctx_claim = TestReceiveContext::empty();
+            ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+            ctx_claim.set_parameter(&claim_parameter_bytes);
+            ctx_claim.set_sender(Address::Account(ACCOUNT_0));
+            ctx_claim.empty_policies();
+            if let Some(nationality) = nationality {
+                ctx_claim.push_policy(OwnedPolicy {
+                    identity_provider: 0,
+                    created_at: Timestamp::from_timestamp_millis(0),
+                    valid_to: Timestamp::from_timestamp_millis(u64::MAX),
+                    items: vec![(
+                        attributes::NATIONALITY,
+                        AttributeValue::new(nationality.as_bytes()).unwrap(),
+                    )],
+                });
+            }
+            claim_nft(
+                &ctx_claim,
+                &mut host,
+                Amount::zero(),
+                &mut logger,
+                &crypto_primitives(),
+            )
+        };
 
-        let mut ctx_wl_claim = TestReceiveContext::empty();
-        ctx_wl_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
-        let address_hashed = digest(account_0_string.clone());
+        // the nationality has to be revealed, and not be a blocked one
+        assert_eq!(claim(None), Err(Error::IdentityPolicyNotMet));
+        assert_eq!(claim(Some("KP")), Err(Error::IdentityPolicyNotMet));
+        assert!(claim(Some("DK")).is_ok());
+    }
 
-        let mut test_proof = vec![];
+    #[concordium_test]
+    fn test_multisig() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
 
-        test_proof.push(address_hashed.clone());
-        test_proof.push(digest(address_hashed.clone() + &address_hashed));
+        const OWNER: AccountAddress = AccountAddress([9u8; 32]);
+        const ADMIN_0: AccountAddress = AccountAddress([0u8; 32]);
+        const ADMIN_1: AccountAddress = AccountAddress([1u8; 32]);
+        const ADMIN_2: AccountAddress = AccountAddress([2u8; 32]);
+        const BENEFICIARY: AccountAddress = AccountAddress([8u8; 32]);
 
-        let mint_wl_params = ClaimNFTParams {
-            node: ACCOUNT_0,
-            node_string: account_0_string,
-            proof: test_proof.clone(),
-            selected_token: concordium_cis2::TokenIdU32(0),
-            amount_of_tokens: 1,
+        let mut params = InitParams {
+            nft_limit: 3,
+            mint_price: Amount::from_ccd(10),
+            admins: vec![ADMIN_0, ADMIN_1, ADMIN_2],
+            admin_threshold: 2,
+            ..default_init_params()
         };
 
-        let wl_claim_parameter_bytes = to_bytes(&mint_wl_params);
-        ctx_wl_claim.set_parameter(&wl_claim_parameter_bytes);
+        params.admin_threshold = 4;
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+        assert_eq!(
+            init(&ctx, &mut state_builder, Amount::zero()).err(),
+            Some(Error::InvalidAdminThreshold.into())
+        );
 
+        params.admin_threshold = 2;
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+        host.set_self_balance(Amount::from_ccd(20));
         let mut logger = TestLogger::init();
-        // this should check the whitelist and pass
-        claim_nft(&ctx_wl_claim, &mut host, &mut logger).unwrap();
 
-        // this should not check the whitelist and fail
-        let fail_claim = claim_nft(&ctx_claim, &mut host, &mut logger);
+        // The owner can no longer withdraw directly
+        let withdraw_params = WithdrawParams {
+            amount: Amount::from_ccd(5),
+            to: BENEFICIARY,
+        };
+        let withdraw_parameter_bytes = to_bytes(&withdraw_params);
+        let mut ctx_withdraw = TestReceiveContext::empty();
+        ctx_withdraw.set_owner(OWNER);
+        ctx_withdraw.set_sender(Address::Account(OWNER));
+        ctx_withdraw.set_parameter(&withdraw_parameter_bytes);
+        assert_eq!(
+            withdraw(&ctx_withdraw, &mut host, &mut logger),
+            Err(Error::MultisigRequired)
+        );
 
-        claim_eq!(
-            fail_claim,
-            Err(Error::AddressNotOnWhitelist),
-            "Function should fail with whitelist error"
+        let propose_bytes = to_bytes(&AdminAction::Withdraw(withdraw_params));
+        let mut ctx_propose = TestReceiveContext::empty();
+        ctx_propose.set_sender(Address::Account(OWNER));
+        ctx_propose.set_parameter(&propose_bytes);
+        assert_eq!(
+            propose_action(&ctx_propose, &mut host, &mut logger),
+            Err(Error::Unauthorized)
+        );
+        ctx_propose.set_sender(Address::Account(ADMIN_0));
+        propose_action(&ctx_propose, &mut host, &mut logger).unwrap();
+        assert!(!host.transfer_occurred(&BENEFICIARY, Amount::from_ccd(5)));
+
+        let confirm_bytes = to_bytes(&0u64);
+        let mut ctx_confirm = TestReceiveContext::empty();
+        ctx_confirm.set_sender(Address::Account(ADMIN_0));
+        ctx_confirm.set_parameter(&confirm_bytes);
+        assert_eq!(
+            confirm_action(&ctx_confirm, &mut host, &mut logger),
+            Err(Error::AlreadyApproved)
+        );
+        assert_eq!(
+            pending_action(&ctx_confirm, &host)
+                .unwrap()
+                .map(|pending| pending.approvals),
+            Some(vec![ADMIN_0])
+        );
+
+        ctx_confirm.set_sender(Address::Account(ADMIN_1));
+        confirm_action(&ctx_confirm, &mut host, &mut logger).unwrap();
+        assert!(host.transfer_occurred(&BENEFICIARY, Amount::from_ccd(5)));
+        claim!(
+            logger
+                .logs
+                .contains(&to_bytes(&AirdropEvent::ActionApproved(
+                    ActionApprovedEvent {
+                        action_id: 0,
+                        admin: ADMIN_1,
+                        approvals: 2,
+                        executed: true,
+                    }
+                ))),
+            "Expected an event for the approval running the action"
         );
+
+        // The action only runs once
+        ctx_confirm.set_sender(Address::Account(ADMIN_2));
+        assert_eq!(
+            confirm_action(&ctx_confirm, &mut host, &mut logger),
+            Err(Error::UnknownAction)
+        );
+
+        let whitelist_bytes = to_bytes(&AdminAction::SetWhitelist(SetWhitelistParams {
+            whitelist: vec![account_address_to_string(ADMIN_0)],
+            whitelist_file: "whitelist.json".to_string(),
+        }));
+        ctx_propose.set_sender(Address::Account(ADMIN_2));
+        ctx_propose.set_parameter(&whitelist_bytes);
+        propose_action(&ctx_propose, &mut host, &mut logger).unwrap();
+        assert!(host.state().merkle_tree.is_none());
+
+        let confirm_bytes = to_bytes(&1u64);
+        ctx_confirm.set_parameter(&confirm_bytes);
+        ctx_confirm.set_sender(Address::Account(ADMIN_1));
+        confirm_action(&ctx_confirm, &mut host, &mut logger).unwrap();
+        assert!(host.state().merkle_tree.is_some());
+        assert_eq!(host.state().whitelist, "whitelist.json");
     }
 
     #[concordium_test]
-    fn test_mint_too_late() {
+    fn test_reroll() {
         let mut ctx = TestInitContext::empty();
-        let mut state_builder = TestStateBuilder::new();
         ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
+
+        const ADMIN: AccountAddress = AccountAddress([9u8; 32]);
         const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
+        const ACCOUNT_1: AccountAddress = AccountAddress([1u8; 32]);
         let account_0_string = "00000000000000000000000000000000000000000000000000".to_string();
 
-        // This should allow anyone to purchase 1 NFT
         let params = InitParams {
-            nft_limit: 1,
-            nft_time_limit: 10,
-            whitelist: vec![],
-            reserve: 0,
-            whitelist_file: String::new(),
-            metadata: String::new(),
-            base_url: String::new(),
-            selected_index: false,
-            nft_limit_per_address: 0,
+            nft_limit: 3,
+            random_index: true,
+            random_salt_commitment: digest("secret salt".to_string()),
+            unlock_time: 2,
+            ..default_init_params()
         };
 
         let parameter_bytes = to_bytes(&params);
         ctx.set_parameter(&parameter_bytes);
 
-        let state_result = init(&ctx, &mut state_builder);
-        let new_state = state_result.unwrap();
-        assert_eq!(new_state.nft_limit, 1);
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+        let mut logger = TestLogger::init();
+
+        let mut ctx_salt = TestReceiveContext::empty();
+        ctx_salt.set_owner(ADMIN);
+        ctx_salt.set_sender(Address::Account(ADMIN));
+        let salt_parameter_bytes = to_bytes(&RandomSaltParams {
+            salt: "secret salt".to_string(),
+        });
+        ctx_salt.set_parameter(&salt_parameter_bytes);
+        reveal_random_salt(&ctx_salt, &mut host).unwrap();
 
         let mut ctx_claim = TestReceiveContext::empty();
         ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        ctx_claim.set_sender(Address::Account(ACCOUNT_0));
         let mint_params = ClaimNFTParams {
-            node: ACCOUNT_0,
+            node: Address::Account(ACCOUNT_0),
             node_string: account_0_string,
             proof: vec![],
-            selected_token: concordium_cis2::TokenIdU32(0),
+            selected_token: index_token_id(0),
             amount_of_tokens: 1,
+            tier: None,
+            round: None,
+            voucher: None,
+            max_claims: None,
         };
-
-        let mut host = TestHost::new(new_state, state_builder);
-
         let claim_parameter_bytes = to_bytes(&mint_params);
-        ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(11));
         ctx_claim.set_parameter(&claim_parameter_bytes);
+        claim_nft(
+            &ctx_claim,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        )
+        .unwrap();
+
+        let claimed = (0..3)
+            .map(index_token_id)
+            .find(|token| host.state().token_owners.get(token).is_some())
+            .unwrap();
+
+        let reroll_parameter_bytes = to_bytes(&TokenParam { token: claimed });
+        let mut ctx_reroll = TestReceiveContext::empty();
+        ctx_reroll.set_metadata_slot_time(Timestamp::from_timestamp_millis(2));
+        ctx_reroll.set_sender(Address::Account(ACCOUNT_1));
+        ctx_reroll.set_parameter(&reroll_parameter_bytes);
+        assert_eq!(
+            reroll(&ctx_reroll, &mut host, &mut logger),
+            Err(Error::Unauthorized)
+        );
 
-        let mut logger = TestLogger::init();
-        let claim_result = claim_nft(&ctx_claim, &mut host, &mut logger);
-        claim_eq!(
-            claim_result,
-            Err(Error::AirdropNowClosed),
-            "Function should fail with Airdrop closed error"
+        // the claimed token is locked until 2
+        ctx_reroll.set_sender(Address::Account(ACCOUNT_0));
+        ctx_reroll.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        assert_eq!(
+            reroll(&ctx_reroll, &mut host, &mut logger),
+            Err(Error::TokenLocked)
+        );
+
+        ctx_reroll.set_metadata_slot_time(Timestamp::from_timestamp_millis(2));
+        reroll(&ctx_reroll, &mut host, &mut logger).unwrap();
+
+        let state = host.state();
+        assert!(state.token_owners.get(&claimed).is_none());
+        let rerolled = (0..3)
+            .map(index_token_id)
+            .find(|token| state.token_owners.get(token).is_some())
+            .unwrap();
+        assert_ne!(rerolled, claimed);
+        assert_eq!(state.claimed_count(), 1);
+        assert_eq!(
+            state
+                .claimed_nfts
+                .get(&Address::Account(ACCOUNT_0))
+                .map(|tokens| *tokens),
+            Some(1)
+        );
+        claim!(
+            logger
+                .logs
+                .contains(&to_bytes(&AirdropEvent::Reroll(RerollEvent {
+                    burned: claimed,
+                    minted: rerolled,
+                    owner: Address::Account(ACCOUNT_0),
+                }))),
+            "Expected an event for the reroll"
+        );
+
+        // The burned token is not drawn again, so the last token can't be rerolled
+        let reroll_parameter_bytes = to_bytes(&TokenParam { token: rerolled });
+        ctx_reroll.set_parameter(&reroll_parameter_bytes);
+        reroll(&ctx_reroll, &mut host, &mut logger).unwrap();
+        assert_eq!(host.state().claimed_count(), 1);
+        let last = (0..3)
+            .map(index_token_id)
+            .find(|token| host.state().token_owners.get(token).is_some())
+            .unwrap();
+        let reroll_parameter_bytes = to_bytes(&TokenParam { token: last });
+        ctx_reroll.set_parameter(&reroll_parameter_bytes);
+        assert_eq!(
+            reroll(&ctx_reroll, &mut host, &mut logger),
+            Err(Error::NFTLimitReached)
         );
     }
 
     #[concordium_test]
-    fn test_mint_no_reserve_no_whitelist_selected_index() {
+    fn test_price_curve() {
         let mut ctx = TestInitContext::empty();
         ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
         let mut state_builder = TestStateBuilder::new();
 
-        let account_0_string = "00000000000000000000000000000000000000000000000000".to_string();
         const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
 
-        // This should allow anyone to purchase 2 NFTs
-        let params = InitParams {
-            nft_limit: 2,
-            nft_time_limit: 0,
-            whitelist: vec![],
-            reserve: 0,
-            base_url: "https://some.example/token/".to_string(),
-            whitelist_file: String::new(),
-            metadata: String::new(),
-            selected_index: true,
-            nft_limit_per_address: 0,
+        let mut params = InitParams {
+            nft_limit: 3,
+            mint_price: Amount::from_ccd(10),
+            price_curve: Some(PriceCurve::Step {
+                step: 0,
+                increment: Amount::from_ccd(1),
+            }),
+            ..default_init_params()
         };
 
         let parameter_bytes = to_bytes(&params);
         ctx.set_parameter(&parameter_bytes);
+        assert_eq!(
+            init(&ctx, &mut state_builder, Amount::zero()).err(),
+            Some(Error::InvalidPriceCurve.into())
+        );
+
+        params.price_curve = Some(PriceCurve::Linear {
+            increment: Amount::from_ccd(1),
+        });
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+        let mut logger = TestLogger::init();
 
-        let state_result = init(&ctx, &mut state_builder);
-        let new_state = state_result.unwrap();
-        assert_eq!(new_state.nft_limit, 2);
+        let mut ctx_view = TestReceiveContext::empty();
+        ctx_view.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        assert_eq!(current_price(&ctx_view, &host), Ok(Amount::from_ccd(10)));
 
         let mut ctx_claim = TestReceiveContext::empty();
         ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        ctx_claim.set_sender(Address::Account(ACCOUNT_0));
         let mint_params = ClaimNFTParams {
-            node: ACCOUNT_0,
-            node_string: account_0_string.clone(),
+            node: Address::Account(ACCOUNT_0),
+            node_string: account_address_to_string(ACCOUNT_0),
             proof: vec![],
-            selected_token: concordium_cis2::TokenIdU32(2),
-            amount_of_tokens: 1,
+            selected_token: index_token_id(0),
+            amount_of_tokens: 2,
+            tier: None,
+            round: None,
+            voucher: None,
+            max_claims: None,
         };
-
-        let mut host = TestHost::new(new_state, state_builder);
-
         let claim_parameter_bytes = to_bytes(&mint_params);
         ctx_claim.set_parameter(&claim_parameter_bytes);
-        let mut logger = TestLogger::init();
-
-        let claim_result = claim_nft(&ctx_claim, &mut host, &mut logger);
-        assert_eq!(claim_result.is_ok(), true);
 
-        claim!(
-            logger.logs.contains(&to_bytes(&Cis2Event::Mint(MintEvent {
-                owner: concordium_std::Address::Account(ACCOUNT_0),
-                token_id: ContractTokenId::from(2),
-                amount: ContractTokenAmount::from(1),
-            }))),
-            "Expected an event for minting token 2"
+        // The second token costs one CCD more than the first
+        assert_eq!(
+            claim_nft(
+                &ctx_claim,
+                &mut host,
+                Amount::from_ccd(20),
+                &mut logger,
+                &crypto_primitives()
+            ),
+            Err(Error::InsufficientFunds)
         );
-
-        claim!(
-            logger.logs.contains(&to_bytes(
-                &Cis2Event::TokenMetadata::<_, ContractTokenAmount>(TokenMetadataEvent {
-                    token_id: concordium_cis2::TokenIdU32(2),
-                    metadata_url: MetadataUrl {
-                        url: "https://some.example/token/02000000".to_string(),
-                        hash: None,
-                    },
-                })
-            )),
-            "Expected an event for token metadata for token 2"
+        claim_nft(
+            &ctx_claim,
+            &mut host,
+            Amount::from_ccd(21),
+            &mut logger,
+            &crypto_primitives(),
+        )
+        .unwrap();
+        assert_eq!(host.state().collected_funds, Amount::from_ccd(21));
+        assert_eq!(current_price(&ctx_view, &host), Ok(Amount::from_ccd(12)));
+
+        let dashboard_parameter_bytes = to_bytes(&Address::Account(ACCOUNT_0));
+        let mut ctx_dashboard = TestReceiveContext::empty();
+        ctx_dashboard.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        ctx_dashboard.set_parameter(&dashboard_parameter_bytes);
+        assert_eq!(
+            dashboard(&ctx_dashboard, &host),
+            Ok(DashboardReply {
+                remaining: 1,
+                time_remaining: None,
+                current_price: Some(Amount::from_ccd(12)),
+                whitelisted: None,
+                claimed: 2,
+                paused: false,
+            })
         );
+    }
 
-        // check that the token has the correct owner:
-        let mut owner_ctx = TestReceiveContext::empty();
-        let owner_params = concordium_cis2::TokenIdU32(2);
-        let owner_parameter_bytes = to_bytes(&owner_params);
-        owner_ctx.set_parameter(&owner_parameter_bytes);
-        let owner_result: Result<CheckOwnerReply, Reject> = check_owner(&owner_ctx, &mut host);
-        assert!(owner_result.is_ok());
-        let result_details = owner_result.unwrap();
-        assert!(result_details.address.is_some());
-        assert_eq!(result_details.address.unwrap(), account_0_string);
+    #[concordium_test]
+    fn test_claim_to() {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        let mut state_builder = TestStateBuilder::new();
 
-        // check that the wrong token has the no owner:
-        let mut non_owner_ctx = TestReceiveContext::empty();
-        let non_owner_params = concordium_cis2::TokenIdU32(5);
-        let non_owner_parameter_bytes = to_bytes(&non_owner_params);
-        non_owner_ctx.set_parameter(&non_owner_parameter_bytes);
-        let non_owner_result: Result<CheckOwnerReply, Reject> =
-            check_owner(&non_owner_ctx, &mut host);
-        assert!(non_owner_result.is_ok());
-        let non_result_details = non_owner_result.unwrap();
-        assert!(non_result_details.address.is_none());
+        const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
+        const SELF_ADDRESS: ContractAddress = ContractAddress {
+            index: 1,
+            subindex: 0,
+        };
+        const ESCROW: ContractAddress = ContractAddress {
+            index: 2,
+            subindex: 0,
+        };
 
-        // Check the right amount of tokens exist and have been claimed
-        assert_eq!(total_supply(&non_owner_ctx, &mut host).unwrap(), 2);
-        assert_eq!(current_supply(&non_owner_ctx, &mut host).unwrap(), 1);
+        let params = InitParams {
+            nft_limit: 2,
+            ..default_init_params()
+        };
+
+        let parameter_bytes = to_bytes(&params);
+        ctx.set_parameter(&parameter_bytes);
+
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+        let hook = OwnedEntrypointName::new_unchecked("onReceivingCIS2".to_string());
+        host.setup_mock_entrypoint(ESCROW, hook.clone(), MockFn::returning_ok(()));
+        let mut logger = TestLogger::init();
 
+        let claim_to_params = ClaimToParams {
+            claim: ClaimNFTParams {
+                node: Address::Account(ACCOUNT_0),
+                node_string: account_address_to_string(ACCOUNT_0),
+                proof: vec![],
+                selected_token: index_token_id(0),
+                amount_of_tokens: 2,
+                tier: None,
+                round: None,
+                voucher: None,
+                max_claims: None,
+            },
+            to: Receiver::Contract(ESCROW, hook),
+            data: AdditionalData::empty(),
+        };
+        let claim_to_parameter_bytes = to_bytes(&claim_to_params);
+        let mut ctx_claim = TestReceiveContext::empty();
+        ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        ctx_claim.set_sender(Address::Account(ACCOUNT_0));
+        ctx_claim.set_self_address(SELF_ADDRESS);
+        ctx_claim.set_parameter(&claim_to_parameter_bytes);
+        claim_to(
+            &ctx_claim,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        )
+        .unwrap();
+
+        // The tokens end up with the escrow but count as claimed by the claimer
+        for token in 0..2 {
+            assert_eq!(
+                *host
+                    .state()
+                    .token_owners
+                    .get(&index_token_id(token))
+                    .unwrap(),
+                Address::Contract(ESCROW)
+            );
+        }
         assert_eq!(
-            view(&ctx_claim, &host).unwrap(),
-            ViewResult {
-                metadata: String::new(),
-                whitelist: String::new(),
-                number_of_nfts: 2
-            }
+            host.state()
+                .claimed_nfts
+                .get(&Address::Account(ACCOUNT_0))
+                .map(|claimed| *claimed),
+            Some(2)
+        );
+        claim!(
+            logger
+                .logs
+                .contains(&to_bytes(&Cis2Event::Transfer(TransferEvent {
+                    token_id: index_token_id(1),
+                    amount: ContractTokenAmount::from(1),
+                    from: Address::Account(ACCOUNT_0),
+                    to: Address::Contract(ESCROW),
+                }))),
+            "Expected a transfer event to the escrow"
         );
-
-        let claim_result_bad: Result<(), Error> = claim_nft(&ctx_claim, &mut host, &mut logger);
-        assert_eq!(claim_result_bad, Err(Error::IndexAlreadyClaimed));
     }
 
     #[concordium_test]
-    fn test_mint_no_reserve_no_whitelist_address_limited() {
+    fn test_cis2_views() {
         let mut ctx = TestInitContext::empty();
         ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
         let mut state_builder = TestStateBuilder::new();
 
-        const ACCOUNT_0: AccountAddress = AccountAddress([1u8; 32]);
-        let account_0_string = "00000000000000000000000000000000000000000000000000".to_string();
+        const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
+        const ACCOUNT_1: AccountAddress = AccountAddress([1u8; 32]);
 
         let params = InitParams {
-            nft_limit: 3,
-            nft_time_limit: 0,
-            whitelist: vec![],
-            reserve: 0,
-            base_url: String::new(),
-            whitelist_file: String::new(),
-            metadata: String::new(),
-            selected_index: false,
-            nft_limit_per_address: 1,
+            nft_limit: 2,
+            ..default_init_params()
         };
-
         let parameter_bytes = to_bytes(&params);
         ctx.set_parameter(&parameter_bytes);
 
-        let state_result = init(&ctx, &mut state_builder);
-        let new_state = state_result.unwrap();
-        assert_eq!(new_state.nft_limit, 3);
+        let state = init(&ctx, &mut state_builder, Amount::zero()).unwrap();
+        let mut host = TestHost::new(state, state_builder);
+        let mut logger = TestLogger::init();
 
-        let mut ctx_claim = TestReceiveContext::empty();
-        ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
         let mint_params = ClaimNFTParams {
-            node: ACCOUNT_0,
-            node_string: account_0_string,
+            node: Address::Account(ACCOUNT_0),
+            node_string: account_address_to_string(ACCOUNT_0),
             proof: vec![],
-            selected_token: concordium_cis2::TokenIdU32(0),
-            amount_of_tokens: 2,
+            selected_token: index_token_id(0),
+            amount_of_tokens: 1,
+            tier: None,
+            round: None,
+            voucher: None,
+            max_claims: None,
         };
-
-        let mut host = TestHost::new(new_state, state_builder);
         let claim_parameter_bytes = to_bytes(&mint_params);
+        let mut ctx_claim = TestReceiveContext::empty();
+        ctx_claim.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
         ctx_claim.set_parameter(&claim_parameter_bytes);
-        let mut logger = TestLogger::init();
+        ctx_claim.set_sender(Address::Account(ACCOUNT_0));
+        claim_nft(
+            &ctx_claim,
+            &mut host,
+            Amount::zero(),
+            &mut logger,
+            &crypto_primitives(),
+        )
+        .unwrap();
+
+        let balance_parameter_bytes = to_bytes(&ContractBalanceOfQueryParams {
+            queries: vec![
+                BalanceOfQuery {
+                    token_id: index_token_id(0),
+                    address: Address::Account(ACCOUNT_0),
+                },
+                BalanceOfQuery {
+                    token_id: index_token_id(0),
+                    address: Address::Account(ACCOUNT_1),
+                },
+            ],
+        });
+        let mut ctx_view = TestReceiveContext::empty();
+        ctx_view.set_parameter(&balance_parameter_bytes);
+        assert_eq!(
+            cis2_balance_of(&ctx_view, &host).unwrap().0,
+            vec![ContractTokenAmount::from(1), ContractTokenAmount::from(0)]
+        );
 
-        let claim_result = claim_nft(&ctx_claim, &mut host, &mut logger);
-        assert_eq!(claim_result.is_ok(), false);
+        // tokens which were never minted are unknown
+        let unknown_parameter_bytes = to_bytes(&ContractBalanceOfQueryParams {
+            queries: vec![BalanceOfQuery {
+                token_id: index_token_id(1),
+                address: Address::Account(ACCOUNT_0),
+            }],
+        });
+        ctx_view.set_parameter(&unknown_parameter_bytes);
+        assert!(matches!(
+            cis2_balance_of(&ctx_view, &host),
+            Err(Error::UnknownToken)
+        ));
+
+        let operator_parameter_bytes = to_bytes(&UpdateOperatorParams(vec![UpdateOperator {
+            update: OperatorUpdate::Add,
+            operator: Address::Account(ACCOUNT_1),
+        }]));
+        let mut ctx_operator = TestReceiveContext::empty();
+        ctx_operator.set_sender(Address::Account(ACCOUNT_0));
+        ctx_operator.set_parameter(&operator_parameter_bytes);
+        update_operator(&ctx_operator, &mut host, &mut logger).unwrap();
+
+        let operator_of_parameter_bytes = to_bytes(&OperatorOfQueryParams {
+            queries: vec![
+                OperatorOfQuery {
+                    owner: Address::Account(ACCOUNT_0),
+                    address: Address::Account(ACCOUNT_1),
+                },
+                OperatorOfQuery {
+                    owner: Address::Account(ACCOUNT_1),
+                    address: Address::Account(ACCOUNT_0),
+                },
+            ],
+        });
+        ctx_view.set_parameter(&operator_of_parameter_bytes);
+        assert_eq!(operator_of(&ctx_view, &host).unwrap().0, vec![true, false]);
+
+        let supports_parameter_bytes = to_bytes(&SupportsQueryParams {
+            queries: vec![
+                StandardIdentifierOwned::new_unchecked("CIS-0".to_string()),
+                StandardIdentifierOwned::new_unchecked("CIS-2".to_string()),
+                StandardIdentifierOwned::new_unchecked("CIS-3".to_string()),
+                StandardIdentifierOwned::new_unchecked("CIS-1".to_string()),
+            ],
+        });
+        ctx_view.set_parameter(&supports_parameter_bytes);
+        assert_eq!(
+            to_bytes(&supports(&ctx_view, &host).unwrap()),
+            to_bytes(&SupportsQueryResponse::from(vec![
+                SupportResult::Support,
+                SupportResult::Support,
+                SupportResult::Support,
+                SupportResult::NoSupport,
+            ]))
+        );
     }
 }